@@ -0,0 +1,97 @@
+/*
+* Parse function benchmark
+*
+* Times every parse function over a corpus of SAS files, loaded once up front so only the
+* parsing is measured. Pass the corpus directory after --, otherwise a synthetic corpus of
+* generated programs is written to a temporary directory and used:
+*
+*     cargo bench --bench parse -- /path/to/sas_code
+*/
+
+use indicatif::ProgressBar;
+use sas_parser::parsers::PARSE_FUNCTIONS;
+use sas_parser::scanner::{self, ScanOptions};
+use sas_parser::FileContent;
+use std::cmp::Reverse;
+use std::env;
+use std::fs;
+use std::io;
+use std::time::{Duration, Instant};
+
+const SYNTHETIC_FILES: usize = 200;
+const SYNTHETIC_REPEATS: usize = 40;
+
+const SAMPLE_PROGRAM: &str = r#"%let run_dt = 2023-06-01;
+%let outdir = /sasdata/prod/out;
+libname src oracle user=etl password="{SAS002}ABCD" path=prod schema=sales;
+%include '/sasdata/prod/macros/common.sas';
+
+%macro load_orders(table);
+  data stage.&table.;
+    set src.&table. (keep=id amount order_dt);
+    where order_dt >= "&run_dt"d;
+    call symputx('rows', _n_);
+  run;
+%mend load_orders;
+
+%load_orders(orders);
+
+proc sql;
+  connect to oracle as ora (user=etl password=secret path=prod);
+  create table stage.summary as
+  select a.id, sum(b.amount) as amt
+  from stage.orders a
+  left join src.customers b on a.id = b.id
+  group by a.id;
+  execute (truncate table sales.tmp) by ora;
+  disconnect from ora;
+quit;
+
+proc export data=stage.summary outfile="&outdir./summary.csv" dbms=csv replace;
+run;
+"#;
+
+fn main() -> io::Result<()> {
+    let dir = match env::args().skip(1).find(|arg| !arg.starts_with("--")) {
+        Some(dir) => dir,
+        None => synthetic_corpus()?,
+    };
+
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    scanner::process_dir(&dir, &mut files, &ProgressBar::hidden(), &ScanOptions::default(), &mut errors)?;
+    let options = ScanOptions::default();
+    let contents: Vec<FileContent> = files.iter().filter_map(|file| scanner::load_file(&file.path(), &options).ok()).collect();
+    let bytes: usize = contents.iter().map(|content| content.raw.len()).sum();
+    println!("corpus: {} ({} files, {} KB)", dir, contents.len(), bytes / 1024);
+
+    let mut timings: Vec<(&str, Duration, usize)> = Vec::new();
+    for (name, _, function) in PARSE_FUNCTIONS {
+        let start = Instant::now();
+        let mut findings = 0;
+        for content in &contents {
+            findings += function("bench", content).len();
+        }
+        timings.push((name, start.elapsed(), findings));
+    }
+    timings.sort_by_key(|(_, elapsed, _)| Reverse(*elapsed));
+
+    println!("{:<20} {:>12} {:>10}", "function", "time", "findings");
+    for (name, elapsed, findings) in &timings {
+        println!("{:<20} {:>12.2?} {:>10}", name, elapsed, findings);
+    }
+    let total: Duration = timings.iter().map(|(_, elapsed, _)| *elapsed).sum();
+    println!("{:<20} {:>12.2?}", "total", total);
+    Ok(())
+}
+
+// synthetic_corpus writes SYNTHETIC_FILES programs, each the sample program repeated, and returns the directory
+fn synthetic_corpus() -> io::Result<String> {
+    let dir = env::temp_dir().join("sas_parser_bench");
+    fs::create_dir_all(&dir)?;
+    let program = SAMPLE_PROGRAM.repeat(SYNTHETIC_REPEATS);
+    for i in 0..SYNTHETIC_FILES {
+        fs::write(dir.join(format!("program_{}.sas", i)), &program)?;
+    }
+    Ok(dir.to_string_lossy().to_string())
+}
@@ -0,0 +1,257 @@
+use crate::parsers::duplicates::Shingle;
+use crate::parsers::engines::EngineUse;
+use crate::parsers::includes::Include;
+use crate::parsers::lineage::LineageEdge;
+use crate::parsers::macros::{MacroCall, MacroDef};
+use crate::parsers::migration::MigrationMetrics;
+use crate::parsers::Finding;
+use crate::scanner::{FileInfo, FileResults};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/* -------------------------
+* Incremental scan cache. The results of each parsed file are saved, keyed by the file path,
+* size and modification time; on the next run a file whose key is unchanged is not read or
+* parsed again, its saved results are used instead. Only the files seen in a run are saved, so
+* deleted files drop out of the cache.
+* The cache also records the run settings (parse functions, comment handling, corpus passes);
+* a cache written with different settings is ignored and every file is parsed again.
+*
+* The file is plain text, one record per line with tab separated fields
+* (tabs, newlines and backslashes in values are escaped):
+*   sas_parser_cache  2
+*   settings  <run settings>
+*   file      <path> <size> <modified>
+*   finding   <function> <result> <line start> <line end> <value> <extra name> <extra value> ...
+*   table     <table index> <columns...>
+*   include   <line> <target> <is_fileref>
+*   macro     <name> <params> <start line> <end line>
+*   call      <line> <caller> <callee>
+*   lineage   <line> <kind> <input> <output>
+*   shingle   <line> <end line> <hash, hex>
+*   engine    <line> <statement> <engine>
+*   migration <sql blocks> <macros> <retain/lag> <shell calls> <pass-through> <includes>
+* Every record after a file record belongs to that file. Macro bodies are not saved.
+* --------------------------- */
+
+const CACHE_VERSION: &str = "2";
+
+struct CacheEntry {
+    size: u64,
+    modified: i64,
+    results: FileResults,
+}
+
+pub struct Cache {
+    path: String,
+    settings: String,
+    entries: HashMap<String, CacheEntry>,
+    // the entries to save: every file seen in this run
+    seen: HashMap<String, CacheEntry>,
+    pub hits: usize,
+}
+
+impl Cache {
+    // load reads the cache file; a missing file, or one written with other settings, gives an empty cache
+    pub fn load(path: &str, settings: &str) -> io::Result<Cache> {
+        let mut cache = Cache {
+            path: path.to_string(),
+            settings: settings.to_string(),
+            entries: HashMap::new(),
+            seen: HashMap::new(),
+            hits: 0,
+        };
+        if !Path::new(path).exists() {
+            return Ok(cache);
+        }
+        let text = fs::read_to_string(path)?;
+        let mut lines = text.lines().map(|line| line.split('\t').map(unescape).collect::<Vec<String>>());
+        if lines.next() != Some(vec![String::from("sas_parser_cache"), CACHE_VERSION.to_string()]) {
+            return Ok(cache);
+        }
+        if lines.next() != Some(vec![String::from("settings"), settings.to_string()]) {
+            return Ok(cache);
+        }
+
+        let mut current: Option<(String, CacheEntry)> = None;
+        for fields in lines {
+            let field = |i: usize| fields.get(i).cloned().unwrap_or_default();
+            let number = |i: usize| field(i).parse::<usize>().unwrap_or(0);
+            if fields[0] == "file" {
+                if let Some((file_path, entry)) = current.take() {
+                    cache.entries.insert(file_path, entry);
+                }
+                let entry = CacheEntry {
+                    size: field(2).parse().unwrap_or(0),
+                    modified: field(3).parse().unwrap_or(0),
+                    results: FileResults::default(),
+                };
+                current = Some((field(1), entry));
+                continue;
+            }
+            let Some((_, entry)) = current.as_mut() else {
+                continue;
+            };
+            let results = &mut entry.results;
+            match fields[0].as_str() {
+                "finding" => {
+                    let mut finding = Finding::new("", &field(1), field(2));
+                    finding.line_start = field(3).parse().ok();
+                    finding.line_end = field(4).parse().ok();
+                    finding.value = field(5);
+                    finding.extra = fields.get(6..).unwrap_or_default().chunks(2).map(|pair| (pair[0].clone(), pair.get(1).cloned().unwrap_or_default())).collect();
+                    results.findings.push(finding);
+                }
+                "table" => {
+                    let index = number(1);
+                    if results.table_rows.len() <= index {
+                        results.table_rows.resize(index + 1, Vec::new());
+                    }
+                    results.table_rows[index].push(fields[2..].to_vec());
+                }
+                "include" => results.includes.push(Include { line: number(1), target: field(2), is_fileref: field(3) == "1" }),
+                "macro" => results.macro_definitions.push(MacroDef {
+                    name: field(1),
+                    params: field(2),
+                    start_line: number(3),
+                    end_line: number(4),
+                    body: String::new(),
+                }),
+                "call" => results.macro_calls.push(MacroCall { line: number(1), caller: field(2), callee: field(3) }),
+                "lineage" => results.lineage.push(LineageEdge { line: number(1), kind: field(2), input: field(3), output: field(4) }),
+                "shingle" => results.shingles.push(Shingle {
+                    line: number(1),
+                    end_line: number(2),
+                    hash: u64::from_str_radix(&field(3), 16).unwrap_or(0),
+                }),
+                "engine" => results.engines.push(EngineUse { line: number(1), statement: field(2), engine: field(3) }),
+                "migration" => {
+                    let mut metrics = MigrationMetrics::default();
+                    for (i, count) in metrics.counts.iter_mut().enumerate() {
+                        *count = number(i + 1);
+                    }
+                    results.migration = metrics;
+                }
+                _ => {}
+            }
+        }
+        if let Some((file_path, entry)) = current {
+            cache.entries.insert(file_path, entry);
+        }
+        Ok(cache)
+    }
+
+    // get returns the saved results of a file if it has not changed since they were saved,
+    // with the findings moved over to the file's uuid in this run
+    pub fn get(&mut self, file_info: &FileInfo, table_count: usize) -> Option<FileResults> {
+        let path = file_info.path();
+        let entry = self.entries.remove(&path)?;
+        if entry.size != file_info.size || entry.modified != file_info.modify_date.timestamp() {
+            return None;
+        }
+        let mut results = entry.results.clone();
+        for finding in &mut results.findings {
+            finding.file_id = file_info.uuid.clone();
+        }
+        results.table_rows.resize(table_count, Vec::new());
+        self.hits += 1;
+        self.seen.insert(path, entry);
+        Some(results)
+    }
+
+    pub fn insert(&mut self, file_info: &FileInfo, results: &FileResults) {
+        let entry = CacheEntry {
+            size: file_info.size,
+            modified: file_info.modify_date.timestamp(),
+            results: results.clone(),
+        };
+        self.seen.insert(file_info.path(), entry);
+    }
+
+    // save writes the entries of every file seen in this run
+    pub fn save(&self) -> io::Result<()> {
+        let mut out = BufWriter::new(fs::File::create(&self.path)?);
+        write_record(&mut out, &["sas_parser_cache", CACHE_VERSION])?;
+        write_record(&mut out, &["settings", &self.settings])?;
+        let mut paths: Vec<&String> = self.seen.keys().collect();
+        paths.sort();
+        for path in paths {
+            let entry = &self.seen[path];
+            let results = &entry.results;
+            write_record(&mut out, &["file", path, &entry.size.to_string(), &entry.modified.to_string()])?;
+            for finding in &results.findings {
+                let line = |line: Option<usize>| line.map_or(String::new(), |line| line.to_string());
+                let (line_start, line_end) = (line(finding.line_start), line(finding.line_end));
+                let mut record = vec!["finding", &finding.func_nm, &finding.result, &line_start, &line_end, &finding.value];
+                for (name, value) in &finding.extra {
+                    record.extend([name.as_str(), value.as_str()]);
+                }
+                write_record(&mut out, &record)?;
+            }
+            for (index, rows) in results.table_rows.iter().enumerate() {
+                for row in rows {
+                    let mut record = vec!["table".to_string(), index.to_string()];
+                    record.extend(row.iter().cloned());
+                    let values: Vec<&str> = record.iter().map(|value| value.as_str()).collect();
+                    write_record(&mut out, &values)?;
+                }
+            }
+            for include in &results.includes {
+                let is_fileref = if include.is_fileref { "1" } else { "0" };
+                write_record(&mut out, &["include", &include.line.to_string(), &include.target, is_fileref])?;
+            }
+            for m in &results.macro_definitions {
+                write_record(&mut out, &["macro", &m.name, &m.params, &m.start_line.to_string(), &m.end_line.to_string()])?;
+            }
+            for call in &results.macro_calls {
+                write_record(&mut out, &["call", &call.line.to_string(), &call.caller, &call.callee])?;
+            }
+            for edge in &results.lineage {
+                write_record(&mut out, &["lineage", &edge.line.to_string(), &edge.kind, &edge.input, &edge.output])?;
+            }
+            for shingle in &results.shingles {
+                write_record(&mut out, &["shingle", &shingle.line.to_string(), &shingle.end_line.to_string(), &format!("{:016x}", shingle.hash)])?;
+            }
+            for engine in &results.engines {
+                write_record(&mut out, &["engine", &engine.line.to_string(), &engine.statement, &engine.engine])?;
+            }
+            if results.migration != MigrationMetrics::default() {
+                let mut record = vec![String::from("migration")];
+                record.extend(results.migration.counts.iter().map(|count| count.to_string()));
+                let values: Vec<&str> = record.iter().map(|value| value.as_str()).collect();
+                write_record(&mut out, &values)?;
+            }
+        }
+        out.flush()
+    }
+}
+
+fn write_record(out: &mut impl Write, values: &[&str]) -> io::Result<()> {
+    let fields: Vec<String> = values.iter().map(|value| escape(value)).collect();
+    writeln!(out, "{}", fields.join("\t"))
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
@@ -0,0 +1,236 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/* -------------------------
+* Configuration files: a scan can be described in a TOML or YAML file instead of a long
+* command line. Keys are the long command line option names (input, output, format,
+* functions, ...), with either - or _ as the word separator. Values given on the command
+* line take precedence over the config file.
+*
+* Only the flat subset of each format that the options need is supported:
+* TOML:                                  YAML:
+*     input = "sas_code"                     input: sas_code
+*     format = "jsonl"                       format: jsonl
+*     functions = ["get_sql", "get_password"]    functions: [get_sql, get_password]
+*                                            functions:
+*                                              - get_sql
+* --------------------------- */
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValue {
+    String(String),
+    Integer(i64),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+#[derive(Debug, Default)]
+pub struct Config {
+    values: HashMap<String, ConfigValue>,
+    used: RefCell<HashSet<String>>,
+}
+
+impl Config {
+    // load reads a config file, choosing the syntax from its extension (.yaml/.yml, otherwise TOML)
+    pub fn load(path: &str) -> io::Result<Config> {
+        let text = fs::read_to_string(path)?;
+        let extension = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+        let parsed = match extension.as_str() {
+            "yaml" | "yml" => Config::parse_yaml(&text),
+            _ => Config::parse_toml(&text),
+        };
+        parsed.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, e)))
+    }
+
+    pub fn parse_toml(text: &str) -> Result<Config, String> {
+        let mut config = Config::default();
+        let mut lines = text.lines().enumerate();
+        while let Some((line_number, line)) = lines.next() {
+            let line = strip_comment(line, '#').trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                return Err(format!("line {}: tables are not supported, put options at the top level", line_number + 1));
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected key = value", line_number + 1))?;
+            let mut value = value.trim().to_string();
+            // arrays may continue over several lines until the closing bracket
+            while value.starts_with('[') && !value.ends_with(']') {
+                match lines.next() {
+                    Some((_, next)) => value.push_str(strip_comment(next, '#').trim()),
+                    None => return Err(format!("line {}: unterminated array", line_number + 1)),
+                }
+            }
+            let value = parse_value(&value).map_err(|e| format!("line {}: {}", line_number + 1, e))?;
+            config.insert(key, value);
+        }
+        Ok(config)
+    }
+
+    pub fn parse_yaml(text: &str) -> Result<Config, String> {
+        let mut config = Config::default();
+        let mut list_key: Option<String> = None;
+        for (line_number, line) in text.lines().enumerate() {
+            let stripped = strip_comment(line, '#');
+            let trimmed = stripped.trim();
+            if trimmed.is_empty() || trimmed == "---" {
+                continue;
+            }
+            if let Some(item) = trimmed.strip_prefix("- ").or(if trimmed == "-" { Some("") } else { None }) {
+                let key = list_key
+                    .as_ref()
+                    .ok_or_else(|| format!("line {}: list item without a key", line_number + 1))?;
+                if let Some(ConfigValue::List(items)) = config.values.get_mut(key) {
+                    items.push(unquote(item.trim()));
+                }
+                continue;
+            }
+            if stripped.starts_with(char::is_whitespace) {
+                return Err(format!("line {}: nested mappings are not supported", line_number + 1));
+            }
+            let (key, value) = trimmed
+                .split_once(':')
+                .ok_or_else(|| format!("line {}: expected key: value", line_number + 1))?;
+            let value = value.trim();
+            if value.is_empty() {
+                // a key with no value starts a block list of "- item" lines
+                list_key = Some(normalize_key(key));
+                config.insert(key, ConfigValue::List(Vec::new()));
+            } else {
+                list_key = None;
+                let value = parse_value(value).map_err(|e| format!("line {}: {}", line_number + 1, e))?;
+                config.insert(key, value);
+            }
+        }
+        Ok(config)
+    }
+
+    fn insert(&mut self, key: &str, value: ConfigValue) {
+        self.values.insert(normalize_key(key), value);
+    }
+
+    // get returns the raw value of an option
+    pub fn get(&self, key: &str) -> Option<&ConfigValue> {
+        let key = normalize_key(key);
+        let value = self.values.get(&key);
+        if value.is_some() {
+            self.used.borrow_mut().insert(key);
+        }
+        value
+    }
+
+    // get_str returns an option as a string; lists are joined with commas, like the command line form
+    pub fn get_str(&self, key: &str) -> Option<String> {
+        self.get(key).map(|value| match value {
+            ConfigValue::String(s) => s.clone(),
+            ConfigValue::Integer(i) => i.to_string(),
+            ConfigValue::Bool(b) => b.to_string(),
+            ConfigValue::List(items) => items.join(","),
+        })
+    }
+
+    // get_list returns an option as a list of strings; a single string is a list of one
+    pub fn get_list(&self, key: &str) -> Option<Vec<String>> {
+        self.get(key).map(|value| match value {
+            ConfigValue::List(items) => items.clone(),
+            ConfigValue::String(s) => vec![s.clone()],
+            ConfigValue::Integer(i) => vec![i.to_string()],
+            ConfigValue::Bool(b) => vec![b.to_string()],
+        })
+    }
+
+    // get_flag returns true when a boolean option is set to true
+    pub fn get_flag(&self, key: &str) -> bool {
+        matches!(self.get(key), Some(ConfigValue::Bool(true)))
+    }
+
+    // unused_keys lists the keys that were never read, which usually means a typo in the file
+    pub fn unused_keys(&self) -> Vec<String> {
+        let used = self.used.borrow();
+        let mut unused: Vec<String> = self.values.keys().filter(|key| !used.contains(*key)).cloned().collect();
+        unused.sort();
+        unused
+    }
+}
+
+fn normalize_key(key: &str) -> String {
+    key.trim().replace('_', "-").to_lowercase()
+}
+
+// strip_comment removes a trailing comment that is not inside a quoted string
+fn strip_comment(line: &str, marker: char) -> &str {
+    let mut quote: Option<char> = None;
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == marker => return &line[..i],
+            None => {}
+        }
+    }
+    line
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    if value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"')) || (value.starts_with('\'') && value.ends_with('\'')))
+    {
+        let inner = &value[1..value.len() - 1];
+        if value.starts_with('"') {
+            return inner.replace("\\\\", "\\").replace("\\\"", "\"");
+        }
+        return inner.to_string();
+    }
+    value.to_string()
+}
+
+fn parse_value(value: &str) -> Result<ConfigValue, String> {
+    let value = value.trim();
+    if value.starts_with('[') {
+        let inner = value
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+            .ok_or_else(|| format!("unterminated array: {}", value))?;
+        let items: Vec<String> = split_list(inner).iter().map(|item| unquote(item)).filter(|item| !item.is_empty()).collect();
+        return Ok(ConfigValue::List(items));
+    }
+    if value == "true" {
+        return Ok(ConfigValue::Bool(true));
+    }
+    if value == "false" {
+        return Ok(ConfigValue::Bool(false));
+    }
+    if let Ok(i) = value.parse::<i64>() {
+        return Ok(ConfigValue::Integer(i));
+    }
+    Ok(ConfigValue::String(unquote(value)))
+}
+
+// split_list splits array items on commas that are outside quotes
+fn split_list(inner: &str) -> Vec<&str> {
+    let mut items: Vec<&str> = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == ',' => {
+                items.push(&inner[start..i]);
+                start = i + 1;
+            }
+            None => {}
+        }
+    }
+    items.push(&inner[start..]);
+    items
+}
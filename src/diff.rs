@@ -0,0 +1,254 @@
+use crate::parsers::Parser;
+use crate::scanner::ids::stable_path;
+use crate::scanner::{self, ScanOptions};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/* -------------------------
+* Run diffs (sas_parser_rust diff OLD NEW): compares the findings of two runs file by file, to
+* track remediation over time (passwords removed, SQL counts going down, ...). OLD and NEW are
+* each either an output directory of an earlier csv run (its latest run is used, or the run of a
+* summary_<timestamp>.csv given as the path), or an input directory, which is scanned first.
+* Files are matched on their path relative to the run's input directory (read from the run's
+* manifest), since uuids differ between runs. For each file and parse function:
+* - a function with one finding in both runs whose result differs is "changed" (sql_count 12 -> 9)
+* - otherwise each finding only in NEW is "new" and each finding only in OLD is "removed"
+* Findings of a file only scanned in one of the runs are all new or removed.
+* --------------------------- */
+
+pub const DIFF_HEADERS: [&str; 5] = ["file", "func_nm", "status", "old_result", "new_result"];
+
+// DiffRow is one new, removed or changed finding
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffRow {
+    pub file: String,
+    pub func_nm: String,
+    pub status: &'static str,
+    pub old_result: String,
+    pub new_result: String,
+}
+
+impl DiffRow {
+    pub fn record(&self) -> Vec<String> {
+        vec![self.file.clone(), self.func_nm.clone(), self.status.to_string(), self.old_result.clone(), self.new_result.clone()]
+    }
+}
+
+// RunFindings are the findings of one run, (func_nm, result) in the order found, by relative file path
+#[derive(Debug, Default)]
+pub struct RunFindings {
+    pub files: BTreeMap<String, Vec<(String, String)>>,
+}
+
+// OutputSet is the summary and detail files of one earlier run, and its manifest if it has one
+struct OutputSet {
+    summary: PathBuf,
+    detail: PathBuf,
+    manifest: Option<PathBuf>,
+    // an --append run: the files hold several runs, told apart by run_id
+    appended: bool,
+}
+
+// timestamp_of returns the timestamp of an output file named <name>_<timestamp>.<extension>
+fn timestamp_of<'a>(file_name: &'a str, name: &str) -> Option<&'a str> {
+    let timestamp = file_name.strip_prefix(name)?.strip_prefix('_')?.strip_suffix(".csv")?;
+    (timestamp.len() == 14 && timestamp.chars().all(|c| c.is_ascii_digit())).then_some(timestamp)
+}
+
+// output_set finds the run an output path stands for: the run of a summary_<timestamp>.csv, or
+// the latest run in an output directory
+fn output_set(path: &Path) -> Option<OutputSet> {
+    let set = |dir: &Path, timestamp: &str| OutputSet {
+        summary: dir.join(format!("summary_{}.csv", timestamp)),
+        detail: dir.join(format!("detail_{}.csv", timestamp)),
+        manifest: Some(dir.join(format!("manifest_{}.json", timestamp))).filter(|manifest| manifest.exists()),
+        appended: false,
+    };
+    if path.is_file() {
+        let timestamp = timestamp_of(&path.file_name()?.to_string_lossy(), "summary")?.to_string();
+        return Some(set(path.parent()?, &timestamp));
+    }
+    let names: Vec<String> = fs::read_dir(path).ok()?.flatten().map(|entry| entry.file_name().to_string_lossy().to_string()).collect();
+    let latest = names
+        .iter()
+        .filter_map(|name| timestamp_of(name, "summary"))
+        .filter(|timestamp| names.contains(&format!("detail_{}.csv", timestamp)))
+        .max();
+    if let Some(timestamp) = latest {
+        return Some(set(path, timestamp));
+    }
+    if names.iter().any(|name| name == "summary.csv") && names.iter().any(|name| name == "detail.csv") {
+        let manifest = names.iter().filter(|name| name.starts_with("manifest_")).max().map(|name| path.join(name));
+        return Some(OutputSet { summary: path.join("summary.csv"), detail: path.join("detail.csv"), manifest, appended: true });
+    }
+    None
+}
+
+// is_output tests whether a path is the output of an earlier run rather than an input directory
+pub fn is_output(path: &str) -> bool {
+    output_set(Path::new(path)).is_some()
+}
+
+// manifest_input reads the input directory recorded in a run manifest
+fn manifest_input(manifest: &Path) -> Option<String> {
+    let json = fs::read_to_string(manifest).ok()?;
+    let rest = &json[json.find("\"input\":")? + 8..];
+    let mut chars = rest.trim_start().strip_prefix('"')?.chars();
+    let mut input = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(input),
+            '\\' => match chars.next()? {
+                'n' => input.push('\n'),
+                't' => input.push('\t'),
+                'r' => input.push('\r'),
+                'u' => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    input.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                escaped => input.push(escaped),
+            },
+            c => input.push(c),
+        }
+    }
+    None
+}
+
+// column returns the index of a column in a csv header row
+fn column(headers: &csv::StringRecord, name: &str) -> io::Result<usize> {
+    headers
+        .iter()
+        .position(|header| header == name)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("no {} column", name)))
+}
+
+impl RunFindings {
+    // load reads the findings of an earlier csv run from its output path
+    pub fn load(path: &str) -> io::Result<RunFindings> {
+        let set = output_set(Path::new(path))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no csv run output (summary and detail files) in {}", path)))?;
+        let root = set.manifest.as_deref().and_then(manifest_input).unwrap_or_default();
+
+        let mut summary = csv::Reader::from_path(&set.summary)?;
+        let headers = summary.headers()?.clone();
+        let (uuid, name, directory) = (column(&headers, "uuid")?, column(&headers, "file_nm")?, column(&headers, "file_dir")?);
+        let run_id = column(&headers, "run_id").ok();
+        let rows: Vec<csv::StringRecord> = summary.records().collect::<Result<_, _>>()?;
+        // an appended summary holds every run: only the last one is compared
+        let last_run = match (set.appended, run_id) {
+            (true, Some(run_id)) => rows.last().map(|row| row[run_id].to_string()),
+            _ => None,
+        };
+        let in_run = |row: &csv::StringRecord, run_id: Option<usize>| last_run.as_ref().is_none_or(|last| run_id.is_some_and(|i| &row[i] == last));
+        let mut files: HashMap<String, String> = HashMap::new();
+        for row in rows.iter().filter(|row| in_run(row, run_id)) {
+            let file = stable_path(&root, &format!("{}/{}", &row[directory], &row[name]));
+            files.insert(row[uuid].to_string(), file);
+        }
+
+        let mut findings = RunFindings::default();
+        for file in files.values() {
+            findings.files.entry(file.clone()).or_default();
+        }
+        let mut detail = csv::Reader::from_path(&set.detail)?;
+        let headers = detail.headers()?.clone();
+        let (uuid, func_nm, result) = (column(&headers, "uuid")?, column(&headers, "func_nm")?, column(&headers, "result")?);
+        let run_id = column(&headers, "run_id").ok();
+        for row in detail.records() {
+            let row = row?;
+            if !in_run(&row, run_id) {
+                continue;
+            }
+            if let Some(file) = files.get(&row[uuid]) {
+                findings.files.entry(file.clone()).or_default().push((row[func_nm].to_string(), row[result].to_string()));
+            }
+        }
+        Ok(findings)
+    }
+
+    // scan runs the parse functions against an input directory
+    pub fn scan(dir: &str, parse_functions: &[Box<dyn Parser>], options: &ScanOptions) -> io::Result<RunFindings> {
+        let result = scanner::scan_dir_with(dir, parse_functions, options)?;
+        let mut files: HashMap<&str, String> = HashMap::new();
+        let mut findings = RunFindings::default();
+        for file in &result.files {
+            let path = stable_path(dir, &file.path());
+            findings.files.entry(path.clone()).or_default();
+            files.insert(&file.uuid, path);
+        }
+        for finding in result.findings {
+            if let Some(file) = files.get(finding.file_id.as_str()) {
+                findings.files.entry(file.clone()).or_default().push((finding.func_nm, finding.result));
+            }
+        }
+        Ok(findings)
+    }
+}
+
+// by_function groups the results of a file's findings by parse function, keeping their order
+fn by_function(findings: &[(String, String)]) -> Vec<(&str, Vec<&str>)> {
+    let mut grouped: Vec<(&str, Vec<&str>)> = Vec::new();
+    for (func_nm, result) in findings {
+        match grouped.iter_mut().find(|(name, _)| name == func_nm) {
+            Some((_, results)) => results.push(result),
+            None => grouped.push((func_nm, vec![result])),
+        }
+    }
+    grouped
+}
+
+// results_of returns the results of one parse function from by_function
+fn results_of<'a>(grouped: &[(&str, Vec<&'a str>)], name: &str) -> Vec<&'a str> {
+    grouped.iter().find(|(function, _)| *function == name).map(|(_, results)| results.clone()).unwrap_or_default()
+}
+
+// diff compares two runs; with functions given, only the findings of those parse functions
+pub fn diff(old: &RunFindings, new: &RunFindings, functions: &[String]) -> Vec<DiffRow> {
+    let empty: Vec<(String, String)> = Vec::new();
+    let mut paths: Vec<&String> = old.files.keys().chain(new.files.keys()).collect();
+    paths.sort();
+    paths.dedup();
+    let mut rows: Vec<DiffRow> = Vec::new();
+    for path in paths {
+        let old_functions = by_function(old.files.get(path).unwrap_or(&empty));
+        let new_functions = by_function(new.files.get(path).unwrap_or(&empty));
+        let mut names: Vec<&str> = Vec::new();
+        for (name, _) in old_functions.iter().chain(new_functions.iter()) {
+            if !names.contains(name) && (functions.is_empty() || functions.iter().any(|function| function == name)) {
+                names.push(name);
+            }
+        }
+        for name in names {
+            let (old_results, new_results) = (results_of(&old_functions, name), results_of(&new_functions, name));
+            let row = |status: &'static str, old_result: &str, new_result: &str| DiffRow {
+                file: path.clone(),
+                func_nm: name.to_string(),
+                status,
+                old_result: old_result.to_string(),
+                new_result: new_result.to_string(),
+            };
+            if let ([old_result], [new_result]) = (&old_results[..], &new_results[..]) {
+                if old_result != new_result {
+                    rows.push(row("changed", old_result, new_result));
+                }
+                continue;
+            }
+            // the findings of both runs are matched up one for one, as the same result can repeat
+            let mut unmatched: Vec<&str> = new_results.clone();
+            for old_result in &old_results {
+                match unmatched.iter().position(|new_result| new_result == old_result) {
+                    Some(i) => {
+                        unmatched.remove(i);
+                    }
+                    None => rows.push(row("removed", old_result, "")),
+                }
+            }
+            for new_result in unmatched {
+                rows.push(row("new", "", new_result));
+            }
+        }
+    }
+    rows
+}
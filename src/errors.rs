@@ -0,0 +1,89 @@
+use std::fmt;
+use std::fs::File;
+use std::io;
+
+/* -------------------------
+* Per-file errors. A file that cannot be read (permissions, a vanished file, content that is not
+* valid UTF-8, ...) no longer stops the run: the error is recorded against the file, the file gets
+* no findings and the scan goes on. Binary files (sas7bdat datasets, catalogs, archives) are
+* skipped the same way, without being parsed as text. At the end of a run the collected errors are
+* written to errors_<timestamp>.csv, one row per failed or skipped file with the kind of error and
+* the underlying message.
+* --------------------------- */
+
+pub const ERROR_HEADERS: [&str; 3] = ["path", "error", "message"];
+
+#[derive(Debug)]
+pub enum FileError {
+    // the file (or a directory entry) could not be read
+    Io { path: String, source: io::Error },
+    // the file content is not valid UTF-8 text
+    NotUtf8 { path: String },
+    // the file looks like binary data and was skipped
+    Binary { path: String },
+}
+
+impl FileError {
+    // from_io classifies an io::Error raised while reading path; invalid UTF-8 content is
+    // reported by read_to_string as InvalidData
+    pub fn from_io(path: &str, source: io::Error) -> FileError {
+        if source.kind() == io::ErrorKind::InvalidData {
+            FileError::NotUtf8 { path: path.to_string() }
+        } else {
+            FileError::Io { path: path.to_string(), source }
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        match self {
+            FileError::Io { path, .. } | FileError::NotUtf8 { path } | FileError::Binary { path } => path,
+        }
+    }
+
+    // kind is the short error name written to the errors table
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FileError::Io { .. } => "io",
+            FileError::NotUtf8 { .. } => "not_utf8",
+            FileError::Binary { .. } => "binary",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            FileError::Io { source, .. } => source.to_string(),
+            FileError::NotUtf8 { .. } => String::from("file content is not valid UTF-8"),
+            FileError::Binary { .. } => String::from("binary content, skipped"),
+        }
+    }
+
+    pub fn record(&self) -> Vec<String> {
+        vec![self.path().to_string(), self.kind().to_string(), self.message()]
+    }
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path(), self.message())
+    }
+}
+
+impl std::error::Error for FileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FileError::Io { source, .. } => Some(source),
+            FileError::NotUtf8 { .. } | FileError::Binary { .. } => None,
+        }
+    }
+}
+
+// write_errors saves the errors as <output_dir>/errors_<timestamp>.csv
+pub fn write_errors(output_dir: &str, timestamp: &str, errors: &[FileError]) -> io::Result<()> {
+    let path = format!("{}/errors_{}.csv", output_dir, timestamp);
+    let mut wtr = csv::Writer::from_writer(File::create(path)?);
+    wtr.write_record(ERROR_HEADERS)?;
+    for error in errors {
+        wtr.write_record(error.record())?;
+    }
+    wtr.flush()
+}
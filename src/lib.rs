@@ -0,0 +1,49 @@
+/*
+* sas_parser
+*
+* Library half of sas_parser_rust. Everything the command line tool does is available here,
+* so other programs can scan a directory of SAS code without shelling out to the CLI.
+*
+* Modules:
+* - cache: the incremental scan cache, so unchanged files are not parsed again
+* - config: TOML/YAML config files holding the same options as the command line
+* - diff: compares the findings of two runs (or two directories) file by file
+* - errors: the per-file errors collected instead of stopping the run
+* - mmap: read-only memory maps for reading very large files
+* - timings: per parse function (and per file) run timings
+* - sha1: SHA-1 digests, for name-based uuids and content fingerprints
+* - zip: read-only zip archives, for the programs embedded in Enterprise Guide projects
+* - scanner: walks a directory tree, collects FileInfo for each file and runs the parse functions
+* - plugins: parse functions loaded from shared libraries (--plugins), through a C ABI
+* - policy: --fail-on threshold rules over the run totals of parse functions
+* - rules: user-defined regex rules (--rules), run alongside the parse functions
+* - parsers: the parse functions themselves, plus the FileContent and Finding types they share,
+*   the Parser trait every analyzer is run through, and the SAS tokenizer (parsers::lexer) they
+*   read statements with
+* - output: writers for the summary and detail results
+*
+* Example:
+*     let result = sas_parser::scan_dir("sas_code")?;
+*     for finding in &result.findings {
+*         println!("{} {} {}", finding.file_id, finding.func_nm, finding.result);
+*     }
+*/
+
+pub mod cache;
+pub mod config;
+pub mod diff;
+pub mod errors;
+#[cfg(unix)]
+pub mod mmap;
+pub mod output;
+pub mod parsers;
+pub mod plugins;
+pub mod policy;
+pub mod rules;
+pub mod scanner;
+pub mod sha1;
+pub mod timings;
+pub mod zip;
+
+pub use parsers::{FileContent, FileContext, Finding, ParseFunction, Parser};
+pub use scanner::{scan_dir, scan_dir_with, FileInfo, ScanOptions, ScanResult};
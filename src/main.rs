@@ -5,187 +5,187 @@
 * Date: 6/23/2023
 * Last updated: 6/30/2023
 *               add timer so we can get more precise timing of the parsing routine
+*               read each file once and share the content across all parse functions
+*               move scanning, parsing and output into the sas_parser library crate (src/lib.rs)
+*               add --format so results can be written as csv, json or jsonl
+*               add sqlite output format (summary and detail tables in one database file)
+*               add --functions and --list-functions to choose which parse functions run
+*               add --config so options can be read from a TOML or YAML file
+*               add get_includes and the includes dependency edge list
+*               parse LIBNAME statements into separate fields, written to the libnames table
+*               ignore commented code by default; --include-comments analyzes it too
+*               add get_macro_calls and the macro_calls cross-file call graph edge list
+*               report macros that are defined but never invoked in the orphans table
+*               add --report html for a self-contained HTML summary of the run
+*               add --report markdown for a Markdown summary with per-directory rollups
+*               add parquet output format for large scans
+*               add --graph-format dot|graphml to export the include, macro call and lineage graphs
+*               add --cache so repeat runs only parse the files that changed since the last run
+*               add --include and --exclude glob patterns to choose which files are scanned
+*               only scan SAS related file extensions by default; --extensions changes the list
+*               add --max-depth to limit how deep the input directory is walked
+*               skip symbolic links unless --follow-symlinks is given, with symlink loop detection
+*               -i can name a single file; without -o its findings are printed to stdout
+*               -i - reads the list of files to analyze from stdin, one path per line
+*               an unreadable or non-UTF-8 file no longer stops the run; failures go to errors.csv
+*               skip binary files (NUL bytes or mostly control characters), recording them in errors.csv
+*               memory-map files over --mmap-threshold MB instead of reading them onto the heap
+*               add --stream to write findings (PROC SQL blocks first of all) as soon as they are found
+*               add --timings (and --timings-per-file) to write the time spent in each parse function
+*               add --stable-ids for uuids derived from the relative path (and content) instead of random ones
+*               write manifest.json with the version, options, times, file counts and outputs of each run
+*               add --append to add each run's rows, with a run_id and run_timestamp, to the same outputs
+*               add --extract-sql to write each PROC SQL block to its own .sql file under extracted_sql/
+*               add --extract-macros to write each macro definition to its own .sas file under extracted_macros/
+*               parse PROC SQL blocks into classified statements (get_sql_statements, sql_statements table)
+*               add get_datastep_metrics: statement counts and nesting depth per DATA step (datastep_metrics table)
+*               add a SAS tokenizer (lexer.rs); statements and comments are read from its tokens, so
+*               semicolons in string literals and %str() no longer split statements
+*               sql_count, get_sql, export_count and null_count no longer match inside string literals
+*               find PROC SQL blocks on statement boundaries, so PROC SQL; ... QUIT; on one line is found
+*               close a PROC SQL block without QUIT; at the next PROC/DATA step or the end of the file (unterminated)
+*               report get_password and find_date with the original line text and the column of the match
+*               export_count counts PROC EXPORT statements; add export_other_count and get_exports with line numbers
+*               find_date recognizes date/datetime literals, MM/DD/YYYY, DDMONYYYY and two-digit years, with the format
+*               add find_pii for SSNs, phone numbers and email addresses in code and DATALINES/CARDS data
+*               add find_hosts for hardcoded IP addresses, SERVER=/HOST= host names and URL hosts
+*               add find_shell_commands for X, SYSTASK COMMAND, %SYSEXEC and CALL SYSTEM
+*               add get_pipes for the shell commands of FILENAME ... PIPE statements
+*               add get_options: one row per system option set by OPTIONS statements (options table)
+*               add get_ods for the ODS destinations a program writes report files to
+*               add get_titles for the text of TITLE and FOOTNOTE statements
+*               add get_formats for the formats defined by PROC FORMAT and CNTLIN= datasets
+*               add get_variable_lists for KEEP=/DROP=/RENAME= options and statements (variable_lists table)
+*               add get_infiles for INFILE statements and the INPUT that reads them (infiles table)
+*               add get_outfiles for FILE statements writing external files and their PUT counts (outfiles table)
+*               add get_arrays for ARRAY statements with their dimension and member variables
+*               add get_hash_objects for DECLARE HASH/HITER objects and the datasets loaded into them
+*               add get_retain_lag for RETAIN statements, sum statements and LAG/DIF calls
+*               add get_do_nesting for the DO ... END block count and deepest DO nesting of each file
+*               add complexity: a per-file score from the IF, ELSE, DO loop, SELECT, WHEN and %IF counts
+*               add --duplicates to report code regions copied between files, from fingerprints of 10-line shingles
+*               add line_breakdown: separate counts of the code, comment and blank lines of each file
+*               add get_sysfunc for %SYSFUNC/%QSYSFUNC calls and the function each one runs
+*               add get_auto_macro_vars for references to automatic macro variables (&SYSDATE9, &SYSUSERID, ...)
+*               add get_deprecated, a rule pack flagging deprecated syntax (PROC CONVERT, V6 engines, GOTO, ...)
+*               add get_engines and the engines table of the files and references per SAS/ACCESS engine
+*               add find_sas_connect for SIGNON, RSUBMIT/ENDRSUBMIT, %SYSLPUT and other SAS/CONNECT statements
+*               add get_datasets_operations for the DELETE, CHANGE, AGE, SAVE and KILL operations of PROC DATASETS
+*               add get_sql_dml and sql_dml_count for the INSERT, UPDATE, DELETE and ALTER statements of PROC SQL
+*               add get_sql_creates for the libref and name of the tables and views created by PROC SQL
+*               add get_sql_into for the macro variables filled by PROC SQL SELECT ... INTO
+*               add --migration-score: a weighted per-file migration difficulty score (migration_scores table)
+*               add get_fcmp_functions for the functions and subroutines defined in PROC FCMP
+*               add find_jumps for %GOTO, %label:, and DATA step GOTO and LINK statements
+*               add get_emails for the recipients and subject of FILENAME ... EMAIL statements
+*               add find_dde for FILENAME ... DDE statements and the Excel topics they reference
+*               add --mode logs to analyze SAS .log files: messages, errors, step times and program
+*               scan the programs embedded in Enterprise Guide projects (.egp), with their flow and name
+*               accept s3://bucket/prefix as the input, reading the objects from S3 through the aws CLI
+*               accept sftp://host/path as the input, scanning a remote directory tree over SSH
+*               add --git and git URL input: scan only tracked files, at a ref, and record the commit
+*               add --since-ref to scan only the files changed since a git commit, tag or branch
+*               add the diff subcommand: new, removed and changed findings between two runs
+*               add --fail-on threshold rules that exit with status 1, as a CI gate
+*               add --rules: user-defined regex rules (TOML or YAML) run alongside the parse functions
+*               add --plugins: parse functions loaded from shared libraries with a C ABI
+*               run every analyzer through the Parser trait; find_file_name can now be selected
+*               add line_start, line_end, value and extra (JSON) fields to detail findings
+*               add --multiline to escape, base64 encode or move multi-line detail values out of the CSV
 * Version: 0.7
 * This program analyzes a directory of text files, providing several output metrics
 * including line count, count of SQL statements, and extracting all SQL blocks.
 *
 * Inputs:
-* -i, --input : Path to the directory to analyze
-* -o, --output : Path to the directory where the output CSV files will be written
+* -i, --input : Path to the directory to analyze, a single file, - to read a list of files from stdin,
+*                 s3://bucket/prefix, sftp://[user@]host[:port]/path or a git URL
+* --git : Scan only the files git tracks in the input repository; =REF clones it and checks out REF first
+* --since-ref : Only scan the tracked files that changed since this git commit, tag or branch (implies --git)
+* -o, --output : Path to the directory where the output CSV files will be written (optional for a single file)
+* -f, --format : Output format: csv (default), json, jsonl, parquet or sqlite
+* --mode : source (default) to analyze SAS programs, or logs to analyze the .log files SAS wrote
+* --extensions : Comma separated file extensions to scan (default sas,inc,sql,egp, or log with --mode logs; * for every file)
+* --max-depth : How many directory levels below the input directory to scan (0 = only the input directory)
+* --follow-symlinks : Follow symbolic links to files and directories (skipped by default)
+* --mmap-threshold : Memory-map files of at least this many MB instead of reading them (default 64, 0 = never)
+* --include : Glob pattern of files to scan, relative to the input directory (repeatable, default all files)
+* --exclude : Glob pattern of files and directories to skip (repeatable)
+* --functions : Comma separated list of parse functions to run (default: all)
+* --list-functions : Print the available parse functions and exit
+* --include-comments : Also analyze code inside comments (ignored by default)
+* --cache : Cache file of parse results; unchanged files are not parsed again on the next run
+* --stream : Write findings to the output as they are found instead of per file (not with --cache)
+* --append : Add this run's rows to summary, detail and the other tables in the output directory (csv, jsonl or sqlite),
+*            with run_id and run_timestamp columns, instead of writing new timestamped files
+* --multiline : How the detail CSV holds results that span lines (e.g. get_sql blocks): keep (default), escape (\n),
+*               base64, or json (get_sql, get_datastep and get_passthrough moved to detail_blocks.jsonl)
+* --extract-sql : Also write each PROC SQL block to its own .sql file in the extracted_sql directory
+* --extract-macros : Also write each %MACRO ... %MEND definition to its own .sas file in the extracted_macros directory
+* --duplicates : Also report blocks of code copied between scanned files, in the duplicates table
+* --migration-score : Also score each file's migration difficulty into the migration_scores table; =name=weight,... changes the weights
+* --timings : Also write the time spent in each parse function to timings.csv
+* --timings-per-file : Also write the time spent on each file, per parse function, to timings.csv
+* --stable-ids : Derive each file's uuid (v5) from its relative path, or with =content its path and content
+* --fail-on : Exit with status 1 when a rule holds for the run totals, e.g. get_password>0,sql_count>100
+* --plugins : Directory of plugin shared libraries (.so/.dylib) whose parse functions are registered next to the built-in ones
+* --rules : TOML or YAML file of named regex rules (pattern, description, severity, line or block scope); their hits
+*           go to detail under the rule name
+* --graph-format : Also write the includes, macro_calls and lineage graphs as dot or graphml
+* --report : Also write run reports: html and/or markdown (comma separated)
+* -c, --config : TOML or YAML file holding any of the long options above, e.g. input = "sas_code"
+* diff OLD NEW : Subcommand comparing the findings of two runs (csv output directories) or two input directories
 *
 * Outputs:
 * Two CSV files in the specified output directory:
 * 1. summary.csv - includes information about each file such as UUID, name, directory, creation date, modification date, and size
-* 2. detail.csv - includes the results of parsing functions such as line_count, sql_count, and get_sql
+* 2. detail.csv - includes the results of parsing functions such as line_count, sql_count, and get_sql, each
+*    as the result tuple and as typed fields: line_start, line_end, value and extra (a JSON object)
+* 3. includes.csv - the %INCLUDE dependency edge list between scanned files (when get_includes runs)
+* 4. libnames.csv - each LIBNAME statement split into libref, engine, path and options (when get_libname runs)
+* 5. macro_calls.csv - the caller -> callee macro call graph edge list across scanned files (when get_macro_calls runs)
+* 6. orphans.csv - macros defined in the scanned tree but never invoked anywhere in it (when get_macros runs)
+* 7. report.html / report.md - a summary of the run for sharing (with --report html or markdown)
+* 8. includes.dot, macro_calls.dot, lineage.dot (or .graphml) - dependency graphs (with --graph-format)
+* 9. errors.csv - each file that could not be read, or was skipped as binary, and why (when any file failed)
+* 10. timings.csv - elapsed time per parse function, and optionally per file (with --timings)
+* 11. manifest.json - tool version, options, start/end time, file counts and output file names of the run
+* 12. extracted_sql/<file>_<line>.sql - each PROC SQL block of the scanned files (with --extract-sql)
+* 13. extracted_macros/<file>_<line>_<macro>.sas - each macro definition of the scanned files (with --extract-macros)
+* 14. duplicates.csv - pairs of near-identical code regions (10+ code lines) in different scanned files (with --duplicates)
+* 15. engines.csv - the number of files and references of each LIBNAME/CONNECT engine across scanned files (when get_engines runs)
+* 16. migration_scores.csv - a weighted migration difficulty score per file, with the counts it is built from (with --migration-score)
+* 17. log_summary.csv - ERROR, WARNING and NOTE counts, total step times and program of each log (with --mode logs)
+* 18. egp_programs.csv - the project, process flow and name of each program embedded in a scanned .egp file
+* 19. detail_blocks.jsonl - the get_sql, get_datastep and get_passthrough findings, instead of detail.csv (with --multiline json)
 *
 */
 
-use chrono::{DateTime, Local, TimeZone, Utc};
-use csv::WriterBuilder;
-use getopts::Options;
+use chrono::Local;
+use getopts::{Matches, Options};
 use indicatif::{ProgressBar, ProgressStyle};
-use regex::Regex;
+use sas_parser::config::Config;
+use sas_parser::output::extract::{self, Extractor};
+use sas_parser::output::graph::{Graph, GraphFormat};
+use sas_parser::output::manifest::{self, FileCounts, Manifest};
+use sas_parser::output::report::{ReportFormat, ReportSink};
+use sas_parser::output::{self, Multiline, OutputFormat, RecordWriter};
+use sas_parser::parsers::{self, duplicates, engines, includes, lineage, macros, migration, sql, FileNameParser, Finding, Parser};
+use sas_parser::plugins::PluginSet;
+use sas_parser::policy::Policy;
+use sas_parser::rules::RuleSet;
+use sas_parser::cache::Cache;
+use sas_parser::diff::{self, RunFindings};
+use sas_parser::errors::{self, FileError};
+use sas_parser::scanner::egp;
+use sas_parser::scanner::git;
+use sas_parser::scanner::glob::Glob;
+use sas_parser::scanner::ids::{self, IdScheme};
+use sas_parser::scanner::{self, CorpusPasses, FileInfo, FileResults, ScanOptions, StructuredTable};
+use sas_parser::timings::Timings;
 use std::env;
-use std::fs::{self, File};
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead};
 use std::path::Path;
-use std::time::UNIX_EPOCH;
-use uuid::Uuid;
-use std::time::{Duration, Instant};
-
-#[derive(Debug)]
-// Define structure to hold information about each file
-struct FileInfo {
-    uuid: String,
-    name: String,
-    directory: String,
-    create_date: DateTime<Utc>,
-    modify_date: DateTime<Utc>,
-    size: u64,
-}
-
-// ParseFunction is a function that takes a file_id and a file_path,
-// and returns a Vec of results where each result is a tuple of (Function name, File ID, Result)
-type ParseFunction = fn(&String, &String) -> Vec<(String, String, String)>;
-
-fn compute_total_files(dir: &str) -> io::Result<u64> {
-    let mut file_count = 0;
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let metadata = fs::metadata(entry.path())?;
-        if metadata.is_file() {
-            file_count += 1;
-        } else if metadata.is_dir() {
-            file_count += compute_total_files(entry.path().to_str().unwrap())?;
-        }
-    }
-    Ok(file_count)
-}
-
-
-// Define our parse functions here:
-/* -------------------------
-* Parse Functions: These are the functions that will be used to parse the files.
-* They each perform a unique analysis on the file:
-* - line_count: Counts the number of lines in a file.
-* - sql_count: Counts the number of SQL blocks in a file.
-* - get_sql: Extracts SQL blocks from a file.
-* - get_libname: extracts any line that starts with LIBNAME.
-* - get_password: return any line, and it's corresponding line number, that has password = (but not &password)
-* - get_export_count: return number of lines that have EXPORT.
-* - get_null_count: return number of lines that have _null_
-* - find_date: return line and line number of any line that has a string of format: YYYY-MM-DD
-* --------------------------- */
-
-fn line_count(file_id: &String, file_path: &String) -> Vec<(String, String, String)> {
-    let file = File::open(file_path).unwrap();
-    let reader = BufReader::new(file);
-    let line_count = reader.lines().count();
-    vec![(file_id.clone(), "line_count".to_string(), line_count.to_string())]
-}
-
-fn sql_count(file_id: &String, file_path: &String) -> Vec<(String, String, String)> {
-    let content = fs::read_to_string(file_path).unwrap();
-    let content = content.to_uppercase();
-    let re = Regex::new(r"(?s)PROC\s+SQL.*?QUIT;").unwrap();
-    let sql_count = re.find_iter(&content).count();
-    vec![(file_id.clone(), "sql_count".to_string(), sql_count.to_string())]
-}
-
-fn get_sql(file_id: &String, file_path: &String) -> Vec<(String, String, String)> {
-    let mut results: Vec<(String, String, String)> = Vec::new();
-    let file = File::open(file_path).unwrap();
-    let reader = BufReader::new(file);
-    let mut inside_sql_block = false;
-    let mut sql_block: Vec<String> = Vec::new();
-    let mut sql_start_line = 0;
-    for (line_number, line_result) in reader.lines().enumerate() {
-        let line = line_result.unwrap();
-        let upper_line = line.to_uppercase();
-        if !inside_sql_block && upper_line.contains("PROC SQL") {
-            inside_sql_block = true;
-            sql_start_line = line_number + 1;
-        }
-        if inside_sql_block {
-            sql_block.push(line);
-            if upper_line.contains("QUIT;") {
-                inside_sql_block = false;
-                results.push((
-                    String::from(file_id),
-                    String::from("get_sql"),
-                    format!("({}, {})", sql_start_line, sql_block.join("\n")),
-                ));
-                sql_block.clear();
-            }
-        }
-    }
-    results
-}
-
-fn get_libname(file_id: &String, file_path: &String) -> Vec<(String, String, String)> {
-    let content = fs::read_to_string(file_path).unwrap();
-    let mut results: Vec<(String, String, String)> = Vec::new();
-    for (line_number, line) in content.lines().enumerate() {
-        if line.to_uppercase().starts_with("LIBNAME") {
-            results.push((file_id.clone(), "get_libname".to_string(), format!("({})", line)));
-        }
-    }
-    results
-}
-
-fn get_password(file_id: &String, file_path: &String) -> Vec<(String, String, String)> {
-    let content = fs::read_to_string(file_path).unwrap();
-    let mut results: Vec<(String, String, String)> = Vec::new();
-    for (line_number, line) in content.lines().enumerate() {
-        let modified_line = line.to_uppercase().replace(char::is_whitespace, "");
-        if modified_line.contains("PASSWORD=") && !modified_line.contains("&PASSWORD") {
-            results.push((file_id.clone(), "get_password".to_string(), format!("({}, {})", line_number + 1, modified_line)));
-        }
-    }
-    results
-}
-
-
-fn export_count(file_id: &String, file_path: &String) -> Vec<(String, String, String)> {
-    let content = fs::read_to_string(file_path).unwrap();
-    let count = content.to_uppercase().matches("EXPORT").count();
-    vec![(file_id.clone(), "export_count".to_string(), count.to_string())]
-}
-
-fn null_count(file_id: &String, file_path: &String) -> Vec<(String, String, String)> {
-    let content = fs::read_to_string(file_path).unwrap();
-    let content = content.to_uppercase();
-    let count = content.matches("_NULL_").count();
-    vec![(file_id.clone(), "null_count".to_string(), count.to_string())]
-}
-
-fn find_date(file_id: &String, file_path: &String) -> Vec<(String, String, String)> {
-    let re = Regex::new(r"\b\d{4}-\d{2}-\d{2}\b").unwrap();
-    let content = fs::read_to_string(file_path).unwrap();
-    let mut results: Vec<(String, String, String)> = Vec::new();
-    for (line_number, line) in content.lines().enumerate() {
-        if re.is_match(line) {
-            results.push((file_id.clone(), "find_date".to_string(), format!("({}, {})", line_number + 1, line)));
-        }
-    }
-    results
-}
-
-fn find_file_name(file_id: &String, file_path: &String, file_list: &Vec<String>) -> Vec<(String, String, String)> {
-    let content = fs::read_to_string(file_path).unwrap();
-    let mut results: Vec<(String, String, String)> = Vec::new();
-    for (line_number, line) in content.lines().enumerate() {
-        for file_name in file_list {
-            if line.contains(file_name) {
-                results.push((file_id.clone(), "find_file_name".to_string(), format!("({}):{}", line_number + 1, line)));
-                break;
-            }
-        }
-    }
-    results
-}
-
-
+use std::time::Instant;
 
 /* -------------------------
 * Main Function: This is where the program execution begins.
@@ -198,11 +198,45 @@ fn find_file_name(file_id: &String, file_path: &String, file_list: &Vec<String>)
 * 6. Finish progress bar
 * --------------------------- */
 fn main() -> io::Result<()> {
+    let run_start = Local::now();
     // Command line argument handling
     let args: Vec<String> = env::args().collect();
+    if args.get(1).is_some_and(|command| command == "diff") {
+        return run_diff(&args[2..]);
+    }
     let mut opts = Options::new();
-    opts.optopt("i", "input", "set input directory", "INPUT");
-    opts.optopt("o", "output", "set output directory", "OUTPUT");
+    opts.optopt("i", "input", "set input directory (or a single file, - for a list of files on stdin, s3://bucket/prefix or sftp://host/path)", "INPUT");
+    opts.optopt("o", "output", "set output directory (without it a single input file is printed to stdout)", "OUTPUT");
+    opts.optopt("f", "format", "set output format: csv, json, jsonl, parquet or sqlite (default csv)", "FORMAT");
+    opts.optflagopt("", "git", "scan only the files git tracks in the input repository, optionally checked out at a ref", "REF");
+    opts.optopt("", "since-ref", "only scan the tracked files that changed since this git ref (commit, tag or branch)", "REF");
+    opts.optopt("", "mode", "what the scanned files are: source (default) or logs (SAS .log files)", "MODE");
+    opts.optopt("", "extensions", "comma separated file extensions to scan (default sas,inc,sql,egp, or log with --mode logs; * for all files)", "EXTENSIONS");
+    opts.optopt("", "max-depth", "how many directory levels below the input directory to scan (default no limit)", "N");
+    opts.optflag("", "follow-symlinks", "follow symbolic links to files and directories, skipping loops");
+    opts.optopt("", "mmap-threshold", "memory-map files of at least this many MB instead of reading them (default 64, 0 = never)", "MB");
+    opts.optmulti("", "include", "only scan files matching this glob pattern (repeatable)", "GLOB");
+    opts.optmulti("", "exclude", "skip files and directories matching this glob pattern (repeatable)", "GLOB");
+    opts.optopt("", "functions", "comma separated list of parse functions to run (default all)", "FUNCTIONS");
+    opts.optflag("", "list-functions", "print the available parse functions and exit");
+    opts.optflag("", "include-comments", "also analyze code inside comments (ignored by default)");
+    opts.optopt("", "cache", "reuse the results of unchanged files from this cache file, and update it", "CACHE");
+    opts.optflag("", "stream", "write findings as soon as they are found, bounding memory on huge files");
+    opts.optflag("", "append", "add this run's rows, with a run_id, to the existing outputs instead of new timestamped files");
+    opts.optopt("", "multiline", "how detail csv holds multi-line values: keep, escape, base64 or json (default keep)", "MODE");
+    opts.optflag("", "extract-sql", "also write each PROC SQL block to its own .sql file under extracted_sql/");
+    opts.optflag("", "extract-macros", "also write each macro definition to its own .sas file under extracted_macros/");
+    opts.optflag("", "duplicates", "also report blocks of code copied between scanned files (duplicates table)");
+    opts.optflagopt("", "migration-score", "also score each file's migration difficulty (migration_scores table), optionally with weights name=weight,...", "WEIGHTS");
+    opts.optflag("", "timings", "also write the time spent in each parse function to timings.csv");
+    opts.optflag("", "timings-per-file", "also write the time spent on each file to timings.csv");
+    opts.optflagopt("", "stable-ids", "derive file uuids from the relative path, or with =content the path and content", "content");
+    opts.optopt("", "plugins", "load the parse functions of every plugin library (.so/.dylib) in this directory", "DIR");
+    opts.optopt("", "rules", "also run the named regex rules of this TOML or YAML file; hits go to detail under the rule name", "RULES_FILE");
+    opts.optopt("", "fail-on", "exit with status 1 when a rule such as get_password>0,sql_count>100 holds for the run totals", "RULES");
+    opts.optopt("", "graph-format", "also write the dependency graphs as dot or graphml", "GRAPH_FORMAT");
+    opts.optopt("", "report", "also write run reports: html and/or markdown (comma separated)", "REPORT");
+    opts.optopt("c", "config", "read options from a TOML or YAML config file", "CONFIG");
     opts.optflag("h", "help", "print this help menu");
     let matches = match opts.parse(&args[1..]) {
         Ok(m) => { m }
@@ -212,17 +246,220 @@ fn main() -> io::Result<()> {
         print_usage(&opts);
         return Ok(());
     }
-    let input_dir = matches.opt_str("i").unwrap();
-    let output_dir = matches.opt_str("o").unwrap();
+    let config = match matches.opt_str("c") {
+        Some(path) => Config::load(&path).unwrap_or_else(|e| panic!("Could not read config file: {}", e)),
+        None => Config::default(),
+    };
+    let config_path = matches.opt_str("c");
+    let arguments: Vec<String> = args[1..].to_vec();
+    let args = Args { matches, config };
+    let mode = match args.opt_str("mode") {
+        Some(m) => m.parse::<parsers::Mode>().unwrap_or_else(|e| panic!("{}", e)),
+        None => parsers::Mode::Source,
+    };
+    let rules = match args.opt_str("rules") {
+        Some(path) => RuleSet::load(&path).unwrap_or_else(|e| panic!("Could not read rules file: {}", e)),
+        None => RuleSet::default(),
+    };
+    let plugins = match args.opt_str("plugins") {
+        Some(dir) => PluginSet::load_dir(&dir).unwrap_or_else(|e| panic!("Could not load plugins: {}", e)),
+        None => PluginSet::default(),
+    };
+    // anything that changes what the plugins and rules find makes an earlier cache unusable
+    let extra_settings = format!("plugins={} rules={:?}", plugins.fingerprint, rules.source);
+    let (plugin_names, rule_names) = (plugins.names(), rules.names());
+    let mut extra_parsers = plugins.into_parsers();
+    extra_parsers.extend(rules.into_parsers());
+    parsers::check_extra_names(&extra_parsers).unwrap_or_else(|e| panic!("--plugins/--rules: {}", e));
+    if args.opt_present("list-functions") {
+        print_functions(mode, &extra_parsers);
+        return Ok(());
+    }
+    let input = args.opt_str("input").expect("No input directory given (use -i or input in the config file)");
+    let output_dir = args.opt_str("output");
+    let format = match args.opt_str("format") {
+        Some(f) => f.parse::<OutputFormat>().unwrap_or_else(|e| panic!("{}", e)),
+        None => OutputFormat::Csv,
+    };
+    let mut function_names: Vec<String> = match args.opt_str("functions") {
+        Some(list) => list.split(',').map(|name| name.trim().to_string()).filter(|name| !name.is_empty()).collect(),
+        None => mode.functions().iter().map(|(name, _, _)| name.to_string()).chain(plugin_names).collect(),
+    };
+    // the rules of --rules always run; find_file_name runs last, as it is only built once the
+    // files are listed
+    for name in rule_names {
+        if !function_names.contains(&name) {
+            function_names.push(name);
+        }
+    }
+    let find_file_names = function_names.iter().any(|name| name == parsers::FIND_FILE_NAME.0);
+    if find_file_names {
+        function_names.retain(|name| name != parsers::FIND_FILE_NAME.0);
+        function_names.push(parsers::FIND_FILE_NAME.0.to_string());
+    }
+    let graph_format = args
+        .opt_str("graph-format")
+        .map(|f| f.parse::<GraphFormat>().unwrap_or_else(|e| panic!("{}", e)));
+    let report_formats: Vec<ReportFormat> = match args.opt_str("report") {
+        Some(list) => list
+            .split(',')
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+            .map(|name| name.parse::<ReportFormat>().unwrap_or_else(|e| panic!("{}", e)))
+            .collect(),
+        None => Vec::new(),
+    };
+    let mut parse_functions: Vec<Box<dyn Parser>> = Vec::new();
+    for name in function_names.iter().filter(|name| *name != parsers::FIND_FILE_NAME.0) {
+        match extra_parsers.iter().position(|parser| parser.name() == name) {
+            Some(i) => parse_functions.push(extra_parsers.remove(i)),
+            None => parse_functions.extend(parsers::select_mode_functions(mode, std::slice::from_ref(name)).unwrap_or_else(|e| panic!("{}", e))),
+        }
+    }
+    let build_includes = function_names.iter().any(|name| name == "get_includes");
+    let build_macro_calls = function_names.iter().any(|name| name == "get_macro_calls");
+    let build_orphans = function_names.iter().any(|name| name == "get_macros");
+    let build_duplicates = args.opt_present("duplicates");
+    let build_engines = function_names.iter().any(|name| name == "get_engines");
+    let build_migration = args.opt_present("migration-score");
+    let migration_weights = match args.opt_str("migration-score") {
+        Some(weights) => migration::parse_weights(&weights).unwrap_or_else(|e| panic!("--migration-score: {}", e)),
+        None => migration::DEFAULT_WEIGHTS,
+    };
+    let mut policy = match args.opt_str("fail-on") {
+        Some(rules) => Policy::parse(&rules).unwrap_or_else(|e| panic!("--fail-on: {}", e)),
+        None => Policy::default(),
+    };
+    if let Some(rule) = policy.rules.iter().find(|rule| !function_names.contains(&rule.function)) {
+        panic!("--fail-on: {} is not one of the parse functions run", rule.function);
+    }
+    let build_lineage_graph = graph_format.is_some() && function_names.iter().any(|name| name == "get_lineage");
+    let mut structured_tables = StructuredTable::for_functions(&function_names);
+    let corpus_passes = CorpusPasses {
+        includes: build_includes,
+        macros: build_macro_calls || build_orphans,
+        lineage: build_lineage_graph,
+        duplicates: build_duplicates,
+        engines: build_engines,
+        migration: build_migration,
+    };
+    let scan_options = ScanOptions {
+        // a log is read as it is: its comments are the submitted code echoed into it
+        include_comments: args.opt_present("include-comments") || mode == parsers::Mode::Logs,
+        include: parse_globs(&args.opt_strs("include")),
+        exclude: parse_globs(&args.opt_strs("exclude")),
+        extensions: match args.opt_str("extensions") {
+            Some(list) if list.trim() == "*" => Vec::new(),
+            Some(list) => list
+                .split(',')
+                .map(|extension| extension.trim().trim_start_matches('.').to_lowercase())
+                .filter(|extension| !extension.is_empty())
+                .collect(),
+            None => mode.extensions().iter().map(|extension| extension.to_string()).collect(),
+        },
+        max_depth: args
+            .opt_str("max-depth")
+            .map(|depth| depth.parse::<usize>().unwrap_or_else(|_| panic!("--max-depth must be a whole number, got {}", depth))),
+        follow_symlinks: args.opt_present("follow-symlinks"),
+        mmap_threshold: match args.opt_str("mmap-threshold") {
+            Some(mb) => match mb.parse::<u64>() {
+                Ok(0) => None,
+                Ok(mb) => Some(mb * 1024 * 1024),
+                Err(_) => panic!("--mmap-threshold must be a whole number of MB, got {}", mb),
+            },
+            None => Some(scanner::DEFAULT_MMAP_THRESHOLD),
+        },
+    };
+    let id_scheme = match args.opt_str("stable-ids").as_deref() {
+        Some("content") => IdScheme::PathAndContent,
+        Some("path") => IdScheme::Path,
+        Some(other) => panic!("Unknown --stable-ids value: {} (expected path or content)", other),
+        None if args.opt_present("stable-ids") => IdScheme::Path,
+        None => IdScheme::Random,
+    };
+    for key in args.config.unused_keys() {
+        eprintln!("Warning: unknown option in config file: {}", key);
+    }
 
-    if !Path::new(&input_dir).exists() {
+    // --git, or a git URL as the input: only the files git tracks are scanned, in the repository
+    // itself or in a temporary clone checked out at the ref given; with --since-ref only those
+    // that changed since that ref
+    let git_checkout = match git::is_url(&input) || args.opt_present("git") || args.opt_present("since-ref") {
+        true => {
+            let mut checkout = git::Checkout::open(&input, args.opt_str("git").as_deref())?;
+            if let Some(reference) = args.opt_str("since-ref") {
+                checkout.since(&reference)?;
+            }
+            Some(checkout)
+        }
+        false => None,
+    };
+    let input_dir = git_checkout.as_ref().map_or(input.clone(), |checkout| checkout.dir.clone());
+    let mut errors: Vec<FileError> = vec![];
+
+    // -i - : the files to analyze are listed on stdin, e.g. piped from find or git ls-files
+    let file_list = if input_dir == "-" { Some(read_file_list(io::stdin().lock())?) } else { None };
+    // git's tracked files, and the files of -i s3://bucket/prefix or sftp://host/path, are listed
+    // now (remote files are read when they are parsed)
+    let mut listed_files = if let Some(checkout) = &git_checkout {
+        Some(checkout.files(&scan_options, &mut errors)?)
+    } else if scanner::is_remote(&input_dir) {
+        Some(scanner::list_remote(&input_dir, &scan_options)?)
+    } else {
+        None
+    };
+    if file_list.is_none() && listed_files.is_none() && !Path::new(&input_dir).exists() {
         panic!("Input directory does not exist");
     }
+    // a single input file: without an output directory its findings are printed to stdout
+    let input_is_file = Path::new(&input_dir).is_file();
+    if input_is_file && output_dir.is_none() {
+        if find_file_names {
+            let file_list = scanner::file_infos(&input_dir)?.into_iter().map(|file_info| file_info.name).collect();
+            parse_functions.push(Box::new(FileNameParser { file_list }));
+        }
+        return print_file_findings(&input_dir, &parse_functions, &scan_options);
+    }
+    let output_dir = output_dir.expect("No output directory given (use -o or output in the config file)");
     if !Path::new(&output_dir).exists() {
         panic!("Output directory does not exist");
     }
+    // in append mode the rows of every run go to the same outputs, told apart by their run_id
+    let run = match args.opt_present("append") {
+        true if !output::appendable(format) => panic!("--append needs csv, jsonl or sqlite output"),
+        true => Some(output::Run::new(run_start)),
+        false => None,
+    };
+    let multiline = match args.opt_str("multiline") {
+        Some(_) if format != OutputFormat::Csv => panic!("--multiline needs csv output"),
+        Some(m) => m.parse::<Multiline>().unwrap_or_else(|e| panic!("{}", e)),
+        None => Multiline::Keep,
+    };
+
+    // anything that changes what is saved for a file makes the cache from an earlier run unusable
+    let cache_settings = format!(
+        "functions={} include_comments={} passes={:?} {}",
+        function_names.join(","),
+        scan_options.include_comments,
+        corpus_passes,
+        extra_settings
+    );
+    // a streamed file's findings are not kept, so there is nothing to cache
+    let stream = args.opt_present("stream");
+    if stream && args.opt_str("cache").is_some() {
+        panic!("--stream cannot be combined with --cache");
+    }
+    let mut cache = match args.opt_str("cache") {
+        Some(path) => Some(Cache::load(&path, &cache_settings)?),
+        None => None,
+    };
 
-    let total_files = compute_total_files(&input_dir)?;
+    let total_files = match &file_list {
+        Some(paths) => paths.len() as u64,
+        None if listed_files.is_some() => listed_files.as_ref().map_or(0, |files| files.len() as u64),
+        None if input_is_file => 1,
+        None => scanner::compute_total_files(&input_dir, &scan_options)?,
+    };
     let pb = ProgressBar::new(total_files);
     pb.set_style(
         ProgressStyle::default_bar()
@@ -236,103 +473,295 @@ fn main() -> io::Result<()> {
 
     let start_time = Instant::now(); // Start the timer
 
-    process_dir(&input_dir, &mut file_data, &pb)?;
-
-    let elapsed_time = start_time.elapsed(); // Calculate the elapsed time
+    if let Some(paths) = &file_list {
+        for path in paths {
+            match scanner::file_infos(path) {
+                Ok(file_infos) => file_data.extend(file_infos),
+                Err(e) => errors.push(FileError::from_io(path, e)),
+            }
+            pb.inc(1);
+        }
+    } else if input_is_file {
+        file_data.extend(scanner::file_infos(&input_dir)?);
+        pb.inc(1);
+    } else if let Some(files) = listed_files.take() {
+        pb.inc(files.len() as u64);
+        file_data = files;
+    } else {
+        scanner::process_dir(&input_dir, &mut file_data, &pb, &scan_options, &mut errors)?;
+    }
+    // %INCLUDE paths are resolved against the scanned directory, the directory of a single file,
+    // or the current directory for a list of files from stdin
+    let scan_root = if file_list.is_some() {
+        String::from(".")
+    } else if input_is_file {
+        FileInfo::from_path(&input_dir)?.directory
+    } else {
+        input_dir.clone()
+    };
+    ids::assign_ids(&mut file_data, &scan_root, id_scheme);
+    if find_file_names {
+        let file_list = file_data.iter().map(|file_info| file_info.name.clone()).collect();
+        parse_functions.push(Box::new(FileNameParser { file_list }));
+    }
 
     let now = Local::now();
-    let output_file_path = format!("{}/summary_{}.csv", output_dir, now.format("%Y%m%d%H%M%S"));
-    let mut wtr_summary = WriterBuilder::new()
-        .has_headers(true)
-        .from_path(output_file_path)?;
-
-    wtr_summary.write_record(&["uuid", "file_nm", "file_dir", "create_dt", "modify_dt", "size_bytes"])?;
-    for file_info in &file_data {
-        wtr_summary.write_record(&[
-            &file_info.uuid,
-            &file_info.name,
-            &file_info.directory,
-            &file_info.create_date.format("%Y-%m-%d %H:%M:%S").to_string(),
-            &file_info.modify_date.format("%Y-%m-%d %H:%M:%S").to_string(),
-            &file_info.size.to_string(),
-        ])?;
-    }
-
-    wtr_summary.flush()?;
-
-    let output_file_path = format!("{}/detail_{}.csv", output_dir, now.format("%Y%m%d%H%M%S"));
-    let mut wtr_detail = WriterBuilder::new()
-        .has_headers(true)
-        .from_path(output_file_path)?;
-
-    wtr_detail.write_record(&["uuid", "func_nm", "result"])?;
-
-    let parse_functions: Vec<ParseFunction> = vec![
-        line_count, 
-        sql_count, 
-        get_sql, 
-        get_libname, 
-        get_password,
-        export_count,
-        null_count,
-        find_date
-    ];
-
+    let timestamp = now.format("%Y%m%d%H%M%S").to_string();
+    let mut sink = output::create_sink(&output_dir, &timestamp, format, run.as_ref(), multiline)?;
+    if !report_formats.is_empty() {
+        sink = Box::new(ReportSink::new(sink, &output_dir, &timestamp, report_formats));
+    }
+    sink.write_summary(&file_data)?;
+
+    let mut file_includes: Vec<(&FileInfo, Vec<includes::Include>)> = Vec::new();
+    let mut file_macros: Vec<macros::FileMacros> = Vec::new();
+    let mut file_lineage: Vec<(&FileInfo, Vec<lineage::LineageEdge>)> = Vec::new();
+    let mut file_shingles: Vec<(&FileInfo, Vec<duplicates::Shingle>)> = Vec::new();
+    let mut file_engines: Vec<(&FileInfo, Vec<engines::EngineUse>)> = Vec::new();
+    let mut file_migration: Vec<(&FileInfo, migration::MigrationMetrics)> = Vec::new();
+    let mut timings = if args.opt_present("timings") || args.opt_present("timings-per-file") {
+        Some(Timings::new(&function_names, args.opt_present("timings-per-file")))
+    } else {
+        None
+    };
+    let mut sql_extractor = match args.opt_present("extract-sql") {
+        true => Some(Extractor::create(&output_dir, extract::SQL_DIR, &scan_root, "sql")?),
+        false => None,
+    };
+    let mut macro_extractor = match args.opt_present("extract-macros") {
+        true => Some(Extractor::create(&output_dir, extract::MACRO_DIR, &scan_root, "sas")?),
+        false => None,
+    };
+    let mut files_parsed = 0;
+    let mut findings_written = 0;
     for file_info in &file_data {
-        let file_path = format!("{}/{}", &file_info.directory, &file_info.name);
-        for parse_function in &parse_functions {
-            let results = parse_function(&file_info.uuid, &file_path);
-            for result in results {
-                let record: Vec<String> = vec![result.0, result.1, result.2];
-                wtr_detail.write_record(&record)?;
+        let cached = cache.as_mut().and_then(|cache| cache.get(file_info, structured_tables.len()));
+        let results = match cached {
+            Some(results) => results,
+            None => {
+                // read the file once, then hand the same content to every parse function;
+                // a file that cannot be read, or is binary, is recorded and gets no results
+                let read_start = Instant::now();
+                let content = match scanner::load_file(&file_info.path(), &scan_options) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        errors.push(e);
+                        continue;
+                    }
+                };
+                let read_time = read_start.elapsed();
+                files_parsed += 1;
+                if let Some(extractor) = sql_extractor.as_mut() {
+                    for block in sql::sql_blocks(&content) {
+                        let text = content.raw_lines(block.start_line, block.end_line);
+                        extractor.write(file_info, block.start_line, "", &text)?;
+                    }
+                }
+                if let Some(extractor) = macro_extractor.as_mut() {
+                    for definition in macros::find_macros(&content) {
+                        let text = content.raw_lines(definition.start_line, definition.end_line);
+                        extractor.write(file_info, definition.start_line, &definition.name, &text)?;
+                    }
+                }
+                let results = if stream {
+                    let mut emit = |finding: Finding| {
+                        findings_written += 1;
+                        policy.add(&finding);
+                        sink.write_finding(&finding)
+                    };
+                    FileResults::stream(file_info, &content, &parse_functions, &structured_tables, corpus_passes, &mut emit)?
+                } else {
+                    let results = FileResults::parse(file_info, &content, &parse_functions, &structured_tables, corpus_passes);
+                    if let Some(cache) = cache.as_mut() {
+                        cache.insert(file_info, &results);
+                    }
+                    results
+                };
+                if let Some(timings) = timings.as_mut() {
+                    timings.add_file(&file_info.path(), read_time, &results.function_times);
+                }
+                results
             }
+        };
+        for finding in &results.findings {
+            policy.add(finding);
+            sink.write_finding(finding)?;
+        }
+        findings_written += results.findings.len();
+        for (table, rows) in structured_tables.iter_mut().zip(results.table_rows) {
+            table.add_rows(file_info, rows);
         }
+        if build_includes {
+            file_includes.push((file_info, results.includes));
+        }
+        if corpus_passes.macros {
+            file_macros.push(macros::FileMacros { file: file_info, definitions: results.macro_definitions, calls: results.macro_calls });
+        }
+        if build_lineage_graph {
+            file_lineage.push((file_info, results.lineage));
+        }
+        if build_duplicates {
+            file_shingles.push((file_info, results.shingles));
+        }
+        if build_engines {
+            file_engines.push((file_info, results.engines));
+        }
+        if build_migration {
+            file_migration.push((file_info, results.migration));
+        }
+    }
+
+    for table in &structured_tables {
+        sink.write_table(table.name, &table.headers, &table.rows)?;
+    }
+
+    let egp_programs = egp::program_rows(&file_data);
+    if !egp_programs.is_empty() {
+        sink.write_table("egp_programs", &egp::EGP_HEADERS, &egp_programs)?;
+    }
+
+    if build_includes {
+        let edges = includes::resolve_includes(&scan_root, &file_includes, &file_data);
+        let rows: Vec<Vec<String>> = edges.iter().map(|edge| edge.record()).collect();
+        sink.write_table("includes", &includes::INCLUDE_HEADERS, &rows)?;
+        if let Some(graph_format) = graph_format {
+            Graph::from_includes(&edges).write(&output_dir, &timestamp, graph_format)?;
+        }
+    }
+
+    if build_macro_calls {
+        let edges = macros::resolve_macro_calls(&file_macros);
+        let rows: Vec<Vec<String>> = edges.iter().map(|edge| edge.record()).collect();
+        sink.write_table("macro_calls", &macros::MACRO_CALL_HEADERS, &rows)?;
+        if let Some(graph_format) = graph_format {
+            Graph::from_macro_calls(&edges).write(&output_dir, &timestamp, graph_format)?;
+        }
+    }
+
+    if let (true, Some(graph_format)) = (build_lineage_graph, graph_format) {
+        Graph::from_lineage(&file_lineage).write(&output_dir, &timestamp, graph_format)?;
     }
 
-    wtr_detail.flush()?;
+    if build_orphans {
+        sink.write_table("orphans", &macros::ORPHAN_HEADERS, &macros::find_orphans(&file_macros))?;
+    }
+
+    if build_duplicates {
+        let rows: Vec<Vec<String>> = duplicates::find_duplicates(&file_shingles).iter().map(|region| region.record()).collect();
+        sink.write_table("duplicates", &duplicates::DUPLICATE_HEADERS, &rows)?;
+    }
+
+    if build_engines {
+        sink.write_table("engines", &engines::ENGINE_HEADERS, &engines::engine_counts(&file_engines))?;
+    }
+
+    if build_migration {
+        let rows = migration::migration_scores(&file_migration, &migration_weights);
+        sink.write_table("migration_scores", &migration::MIGRATION_HEADERS, &rows)?;
+    }
+
+    sink.finish()?;
+
+    if let Some(timings) = &timings {
+        timings.write(&output_dir, &timestamp)?;
+    }
+
+    if !errors.is_empty() {
+        errors::write_errors(&output_dir, &timestamp, &errors)?;
+        println!("Files with errors: {} (see errors_{}.csv)", errors.len(), timestamp);
+    }
+
+    if let Some(extractor) = &sql_extractor {
+        println!("SQL blocks extracted: {} (see {}/)", extractor.written, extract::SQL_DIR);
+    }
+    if let Some(extractor) = &macro_extractor {
+        println!("Macros extracted: {} (see {}/)", extractor.written, extract::MACRO_DIR);
+    }
+
+    if let Some(cache) = &cache {
+        cache.save()?;
+        println!("Files reused from cache: {} of {}", cache.hits, file_data.len());
+    }
+
+    let mut outputs = manifest::run_outputs(&output_dir, &timestamp)?;
+    if run.is_some() {
+        outputs.extend(manifest::appended_outputs(&output_dir, format.extension())?);
+    }
+    if sql_extractor.is_some() {
+        outputs.push(format!("{}/", extract::SQL_DIR));
+    }
+    if macro_extractor.is_some() {
+        outputs.push(format!("{}/", extract::MACRO_DIR));
+    }
+    outputs.push(format!("manifest_{}.json", timestamp));
+    let manifest = Manifest {
+        arguments,
+        config: config_path,
+        input: input.clone(),
+        commit: git_checkout.as_ref().map(|checkout| checkout.commit.clone()),
+        output: output_dir.clone(),
+        format: args.opt_str("format").unwrap_or_else(|| String::from("csv")),
+        run_id: run.as_ref().map(|run| run.id.clone()),
+        functions: function_names.clone(),
+        start: run_start,
+        end: Local::now(),
+        files: FileCounts {
+            scanned: file_data.len(),
+            parsed: files_parsed,
+            cached: cache.as_ref().map_or(0, |cache| cache.hits),
+            failed: errors.len(),
+        },
+        findings: findings_written,
+        outputs,
+    };
+    manifest.write(&output_dir, &timestamp)?;
+
+    let elapsed_time = start_time.elapsed(); // Calculate the elapsed time
 
     pb.finish_with_message("done");
     println!("Total time elapsed: {:?}", elapsed_time);
 
+    // --fail-on: a rule that holds fails the run, for CI pipelines
+    let failures = policy.failures();
+    if !failures.is_empty() {
+        for failure in &failures {
+            eprintln!("Policy failed: {}", failure);
+        }
+        std::process::exit(1);
+    }
+
     Ok(())
 }
 
-/* -------------------------
-* This function processes a single file, it does the following:
-* 1. Get the metadata
-* 2. Create and store a FileInfo structure
-* 3. Update the progress bar
-* --------------------------- */
-fn process_dir(dir: &str, file_data: &mut Vec<FileInfo>, pb: &ProgressBar) -> io::Result<()> {
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let metadata = fs::metadata(entry.path())?;
-        if metadata.is_file() {
-            let file_name = String::from(entry.file_name().to_str().unwrap());
-            let file_directory = String::from(dir);
-
-            let create_date = Utc.timestamp_opt(metadata.created()?.duration_since(UNIX_EPOCH).expect("Time went backwards").as_secs() as i64, 0).unwrap();
-            let modify_date = Utc.timestamp_opt(metadata.modified()?.duration_since(UNIX_EPOCH).expect("Time went backwards").as_secs() as i64, 0).unwrap();
-
-            let size = metadata.len();
-            let uuid = Uuid::new_v4().to_string();
-
-            let file_info = FileInfo {
-                uuid: uuid,
-                name: file_name,
-                directory: file_directory,
-                create_date: create_date,
-                modify_date: modify_date,
-                size: size,
-            };
+// Args looks up options on the command line first, then falls back to the config file
+struct Args {
+    matches: Matches,
+    config: Config,
+}
 
-            file_data.push(file_info);
-            pb.inc(1);
-        } else if metadata.is_dir() {
-            process_dir(entry.path().to_str().unwrap(), file_data, pb)?;
+impl Args {
+    fn opt_str(&self, name: &str) -> Option<String> {
+        self.matches.opt_str(name).or_else(|| self.config.get_str(name))
+    }
+
+    fn opt_present(&self, name: &str) -> bool {
+        self.matches.opt_present(name) || self.config.get_flag(name)
+    }
+
+    // opt_strs returns every value of a repeatable option
+    fn opt_strs(&self, name: &str) -> Vec<String> {
+        let values = self.matches.opt_strs(name);
+        if values.is_empty() {
+            self.config.get_list(name).unwrap_or_default()
+        } else {
+            values
         }
     }
-    Ok(())
+}
+
+// parse_globs turns --include/--exclude patterns into Globs, stopping on an invalid pattern
+fn parse_globs(patterns: &[String]) -> Vec<Glob> {
+    patterns.iter().map(|pattern| pattern.parse::<Glob>().unwrap_or_else(|e| panic!("{}", e))).collect()
 }
 
 /* -------------------------
@@ -340,6 +769,115 @@ fn process_dir(dir: &str, file_data: &mut Vec<FileInfo>, pb: &ProgressBar) -> io
 * It's called when the command line arguments are not valid.
 * --------------------------- */
 fn print_usage(opts: &Options) {
-    let brief = format!("Usage: ./text_file_analyzer [options]");
-    print!("{}", opts.usage(&brief));
+    let brief = "Usage: ./text_file_analyzer [options]";
+    print!("{}", opts.usage(brief));
+}
+
+/* -------------------------
+* This function runs the diff subcommand: diff OLD NEW compares the findings of two earlier runs
+* (csv output directories) or two input directories, which are scanned first, and prints the new,
+* removed and changed findings of each file as CSV, or writes them to diff_<timestamp> in -o.
+* --------------------------- */
+fn run_diff(args: &[String]) -> io::Result<()> {
+    let mut opts = Options::new();
+    opts.optopt("o", "output", "write diff_<timestamp> to this directory instead of printing it", "OUTPUT");
+    opts.optopt("f", "format", "set output format: csv, json, jsonl or parquet (default csv)", "FORMAT");
+    opts.optopt("", "functions", "only compare these parse functions (input directories are scanned with them)", "FUNCTIONS");
+    opts.optflag("", "include-comments", "also analyze code inside comments when scanning input directories");
+    opts.optflag("h", "help", "print this help menu");
+    let matches = opts.parse(args).unwrap_or_else(|f| panic!("{}", f));
+    if matches.opt_present("h") || matches.free.len() != 2 {
+        print!("{}", opts.usage("Usage: ./text_file_analyzer diff OLD NEW [options]"));
+        return Ok(());
+    }
+    let functions: Vec<String> = match matches.opt_str("functions") {
+        Some(list) => list.split(',').map(|name| name.trim().to_string()).filter(|name| !name.is_empty()).collect(),
+        None => Vec::new(),
+    };
+    let parse_functions = match functions.is_empty() {
+        true => parsers::default_functions(),
+        false => parsers::select_functions(&functions).unwrap_or_else(|e| panic!("{}", e)),
+    };
+    let scan_options = ScanOptions { include_comments: matches.opt_present("include-comments"), ..ScanOptions::default() };
+    let load = |path: &str| match diff::is_output(path) {
+        true => RunFindings::load(path),
+        false => RunFindings::scan(path, &parse_functions, &scan_options),
+    };
+    let (old, new) = (load(&matches.free[0])?, load(&matches.free[1])?);
+    let rows = diff::diff(&old, &new, &functions);
+
+    match matches.opt_str("output") {
+        Some(output_dir) => {
+            let format = match matches.opt_str("format") {
+                Some(f) => f.parse::<OutputFormat>().unwrap_or_else(|e| panic!("{}", e)),
+                None => OutputFormat::Csv,
+            };
+            let timestamp = Local::now().format("%Y%m%d%H%M%S").to_string();
+            let path = format!("{}/diff_{}.{}", output_dir, timestamp, format.extension());
+            let mut wtr = RecordWriter::create(&path, format, &diff::DIFF_HEADERS)?;
+            for row in &rows {
+                let record = row.record();
+                wtr.write(&record.iter().map(|value| value.as_str()).collect::<Vec<&str>>())?;
+            }
+            wtr.finish()?;
+        }
+        None => {
+            let mut wtr = csv::Writer::from_writer(io::stdout());
+            wtr.write_record(diff::DIFF_HEADERS)?;
+            for row in &rows {
+                wtr.write_record(row.record())?;
+            }
+            wtr.flush()?;
+        }
+    }
+    let count = |status: &str| rows.iter().filter(|row| row.status == status).count();
+    eprintln!("New: {}, removed: {}, changed: {}", count("new"), count("removed"), count("changed"));
+    Ok(())
+}
+
+/* -------------------------
+* This function reads the list of files to analyze, one path per line; blank lines are ignored.
+* --------------------------- */
+fn read_file_list(reader: impl BufRead) -> io::Result<Vec<String>> {
+    let mut paths: Vec<String> = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let path = line.trim();
+        if !path.is_empty() {
+            paths.push(path.to_string());
+        }
+    }
+    Ok(paths)
+}
+
+/* -------------------------
+* This function analyzes a single file and prints its findings to stdout as CSV
+* (function name and result), for a quick look at one program without an output directory.
+* --------------------------- */
+fn print_file_findings(file_path: &str, parse_functions: &[Box<dyn Parser>], scan_options: &ScanOptions) -> io::Result<()> {
+    let mut wtr = csv::Writer::from_writer(io::stdout());
+    wtr.write_record(["func_nm", "result"])?;
+    // an Enterprise Guide project prints the findings of each embedded program in turn
+    for file_info in scanner::file_infos(file_path)? {
+        let content = scanner::load_file(&file_info.path(), scan_options).map_err(io::Error::other)?;
+        for finding in scanner::parse_content(&file_info, &content, parse_functions) {
+            wtr.write_record([&finding.func_nm, &finding.result])?;
+        }
+    }
+    wtr.flush()
+}
+
+/* -------------------------
+* This function prints the name and description of every parse function
+* that can be passed to --functions (in the --mode given), then find_file_name and the
+* functions of --plugins and rules of --rules.
+* --------------------------- */
+fn print_functions(mode: parsers::Mode, extra_parsers: &[Box<dyn Parser>]) {
+    for (name, description, _) in mode.functions() {
+        println!("{:<16} {}", name, description);
+    }
+    println!("{:<16} {}", parsers::FIND_FILE_NAME.0, parsers::FIND_FILE_NAME.1);
+    for parser in extra_parsers {
+        println!("{:<16} {}", parser.name(), parser.describe());
+    }
 }
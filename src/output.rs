@@ -0,0 +1,546 @@
+use crate::parsers::Finding;
+use crate::scanner::FileInfo;
+use chrono::{DateTime, Local};
+use csv::{Writer, WriterBuilder};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::str::FromStr;
+use uuid::Uuid;
+
+pub mod extract;
+pub mod graph;
+pub mod manifest;
+pub mod parquet;
+pub mod report;
+pub mod sqlite;
+
+/* -------------------------
+* Output writers. Two result sets are produced per run:
+* 1. summary - one row per file with its metadata
+* 2. detail - one row per finding produced by the parse functions: its formatted result, and the
+*    same finding as typed fields (line_start, line_end, value, and the other fields as a JSON object
+*    in extra), so consumers don't have to parse the result tuple
+* Each can be written as CSV, a JSON array of objects, JSON lines (one object per line),
+* Parquet (for large scans queried with Spark or DuckDB), or as two tables in a single SQLite database.
+* In append mode (--append) every run adds its rows to the same summary, detail and table outputs
+* (no timestamp in the names), each row starting with the run_id and run_timestamp of its run.
+* Only CSV, JSON lines and SQLite outputs can be appended to.
+* A result or value that spans lines (the PROC SQL blocks of get_sql, the DATA steps of get_datastep,
+* ...) is a quoted, multi-line CSV field, which some tools can't read. --multiline sets how the
+* detail CSV holds them:
+* - keep (the default): as is
+* - escape: backslashes, newlines and carriage returns of every result and value are written as
+*   \\, \n and \r, so each finding is one line
+* - base64: each result or value that spans lines is base64 encoded (standard alphabet, padded)
+*   behind a base64: prefix
+* - json: every finding of the block-valued functions (BLOCK_FUNCTIONS: get_sql, get_datastep and
+*   get_passthrough, whose values are code blocks) goes to a detail_blocks JSON lines output
+*   instead of the detail CSV, so all the rows of a function are in one output; a multi-line
+*   value of any other function stays in the CSV as is
+* --------------------------- */
+
+pub const SUMMARY_HEADERS: [&str; 6] = ["uuid", "file_nm", "file_dir", "create_dt", "modify_dt", "size_bytes"];
+pub const DETAIL_HEADERS: [&str; 7] = ["uuid", "func_nm", "result", "line_start", "line_end", "value", "extra"];
+pub const RUN_HEADERS: [&str; 2] = ["run_id", "run_timestamp"];
+// the parse functions whose values are blocks of code, written to detail_blocks with --multiline json
+pub const BLOCK_FUNCTIONS: [&str; 3] = ["get_sql", "get_datastep", "get_passthrough"];
+
+// Multiline is how the detail CSV holds results and values that span lines (--multiline)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiline {
+    Keep,
+    Escape,
+    Base64,
+    Json,
+}
+
+impl FromStr for Multiline {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Multiline, String> {
+        match s.to_lowercase().as_str() {
+            "keep" => Ok(Multiline::Keep),
+            "escape" => Ok(Multiline::Escape),
+            "base64" => Ok(Multiline::Base64),
+            "json" => Ok(Multiline::Json),
+            _ => Err(format!("Unknown multiline handling: {} (expected keep, escape, base64 or json)", s)),
+        }
+    }
+}
+
+// spans_lines tests whether a value holds a line break
+fn spans_lines(value: &str) -> bool {
+    value.contains(['\n', '\r'])
+}
+
+// escape_lines writes the line breaks and backslashes of a value as \n, \r and \\
+pub fn escape_lines(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// base64 encodes bytes with the standard alphabet and = padding
+pub fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(BASE64_ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
+
+// Run identifies one run in append mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub id: String,
+    pub timestamp: String,
+}
+
+impl Run {
+    pub fn new(start: DateTime<Local>) -> Run {
+        Run { id: Uuid::new_v4().to_string(), timestamp: start.format("%Y-%m-%d %H:%M:%S").to_string() }
+    }
+
+    // headers puts the run columns in front of a table's headers
+    pub fn headers<'a>(&self, headers: &[&'a str]) -> Vec<&'a str> {
+        let mut all = RUN_HEADERS.to_vec();
+        all.extend(headers);
+        all
+    }
+
+    // values puts this run's id and timestamp in front of a row
+    pub fn values<'a>(&'a self, values: &[&'a str]) -> Vec<&'a str> {
+        let mut all = vec![self.id.as_str(), self.timestamp.as_str()];
+        all.extend(values);
+        all
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Csv,
+    Json,
+    Jsonl,
+    Parquet,
+    Sqlite,
+}
+
+impl OutputFormat {
+    // extension is the file extension used for output files of this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Csv => "csv",
+            OutputFormat::Json => "json",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Parquet => "parquet",
+            OutputFormat::Sqlite => "db",
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<OutputFormat, String> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(OutputFormat::Csv),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" => Ok(OutputFormat::Jsonl),
+            "parquet" => Ok(OutputFormat::Parquet),
+            "sqlite" => Ok(OutputFormat::Sqlite),
+            _ => Err(format!("Unknown output format: {} (expected csv, json, jsonl, parquet or sqlite)", s)),
+        }
+    }
+}
+
+// RecordSink is the underlying file writer for each output format
+enum RecordSink {
+    Csv(Box<Writer<File>>),
+    Json { out: BufWriter<File>, first: bool },
+    Jsonl(BufWriter<File>),
+    Parquet(Box<parquet::ParquetWriter>),
+}
+
+// RecordWriter writes rows of string values under a fixed set of headers, in any OutputFormat
+pub struct RecordWriter {
+    headers: Vec<String>,
+    sink: RecordSink,
+}
+
+impl RecordWriter {
+    pub fn create(output_file_path: &str, format: OutputFormat, headers: &[&str]) -> io::Result<RecordWriter> {
+        let sink = match format {
+            OutputFormat::Csv => {
+                let mut wtr = WriterBuilder::new()
+                    .has_headers(true)
+                    .from_path(output_file_path)?;
+                wtr.write_record(headers)?;
+                RecordSink::Csv(Box::new(wtr))
+            }
+            OutputFormat::Json => {
+                let mut out = BufWriter::new(File::create(output_file_path)?);
+                out.write_all(b"[")?;
+                RecordSink::Json { out, first: true }
+            }
+            OutputFormat::Jsonl => RecordSink::Jsonl(BufWriter::new(File::create(output_file_path)?)),
+            OutputFormat::Parquet => RecordSink::Parquet(Box::new(parquet::ParquetWriter::create(output_file_path, headers)?)),
+            OutputFormat::Sqlite => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "sqlite output is written with SqliteSink, not RecordWriter"))
+            }
+        };
+        Ok(RecordWriter {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            sink,
+        })
+    }
+
+    pub fn write(&mut self, values: &[&str]) -> io::Result<()> {
+        match &mut self.sink {
+            RecordSink::Csv(wtr) => wtr.write_record(values)?,
+            RecordSink::Json { out, first } => {
+                out.write_all(if *first { b"\n" } else { b",\n" })?;
+                *first = false;
+                out.write_all(json_object(&self.headers, values).as_bytes())?;
+            }
+            RecordSink::Jsonl(out) => {
+                out.write_all(json_object(&self.headers, values).as_bytes())?;
+                out.write_all(b"\n")?;
+            }
+            RecordSink::Parquet(wtr) => wtr.write(values)?,
+        }
+        Ok(())
+    }
+
+    // append opens an output to add rows to, creating it if it doesn't exist yet. A CSV file that
+    // already has rows must have the same headers.
+    pub fn append(output_file_path: &str, format: OutputFormat, headers: &[&str]) -> io::Result<RecordWriter> {
+        let exists = fs::metadata(output_file_path).is_ok_and(|metadata| metadata.len() > 0);
+        let file = OpenOptions::new().create(true).append(true).open(output_file_path)?;
+        let sink = match format {
+            OutputFormat::Csv => {
+                if exists {
+                    let mut first_line = String::new();
+                    BufReader::new(File::open(output_file_path)?).read_line(&mut first_line)?;
+                    if first_line.trim_end() != headers.join(",") {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("cannot append to {}: its columns are not {}", output_file_path, headers.join(",")),
+                        ));
+                    }
+                }
+                let mut wtr = WriterBuilder::new().has_headers(false).from_writer(file);
+                if !exists {
+                    wtr.write_record(headers)?;
+                }
+                RecordSink::Csv(Box::new(wtr))
+            }
+            OutputFormat::Jsonl => RecordSink::Jsonl(BufWriter::new(file)),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} output cannot be appended to (use csv, jsonl or sqlite)", format.extension()),
+                ))
+            }
+        };
+        Ok(RecordWriter {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            sink,
+        })
+    }
+
+    // finish closes any open JSON array (or writes the Parquet footer) and flushes the file
+    pub fn finish(mut self) -> io::Result<()> {
+        if let RecordSink::Parquet(wtr) = self.sink {
+            return wtr.finish();
+        }
+        match &mut self.sink {
+            RecordSink::Csv(wtr) => wtr.flush(),
+            RecordSink::Json { out, .. } => {
+                out.write_all(b"\n]\n")?;
+                out.flush()
+            }
+            RecordSink::Jsonl(out) => out.flush(),
+            RecordSink::Parquet(_) => Ok(()),
+        }
+    }
+}
+
+// json_object renders header/value pairs as a single-line JSON object with string values
+fn json_object(headers: &[String], values: &[&str]) -> String {
+    let fields: Vec<String> = headers
+        .iter()
+        .zip(values)
+        .map(|(header, value)| format!("{}:{}", json_string(header), json_string(value)))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+// json_string quotes and escapes a value so multi-line text (e.g. SQL blocks) survives verbatim
+pub fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+// detail_record returns the detail output values of a finding
+pub fn detail_record(finding: &Finding) -> Vec<String> {
+    let line = |line: Option<usize>| line.map_or(String::new(), |line| line.to_string());
+    vec![
+        finding.file_id.clone(),
+        finding.func_nm.clone(),
+        finding.result.clone(),
+        line(finding.line_start),
+        line(finding.line_end),
+        finding.value.clone(),
+        extra_json(&finding.extra),
+    ]
+}
+
+// extra_json renders the named fields of a finding as a JSON object
+pub fn extra_json(extra: &[(String, String)]) -> String {
+    let fields: Vec<String> = extra.iter().map(|(name, value)| format!("{}:{}", json_string(name), json_string(value))).collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+pub fn write_summary(output_file_path: &str, format: OutputFormat, file_data: &[FileInfo]) -> io::Result<()> {
+    let mut wtr_summary = RecordWriter::create(output_file_path, format, &SUMMARY_HEADERS)?;
+    for file_info in file_data {
+        let record = summary_record(file_info);
+        let values: Vec<&str> = record.iter().map(|value| value.as_str()).collect();
+        wtr_summary.write(&values)?;
+    }
+    wtr_summary.finish()
+}
+
+// summary_record is the summary row of a file, in SUMMARY_HEADERS order
+pub fn summary_record(file_info: &FileInfo) -> Vec<String> {
+    vec![
+        file_info.uuid.clone(),
+        file_info.name.clone(),
+        file_info.directory.clone(),
+        file_info.create_date.format("%Y-%m-%d %H:%M:%S").to_string(),
+        file_info.modify_date.format("%Y-%m-%d %H:%M:%S").to_string(),
+        file_info.size.to_string(),
+    ]
+}
+
+// the detail_record positions of result and value, the fields that can span lines
+const MULTILINE_FIELDS: [usize; 2] = [2, 5];
+
+// DetailWriter writes findings to the detail output as they are produced, one file at a time
+pub struct DetailWriter {
+    wtr: RecordWriter,
+    run: Option<Run>,
+    multiline: Multiline,
+    // with Multiline::Json, the output of the findings that span lines
+    blocks: Option<RecordWriter>,
+}
+
+impl DetailWriter {
+    pub fn create(output_file_path: &str, format: OutputFormat) -> io::Result<DetailWriter> {
+        let wtr = RecordWriter::create(output_file_path, format, &DETAIL_HEADERS)?;
+        Ok(DetailWriter { wtr, run: None, multiline: Multiline::Keep, blocks: None })
+    }
+
+    // append adds this run's findings to an existing detail output
+    pub fn append(output_file_path: &str, format: OutputFormat, run: &Run) -> io::Result<DetailWriter> {
+        let wtr = RecordWriter::append(output_file_path, format, &run.headers(&DETAIL_HEADERS))?;
+        Ok(DetailWriter { wtr, run: Some(run.clone()), multiline: Multiline::Keep, blocks: None })
+    }
+
+    // multiline sets how results and values that span lines are written; with Multiline::Json
+    // the findings of BLOCK_FUNCTIONS go to a JSON lines output at blocks_path
+    pub fn multiline(mut self, multiline: Multiline, blocks_path: &str) -> io::Result<DetailWriter> {
+        if multiline == Multiline::Json {
+            self.blocks = Some(match &self.run {
+                Some(run) => RecordWriter::append(blocks_path, OutputFormat::Jsonl, &run.headers(&DETAIL_HEADERS))?,
+                None => RecordWriter::create(blocks_path, OutputFormat::Jsonl, &DETAIL_HEADERS)?,
+            });
+        }
+        self.multiline = multiline;
+        Ok(self)
+    }
+
+    pub fn write(&mut self, finding: &Finding) -> io::Result<()> {
+        let mut record = detail_record(finding);
+        let mut wtr = &mut self.wtr;
+        match self.multiline {
+            Multiline::Keep => {}
+            Multiline::Escape => {
+                for i in MULTILINE_FIELDS {
+                    record[i] = escape_lines(&record[i]);
+                }
+            }
+            Multiline::Base64 => {
+                for i in MULTILINE_FIELDS {
+                    if spans_lines(&record[i]) {
+                        record[i] = format!("base64:{}", base64(record[i].as_bytes()));
+                    }
+                }
+            }
+            Multiline::Json => {
+                if BLOCK_FUNCTIONS.contains(&finding.func_nm.as_str()) {
+                    wtr = self.blocks.as_mut().unwrap_or(wtr);
+                }
+            }
+        }
+        let values: Vec<&str> = record.iter().map(|value| value.as_str()).collect();
+        match &self.run {
+            Some(run) => wtr.write(&run.values(&values)),
+            None => wtr.write(&values),
+        }
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        if let Some(blocks) = self.blocks {
+            blocks.finish()?;
+        }
+        self.wtr.finish()
+    }
+}
+
+/* -------------------------
+* ResultSink is where a run sends its results. The summary is written once all files are known,
+* and findings are written one at a time as each file is parsed. Corpus-level results
+* (e.g. the %INCLUDE edge list) are written as additional named tables.
+* --------------------------- */
+pub trait ResultSink {
+    fn write_summary(&mut self, file_data: &[FileInfo]) -> io::Result<()>;
+    fn write_finding(&mut self, finding: &Finding) -> io::Result<()>;
+    fn write_table(&mut self, name: &str, headers: &[&str], rows: &[Vec<String>]) -> io::Result<()>;
+    fn finish(self: Box<Self>) -> io::Result<()>;
+}
+
+// FileSink writes summary_<timestamp>, detail_<timestamp> and <table>_<timestamp> files in a csv, json or parquet format,
+// or in append mode adds to summary, detail and <table> files
+pub struct FileSink {
+    output_dir: String,
+    timestamp: String,
+    format: OutputFormat,
+    detail: DetailWriter,
+    run: Option<Run>,
+}
+
+impl FileSink {
+    pub fn create(output_dir: &str, timestamp: &str, format: OutputFormat, multiline: Multiline) -> io::Result<FileSink> {
+        let detail_path = format!("{}/detail_{}.{}", output_dir, timestamp, format.extension());
+        let blocks_path = format!("{}/detail_blocks_{}.jsonl", output_dir, timestamp);
+        let detail = DetailWriter::create(&detail_path, format)?.multiline(multiline, &blocks_path)?;
+        Ok(FileSink {
+            output_dir: output_dir.to_string(),
+            timestamp: timestamp.to_string(),
+            format,
+            detail,
+            run: None,
+        })
+    }
+
+    // append opens the outputs in append mode, for the rows of this run
+    pub fn append(output_dir: &str, format: OutputFormat, run: &Run, multiline: Multiline) -> io::Result<FileSink> {
+        let detail_path = format!("{}/detail.{}", output_dir, format.extension());
+        let blocks_path = format!("{}/detail_blocks.jsonl", output_dir);
+        let detail = DetailWriter::append(&detail_path, format, run)?.multiline(multiline, &blocks_path)?;
+        Ok(FileSink {
+            output_dir: output_dir.to_string(),
+            timestamp: String::new(),
+            format,
+            detail,
+            run: Some(run.clone()),
+        })
+    }
+
+    fn table_path(&self, name: &str) -> String {
+        match self.run {
+            Some(_) => format!("{}/{}.{}", self.output_dir, name, self.format.extension()),
+            None => format!("{}/{}_{}.{}", self.output_dir, name, self.timestamp, self.format.extension()),
+        }
+    }
+}
+
+impl ResultSink for FileSink {
+    fn write_summary(&mut self, file_data: &[FileInfo]) -> io::Result<()> {
+        if self.run.is_none() {
+            return write_summary(&self.table_path("summary"), self.format, file_data);
+        }
+        let rows: Vec<Vec<String>> = file_data.iter().map(summary_record).collect();
+        self.write_table("summary", &SUMMARY_HEADERS, &rows)
+    }
+
+    fn write_table(&mut self, name: &str, headers: &[&str], rows: &[Vec<String>]) -> io::Result<()> {
+        let mut wtr = match &self.run {
+            Some(run) => RecordWriter::append(&self.table_path(name), self.format, &run.headers(headers))?,
+            None => RecordWriter::create(&self.table_path(name), self.format, headers)?,
+        };
+        for row in rows {
+            let values: Vec<&str> = row.iter().map(|value| value.as_str()).collect();
+            match &self.run {
+                Some(run) => wtr.write(&run.values(&values))?,
+                None => wtr.write(&values)?,
+            }
+        }
+        wtr.finish()
+    }
+
+    fn write_finding(&mut self, finding: &Finding) -> io::Result<()> {
+        self.detail.write(finding)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        self.detail.finish()
+    }
+}
+
+// create_sink opens the output for a run in the requested format; with a run (append mode) the
+// rows are added to the outputs of earlier runs. multiline only applies to csv output.
+pub fn create_sink(
+    output_dir: &str,
+    timestamp: &str,
+    format: OutputFormat,
+    run: Option<&Run>,
+    multiline: Multiline,
+) -> io::Result<Box<dyn ResultSink>> {
+    match (format, run) {
+        (OutputFormat::Sqlite, None) => {
+            let db_path = format!("{}/results_{}.{}", output_dir, timestamp, format.extension());
+            Ok(Box::new(sqlite::SqliteSink::create(&db_path, None)?))
+        }
+        (OutputFormat::Sqlite, Some(run)) => {
+            let db_path = format!("{}/results.{}", output_dir, format.extension());
+            Ok(Box::new(sqlite::SqliteSink::create(&db_path, Some(run))?))
+        }
+        (_, None) => Ok(Box::new(FileSink::create(output_dir, timestamp, format, multiline)?)),
+        (_, Some(run)) => Ok(Box::new(FileSink::append(output_dir, format, run, multiline)?)),
+    }
+}
+
+// appendable says whether a format can be used with --append
+pub fn appendable(format: OutputFormat) -> bool {
+    matches!(format, OutputFormat::Csv | OutputFormat::Jsonl | OutputFormat::Sqlite)
+}
@@ -0,0 +1,64 @@
+use crate::scanner::ids::stable_path;
+use crate::scanner::FileInfo;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/* -------------------------
+* Code extraction: pieces of code found by the parse functions written out one per file, so they
+* can be handed to conversion tooling or reviewed on their own.
+* - extracted_sql/: each PROC SQL block found by get_sql, comments included (with --extract-sql)
+* - extracted_macros/: each %MACRO ... %MEND definition found by get_macros, comments included
+*   (with --extract-macros)
+* A file is named after its source file, relative to the scanned directory with the directory
+* separators as __, the line the code starts on and for a macro its name, e.g.
+* jobs__etl_main_12.sql or jobs__etl_main_40_load_stage.sas. The directories are not timestamped:
+* a later run overwrites the files of the same source code, and files reused from the cache keep
+* the files extracted by the run that parsed them.
+* --------------------------- */
+
+pub const SQL_DIR: &str = "extracted_sql";
+pub const MACRO_DIR: &str = "extracted_macros";
+
+pub struct Extractor {
+    dir: PathBuf,
+    root: String,
+    extension: &'static str,
+    pub written: usize,
+}
+
+impl Extractor {
+    // create makes the <output_dir>/<dir_name> directory; root is the scanned directory the file
+    // names are relative to
+    pub fn create(output_dir: &str, dir_name: &str, root: &str, extension: &'static str) -> io::Result<Extractor> {
+        let dir = PathBuf::from(output_dir).join(dir_name);
+        fs::create_dir_all(&dir)?;
+        Ok(Extractor { dir, root: root.to_string(), extension, written: 0 })
+    }
+
+    // file_name is the name of the file holding the code of file_info that starts on start_line,
+    // followed by the label when there is one
+    pub fn file_name(&self, file_info: &FileInfo, start_line: usize, label: &str) -> String {
+        let relative = stable_path(&self.root, &file_info.path());
+        let stem = match relative.rsplit_once('.') {
+            Some((stem, _)) if !stem.is_empty() && !stem.ends_with('/') => stem.to_string(),
+            _ => relative.clone(),
+        };
+        let label: String = label.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '_').collect();
+        match label.is_empty() {
+            true => format!("{}_{}.{}", stem.replace('/', "__"), start_line, self.extension),
+            false => format!("{}_{}_{}.{}", stem.replace('/', "__"), start_line, label, self.extension),
+        }
+    }
+
+    pub fn write(&mut self, file_info: &FileInfo, start_line: usize, label: &str, text: &str) -> io::Result<()> {
+        let path = self.dir.join(self.file_name(file_info, start_line, label));
+        let mut code = text.to_string();
+        if !code.ends_with('\n') {
+            code.push('\n');
+        }
+        fs::write(path, code)?;
+        self.written += 1;
+        Ok(())
+    }
+}
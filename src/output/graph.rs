@@ -0,0 +1,206 @@
+use crate::parsers::includes::IncludeEdge;
+use crate::parsers::lineage::LineageEdge;
+use crate::parsers::macros::MacroCallEdge;
+use crate::scanner::FileInfo;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::str::FromStr;
+
+/* -------------------------
+* Graph export: the dependency style results (the %INCLUDE edge list, the macro call graph and
+* dataset lineage) written as <graph>_<timestamp>.dot for Graphviz or .graphml for yEd and Gephi.
+* Nodes carry a label and a kind (file, macro, dataset, ...) and edges a label, so the
+* graphs can be styled in the viewer.
+* --------------------------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Graphml,
+}
+
+impl GraphFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "dot",
+            GraphFormat::Graphml => "graphml",
+        }
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<GraphFormat, String> {
+        match s.to_lowercase().as_str() {
+            "dot" => Ok(GraphFormat::Dot),
+            "graphml" => Ok(GraphFormat::Graphml),
+            _ => Err(format!("Unknown graph format: {} (expected dot or graphml)", s)),
+        }
+    }
+}
+
+// GraphNode is a node of an exported graph; id is unique within the graph
+pub struct GraphNode {
+    pub id: String,
+    pub label: String,
+    pub kind: String,
+}
+
+// GraphEdge links two nodes, by their index in Graph::nodes
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    pub label: String,
+}
+
+pub struct Graph {
+    pub name: String,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    index: HashMap<String, usize>,
+}
+
+impl Graph {
+    pub fn new(name: &str) -> Graph {
+        Graph { name: name.to_string(), nodes: Vec::new(), edges: Vec::new(), index: HashMap::new() }
+    }
+
+    // add_node returns the index of the node with this id, adding it the first time it is seen
+    pub fn add_node(&mut self, id: &str, label: &str, kind: &str) -> usize {
+        if let Some(&i) = self.index.get(id) {
+            return i;
+        }
+        self.nodes.push(GraphNode { id: id.to_string(), label: label.to_string(), kind: kind.to_string() });
+        self.index.insert(id.to_string(), self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize, label: &str) {
+        self.edges.push(GraphEdge { from, to, label: label.to_string() });
+    }
+
+    // from_includes builds the file -> included file graph; unresolved targets are nodes of their own
+    pub fn from_includes(edges: &[IncludeEdge]) -> Graph {
+        let mut graph = Graph::new("includes");
+        for edge in edges {
+            let from = graph.add_node(&edge.source_uuid, &edge.source_file, "file");
+            let to = if edge.target_uuid.is_empty() {
+                graph.add_node(&format!("{}:{}", edge.status, edge.include), &edge.include, &edge.status)
+            } else {
+                graph.add_node(&edge.target_uuid, &edge.target_file, "file")
+            };
+            graph.add_edge(from, to, &edge.line.to_string());
+        }
+        graph
+    }
+
+    // from_macro_calls builds the caller -> callee macro graph. Calls made in open code come from
+    // the file node; macros with the same name in different files are one node.
+    pub fn from_macro_calls(edges: &[MacroCallEdge]) -> Graph {
+        let mut graph = Graph::new("macro_calls");
+        for edge in edges {
+            let from = if edge.caller.is_empty() {
+                graph.add_node(&edge.caller_uuid, &edge.caller_file, "file")
+            } else {
+                graph.add_node(&format!("macro:{}", edge.caller.to_lowercase()), &edge.caller, "macro")
+            };
+            let kind = if edge.status == "undefined" { "undefined_macro" } else { "macro" };
+            let to = graph.add_node(&format!("macro:{}", edge.callee.to_lowercase()), &edge.callee, kind);
+            graph.add_edge(from, to, &edge.status);
+        }
+        graph
+    }
+
+    // from_lineage builds the input dataset -> output dataset graph across all files.
+    // Dataset names are not case sensitive.
+    pub fn from_lineage(lineage: &[(&FileInfo, Vec<LineageEdge>)]) -> Graph {
+        let mut graph = Graph::new("lineage");
+        for (file_info, edges) in lineage {
+            // a step with no input dataset (or no output) has nothing to connect
+            for edge in edges.iter().filter(|e| !e.input.is_empty() && !e.output.is_empty()) {
+                let from = graph.add_node(&edge.input.to_uppercase(), &edge.input, "dataset");
+                let to = graph.add_node(&edge.output.to_uppercase(), &edge.output, "dataset");
+                graph.add_edge(from, to, &format!("{} {}:{}", edge.kind, file_info.name, edge.line));
+            }
+        }
+        graph
+    }
+
+    pub fn dot(&self) -> String {
+        let mut dot = format!("digraph {} {{\n  rankdir=LR;\n", self.name);
+        for (i, node) in self.nodes.iter().enumerate() {
+            let shape = match node.kind.as_str() {
+                "file" => "box",
+                "dataset" => "cylinder",
+                "macro" => "ellipse",
+                _ => "octagon",
+            };
+            dot.push_str(&format!("  n{} [label={}, shape={}, kind={}];\n", i, dot_string(&node.label), shape, dot_string(&node.kind)));
+        }
+        for edge in &self.edges {
+            dot.push_str(&format!("  n{} -> n{} [label={}];\n", edge.from, edge.to, dot_string(&edge.label)));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn graphml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        xml.push_str("  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n");
+        xml.push_str("  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n");
+        xml.push_str("  <key id=\"edge_label\" for=\"edge\" attr.name=\"label\" attr.type=\"string\"/>\n");
+        xml.push_str(&format!("  <graph id=\"{}\" edgedefault=\"directed\">\n", escape_xml(&self.name)));
+        for (i, node) in self.nodes.iter().enumerate() {
+            xml.push_str(&format!(
+                "    <node id=\"n{}\"><data key=\"label\">{}</data><data key=\"kind\">{}</data></node>\n",
+                i,
+                escape_xml(&node.label),
+                escape_xml(&node.kind)
+            ));
+        }
+        for (i, edge) in self.edges.iter().enumerate() {
+            xml.push_str(&format!(
+                "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\"><data key=\"edge_label\">{}</data></edge>\n",
+                i,
+                edge.from,
+                edge.to,
+                escape_xml(&edge.label)
+            ));
+        }
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    // write saves the graph as <output_dir>/<name>_<timestamp>.<ext>
+    pub fn write(&self, output_dir: &str, timestamp: &str, format: GraphFormat) -> io::Result<()> {
+        let path = format!("{}/{}_{}.{}", output_dir, self.name, timestamp, format.extension());
+        let text = match format {
+            GraphFormat::Dot => self.dot(),
+            GraphFormat::Graphml => self.graphml(),
+        };
+        fs::write(path, text)
+    }
+}
+
+// dot_string quotes a DOT attribute value
+fn dot_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
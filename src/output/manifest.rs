@@ -0,0 +1,113 @@
+use super::json_string;
+use chrono::{DateTime, Local};
+use std::fs;
+use std::io;
+
+/* -------------------------
+* Run manifest: manifest_<timestamp>.json records how a run was made and what it wrote — the
+* tool version, the command line and config file, the resolved input, output, format and parse
+* functions, the commit of a git input, start and end time, file counts and the output files — so a set of results can
+* still be traced back to the run that produced it months later. In append mode the run_id of the
+* run's rows is recorded too.
+* --------------------------- */
+
+#[derive(Debug, Clone, Default)]
+pub struct FileCounts {
+    // files selected for the run
+    pub scanned: usize,
+    // files read and parsed in this run
+    pub parsed: usize,
+    // files whose results came from the cache
+    pub cached: usize,
+    // files that could not be read or were skipped (see errors_<timestamp>.csv)
+    pub failed: usize,
+}
+
+pub struct Manifest {
+    pub arguments: Vec<String>,
+    pub config: Option<String>,
+    pub input: String,
+    // the commit scanned, for a git repository input
+    pub commit: Option<String>,
+    pub output: String,
+    pub format: String,
+    // the run_id column value of this run's rows, in append mode
+    pub run_id: Option<String>,
+    pub functions: Vec<String>,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub files: FileCounts,
+    pub findings: usize,
+    pub outputs: Vec<String>,
+}
+
+impl Manifest {
+    pub fn json(&self) -> String {
+        let strings = |values: &[String]| values.iter().map(|value| json_string(value)).collect::<Vec<String>>().join(", ");
+        let mut json = String::from("{\n");
+        json.push_str(&format!("  \"tool\": {},\n", json_string(env!("CARGO_PKG_NAME"))));
+        json.push_str(&format!("  \"version\": {},\n", json_string(env!("CARGO_PKG_VERSION"))));
+        json.push_str(&format!("  \"arguments\": [{}],\n", strings(&self.arguments)));
+        json.push_str(&format!("  \"config\": {},\n", self.config.as_deref().map(json_string).unwrap_or_else(|| String::from("null"))));
+        json.push_str(&format!("  \"input\": {},\n", json_string(&self.input)));
+        json.push_str(&format!("  \"commit\": {},\n", self.commit.as_deref().map(json_string).unwrap_or_else(|| String::from("null"))));
+        json.push_str(&format!("  \"output\": {},\n", json_string(&self.output)));
+        json.push_str(&format!("  \"format\": {},\n", json_string(&self.format)));
+        json.push_str(&format!("  \"run_id\": {},\n", self.run_id.as_deref().map(json_string).unwrap_or_else(|| String::from("null"))));
+        json.push_str(&format!("  \"functions\": [{}],\n", strings(&self.functions)));
+        json.push_str(&format!("  \"start\": {},\n", json_string(&self.start.to_rfc3339())));
+        json.push_str(&format!("  \"end\": {},\n", json_string(&self.end.to_rfc3339())));
+        json.push_str(&format!("  \"elapsed_seconds\": {:.3},\n", (self.end - self.start).num_milliseconds() as f64 / 1000.0));
+        json.push_str(&format!(
+            "  \"files\": {{\"scanned\": {}, \"parsed\": {}, \"cached\": {}, \"failed\": {}}},\n",
+            self.files.scanned, self.files.parsed, self.files.cached, self.files.failed
+        ));
+        json.push_str(&format!("  \"findings\": {},\n", self.findings));
+        json.push_str(&format!("  \"outputs\": [{}]\n", strings(&self.outputs)));
+        json.push_str("}\n");
+        json
+    }
+
+    // write saves the manifest as <output_dir>/manifest_<timestamp>.json
+    pub fn write(&self, output_dir: &str, timestamp: &str) -> io::Result<()> {
+        fs::write(manifest_path(output_dir, timestamp), self.json())
+    }
+}
+
+pub fn manifest_path(output_dir: &str, timestamp: &str) -> String {
+    format!("{}/manifest_{}.json", output_dir, timestamp)
+}
+
+// run_outputs lists the files of the output directory written by the run with this timestamp
+// (every output file name ends in _<timestamp>.<extension>)
+pub fn run_outputs(output_dir: &str, timestamp: &str) -> io::Result<Vec<String>> {
+    let marker = format!("_{}.", timestamp);
+    let mut outputs: Vec<String> = Vec::new();
+    for entry in fs::read_dir(output_dir)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        if name.contains(&marker) {
+            outputs.push(name);
+        }
+    }
+    outputs.sort();
+    Ok(outputs)
+}
+
+// appended_outputs lists the files of the output directory that runs in append mode add to: the
+// files with this extension and no _<timestamp> in their name
+pub fn appended_outputs(output_dir: &str, extension: &str) -> io::Result<Vec<String>> {
+    let suffix = format!(".{}", extension);
+    let mut outputs: Vec<String> = Vec::new();
+    for entry in fs::read_dir(output_dir)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        let Some(stem) = name.strip_suffix(&suffix) else { continue };
+        let timestamped = stem
+            .rsplit_once('_')
+            .is_some_and(|(_, timestamp)| timestamp.len() == 14 && timestamp.chars().all(|c| c.is_ascii_digit()));
+        if !timestamped {
+            outputs.push(name);
+        }
+    }
+    outputs.sort();
+    Ok(outputs)
+}
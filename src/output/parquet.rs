@@ -0,0 +1,277 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/* -------------------------
+* Parquet writer for the result tables. Every output column is text, so each file has one flat
+* schema of required UTF8 (STRING) columns. Rows are buffered and written as a row group every
+* ROW_GROUP_ROWS rows, each column chunk holding a single uncompressed PLAIN encoded data page.
+* The file metadata is encoded with the Thrift compact protocol, as the Parquet format requires.
+* This covers what the result tables need and is readable by Spark, DuckDB, pyarrow and others.
+* --------------------------- */
+
+const MAGIC: &[u8] = b"PAR1";
+const ROW_GROUP_ROWS: usize = 100_000;
+
+// parquet.thrift enum values used by the writer
+const TYPE_BYTE_ARRAY: i32 = 6;
+const REPETITION_REQUIRED: i32 = 0;
+const CONVERTED_TYPE_UTF8: i32 = 0;
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+const CODEC_UNCOMPRESSED: i32 = 0;
+const PAGE_TYPE_DATA: i32 = 0;
+
+// ColumnChunkMeta is what the footer needs to know about a written column chunk
+struct ColumnChunkMeta {
+    offset: i64,
+    num_values: i64,
+    total_size: i64,
+}
+
+// RowGroupMeta is what the footer needs to know about a written row group
+struct RowGroupMeta {
+    columns: Vec<ColumnChunkMeta>,
+    total_byte_size: i64,
+    num_rows: i64,
+}
+
+pub struct ParquetWriter {
+    out: BufWriter<File>,
+    headers: Vec<String>,
+    // buffered column values of the current row group
+    columns: Vec<Vec<String>>,
+    buffered_rows: usize,
+    offset: i64,
+    row_groups: Vec<RowGroupMeta>,
+}
+
+impl ParquetWriter {
+    pub fn create(output_file_path: &str, headers: &[&str]) -> io::Result<ParquetWriter> {
+        let mut out = BufWriter::new(File::create(output_file_path)?);
+        out.write_all(MAGIC)?;
+        Ok(ParquetWriter {
+            out,
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            columns: vec![Vec::new(); headers.len()],
+            buffered_rows: 0,
+            offset: MAGIC.len() as i64,
+            row_groups: Vec::new(),
+        })
+    }
+
+    pub fn write(&mut self, values: &[&str]) -> io::Result<()> {
+        for (i, column) in self.columns.iter_mut().enumerate() {
+            column.push(values.get(i).copied().unwrap_or("").to_string());
+        }
+        self.buffered_rows += 1;
+        if self.buffered_rows >= ROW_GROUP_ROWS {
+            self.flush_row_group()?;
+        }
+        Ok(())
+    }
+
+    // flush_row_group writes the buffered rows as one row group
+    fn flush_row_group(&mut self) -> io::Result<()> {
+        if self.buffered_rows == 0 {
+            return Ok(());
+        }
+        let mut chunks: Vec<ColumnChunkMeta> = Vec::new();
+        for column in &mut self.columns {
+            // PLAIN encoded BYTE_ARRAY values: 4 byte little-endian length, then the bytes
+            let mut data: Vec<u8> = Vec::new();
+            for value in column.iter() {
+                data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+                data.extend_from_slice(value.as_bytes());
+            }
+            let header = page_header(column.len(), data.len());
+            self.out.write_all(&header)?;
+            self.out.write_all(&data)?;
+            let total_size = (header.len() + data.len()) as i64;
+            chunks.push(ColumnChunkMeta { offset: self.offset, num_values: column.len() as i64, total_size });
+            self.offset += total_size;
+            column.clear();
+        }
+        self.row_groups.push(RowGroupMeta {
+            total_byte_size: chunks.iter().map(|c| c.total_size).sum(),
+            num_rows: self.buffered_rows as i64,
+            columns: chunks,
+        });
+        self.buffered_rows = 0;
+        Ok(())
+    }
+
+    // finish writes the last row group and the footer: file metadata, its length and the magic number
+    pub fn finish(mut self) -> io::Result<()> {
+        self.flush_row_group()?;
+        let metadata = self.file_metadata();
+        self.out.write_all(&metadata)?;
+        self.out.write_all(&(metadata.len() as u32).to_le_bytes())?;
+        self.out.write_all(MAGIC)?;
+        self.out.flush()
+    }
+
+    fn file_metadata(&self) -> Vec<u8> {
+        let mut w = CompactWriter::new();
+        w.i32_field(1, 1);
+        // schema: the root element, then one required UTF8 column per header
+        w.list_field(2, CT_STRUCT, self.headers.len() + 1);
+        w.struct_begin();
+        w.binary_field(4, b"schema");
+        w.i32_field(5, self.headers.len() as i32);
+        w.struct_end();
+        for header in &self.headers {
+            w.struct_begin();
+            w.i32_field(1, TYPE_BYTE_ARRAY);
+            w.i32_field(3, REPETITION_REQUIRED);
+            w.binary_field(4, header.as_bytes());
+            w.i32_field(6, CONVERTED_TYPE_UTF8);
+            // logicalType: the STRING member of the LogicalType union, an empty struct
+            w.field_begin(10, CT_STRUCT);
+            w.struct_begin();
+            w.field_begin(1, CT_STRUCT);
+            w.struct_begin();
+            w.struct_end();
+            w.struct_end();
+            w.struct_end();
+        }
+        w.i64_field(3, self.row_groups.iter().map(|g| g.num_rows).sum());
+        w.list_field(4, CT_STRUCT, self.row_groups.len());
+        for group in &self.row_groups {
+            w.struct_begin();
+            w.list_field(1, CT_STRUCT, group.columns.len());
+            for (chunk, header) in group.columns.iter().zip(&self.headers) {
+                w.struct_begin();
+                w.i64_field(2, chunk.offset);
+                w.field_begin(3, CT_STRUCT);
+                w.struct_begin();
+                w.i32_field(1, TYPE_BYTE_ARRAY);
+                w.list_field(2, CT_I32, 2);
+                w.i32(ENCODING_PLAIN);
+                w.i32(ENCODING_RLE);
+                w.list_field(3, CT_BINARY, 1);
+                w.binary(header.as_bytes());
+                w.i32_field(4, CODEC_UNCOMPRESSED);
+                w.i64_field(5, chunk.num_values);
+                w.i64_field(6, chunk.total_size);
+                w.i64_field(7, chunk.total_size);
+                w.i64_field(9, chunk.offset);
+                w.struct_end();
+                w.struct_end();
+            }
+            w.i64_field(2, group.total_byte_size);
+            w.i64_field(3, group.num_rows);
+            w.struct_end();
+        }
+        w.binary_field(6, b"sas_parser_rust");
+        w.stop();
+        w.buf
+    }
+}
+
+// page_header encodes the PageHeader of an uncompressed PLAIN data page
+fn page_header(num_values: usize, data_len: usize) -> Vec<u8> {
+    let mut w = CompactWriter::new();
+    w.i32_field(1, PAGE_TYPE_DATA);
+    w.i32_field(2, data_len as i32);
+    w.i32_field(3, data_len as i32);
+    w.field_begin(5, CT_STRUCT);
+    w.struct_begin();
+    w.i32_field(1, num_values as i32);
+    w.i32_field(2, ENCODING_PLAIN);
+    w.i32_field(3, ENCODING_RLE);
+    w.i32_field(4, ENCODING_RLE);
+    w.struct_end();
+    w.stop();
+    w.buf
+}
+
+// Thrift compact protocol type ids
+const CT_I32: u8 = 5;
+const CT_I64: u8 = 6;
+const CT_BINARY: u8 = 8;
+const CT_STRUCT: u8 = 12;
+
+// CompactWriter encodes Thrift structs with the compact protocol. Field ids are written as a
+// delta from the previous field of the same struct, so each open struct keeps its last field id.
+struct CompactWriter {
+    buf: Vec<u8>,
+    last_field: Vec<i16>,
+    current_field: i16,
+}
+
+impl CompactWriter {
+    fn new() -> CompactWriter {
+        CompactWriter { buf: Vec::new(), last_field: Vec::new(), current_field: 0 }
+    }
+
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    fn i32(&mut self, value: i32) {
+        self.varint(((value << 1) ^ (value >> 31)) as u32 as u64);
+    }
+
+    fn i64(&mut self, value: i64) {
+        self.varint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn binary(&mut self, value: &[u8]) {
+        self.varint(value.len() as u64);
+        self.buf.extend_from_slice(value);
+    }
+
+    fn field_begin(&mut self, id: i16, field_type: u8) {
+        let delta = id - self.current_field;
+        if (1..=15).contains(&delta) {
+            self.buf.push(((delta as u8) << 4) | field_type);
+        } else {
+            self.buf.push(field_type);
+            self.i32(id as i32);
+        }
+        self.current_field = id;
+    }
+
+    fn i32_field(&mut self, id: i16, value: i32) {
+        self.field_begin(id, CT_I32);
+        self.i32(value);
+    }
+
+    fn i64_field(&mut self, id: i16, value: i64) {
+        self.field_begin(id, CT_I64);
+        self.i64(value);
+    }
+
+    fn binary_field(&mut self, id: i16, value: &[u8]) {
+        self.field_begin(id, CT_BINARY);
+        self.binary(value);
+    }
+
+    fn list_field(&mut self, id: i16, element_type: u8, size: usize) {
+        self.field_begin(id, 9);
+        if size < 15 {
+            self.buf.push(((size as u8) << 4) | element_type);
+        } else {
+            self.buf.push(0xf0 | element_type);
+            self.varint(size as u64);
+        }
+    }
+
+    fn struct_begin(&mut self) {
+        self.last_field.push(self.current_field);
+        self.current_field = 0;
+    }
+
+    fn struct_end(&mut self) {
+        self.stop();
+        self.current_field = self.last_field.pop().unwrap_or(0);
+    }
+
+    fn stop(&mut self) {
+        self.buf.push(0);
+    }
+}
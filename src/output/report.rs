@@ -0,0 +1,336 @@
+use super::ResultSink;
+use crate::parsers::Finding;
+use crate::scanner::FileInfo;
+use chrono::Local;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::str::FromStr;
+
+/* -------------------------
+* Run reports: a human readable summary of a run, written next to the regular output.
+* ReportSink passes everything through to the real sink and keeps a copy of the summary,
+* findings and table sizes; when the run finishes it writes report_<timestamp>.<ext>.
+* - html: a single self-contained HTML page with the file counts, per-function totals, the
+*   top files by SQL count and a table of every finding linked to its full extracted text.
+* - markdown: overall counts, per-directory rollups and the notable findings (credentials,
+*   hardcoded paths, database connections), for pasting into wikis and pull requests.
+* --------------------------- */
+
+// how many files are listed in the top files by SQL count
+const TOP_FILES: usize = 10;
+// parse functions whose findings are listed as notable in the markdown report, and how many are listed
+const NOTABLE_FUNCTIONS: &[&str] = &["get_credentials", "get_connections", "get_passthrough", "find_paths"];
+const NOTABLE_LIMIT: usize = 50;
+// findings longer than this are shortened in the findings table (the full text is linked)
+const PREVIEW_CHARS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Html,
+    Markdown,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Markdown => "md",
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ReportFormat, String> {
+        match s.to_lowercase().as_str() {
+            "html" => Ok(ReportFormat::Html),
+            "markdown" | "md" => Ok(ReportFormat::Markdown),
+            _ => Err(format!("Unknown report format: {} (expected html or markdown)", s)),
+        }
+    }
+}
+
+// ReportSink forwards results to another sink and writes the run reports when it finishes
+pub struct ReportSink {
+    inner: Box<dyn ResultSink>,
+    output_dir: String,
+    timestamp: String,
+    formats: Vec<ReportFormat>,
+    files: Vec<FileInfo>,
+    findings: Vec<Finding>,
+    tables: Vec<(String, usize)>,
+}
+
+impl ReportSink {
+    pub fn new(inner: Box<dyn ResultSink>, output_dir: &str, timestamp: &str, formats: Vec<ReportFormat>) -> ReportSink {
+        ReportSink {
+            inner,
+            output_dir: output_dir.to_string(),
+            timestamp: timestamp.to_string(),
+            formats,
+            files: Vec::new(),
+            findings: Vec::new(),
+            tables: Vec::new(),
+        }
+    }
+}
+
+impl ResultSink for ReportSink {
+    fn write_summary(&mut self, file_data: &[FileInfo]) -> io::Result<()> {
+        self.files.extend_from_slice(file_data);
+        self.inner.write_summary(file_data)
+    }
+
+    fn write_finding(&mut self, finding: &Finding) -> io::Result<()> {
+        self.findings.push(finding.clone());
+        self.inner.write_finding(finding)
+    }
+
+    fn write_table(&mut self, name: &str, headers: &[&str], rows: &[Vec<String>]) -> io::Result<()> {
+        self.tables.push((name.to_string(), rows.len()));
+        self.inner.write_table(name, headers, rows)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        let sink = *self;
+        sink.inner.finish()?;
+        let report = Report::new(&sink.files, &sink.findings, &sink.tables);
+        for format in &sink.formats {
+            let path = format!("{}/report_{}.{}", sink.output_dir, sink.timestamp, format.extension());
+            let text = match format {
+                ReportFormat::Html => report.html(),
+                ReportFormat::Markdown => report.markdown(),
+            };
+            fs::write(path, text)?;
+        }
+        Ok(())
+    }
+}
+
+// Report holds the figures shown in every report format
+pub struct Report<'a> {
+    pub files: &'a [FileInfo],
+    pub findings: &'a [Finding],
+    pub tables: &'a [(String, usize)],
+    // function name -> (findings, files with at least one finding), in function name order
+    pub function_totals: BTreeMap<String, (usize, usize)>,
+    // (file, SQL block count), largest first
+    pub top_sql_files: Vec<(&'a FileInfo, usize)>,
+    paths: HashMap<&'a str, String>,
+}
+
+impl<'a> Report<'a> {
+    pub fn new(files: &'a [FileInfo], findings: &'a [Finding], tables: &'a [(String, usize)]) -> Report<'a> {
+        let mut function_totals: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+        let mut last: Option<(&str, &str)> = None;
+        for finding in findings {
+            let totals = function_totals.entry(finding.func_nm.clone()).or_default();
+            totals.0 += 1;
+            // findings arrive file by file, so a new file for a function shows up as a change of file id
+            if last != Some((finding.file_id.as_str(), finding.func_nm.as_str())) {
+                totals.1 += 1;
+            }
+            last = Some((finding.file_id.as_str(), finding.func_nm.as_str()));
+        }
+
+        let by_uuid: HashMap<&str, &FileInfo> = files.iter().map(|f| (f.uuid.as_str(), f)).collect();
+        let mut top_sql_files: Vec<(&FileInfo, usize)> = findings
+            .iter()
+            .filter(|f| f.func_nm == "sql_count")
+            .filter_map(|f| Some((*by_uuid.get(f.file_id.as_str())?, f.result.parse::<usize>().ok()?)))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        top_sql_files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.path().cmp(&b.0.path())));
+        top_sql_files.truncate(TOP_FILES);
+
+        let paths = files.iter().map(|f| (f.uuid.as_str(), f.path())).collect();
+        Report { files, findings, tables, function_totals, top_sql_files, paths }
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+
+    // file_path returns the path of the file a finding came from
+    fn file_path(&self, file_id: &str) -> String {
+        self.paths.get(file_id).cloned().unwrap_or_else(|| file_id.to_string())
+    }
+
+    pub fn html(&self) -> String {
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>SAS code scan report</title>\n");
+        html.push_str("<style>\nbody{font-family:sans-serif;margin:2em;color:#222}\ntable{border-collapse:collapse;margin-bottom:2em}\n");
+        html.push_str("th,td{border:1px solid #ccc;padding:4px 8px;text-align:left;vertical-align:top}\nth{background:#eee}\n");
+        html.push_str("td.num{text-align:right}\npre{background:#f6f6f6;padding:8px;overflow-x:auto}\n</style>\n</head>\n<body>\n");
+        html.push_str("<h1>SAS code scan report</h1>\n");
+        html.push_str(&format!("<p>Generated {}</p>\n", Local::now().format("%Y-%m-%d %H:%M:%S")));
+
+        html.push_str("<h2>Overview</h2>\n<table>\n");
+        html.push_str(&format!("<tr><th>Files scanned</th><td class=\"num\">{}</td></tr>\n", self.files.len()));
+        html.push_str(&format!("<tr><th>Total size (bytes)</th><td class=\"num\">{}</td></tr>\n", self.total_bytes()));
+        html.push_str(&format!("<tr><th>Findings</th><td class=\"num\">{}</td></tr>\n", self.findings.len()));
+        for (name, rows) in self.tables {
+            html.push_str(&format!("<tr><th>{} rows</th><td class=\"num\">{}</td></tr>\n", escape_html(name), rows));
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>Findings by function</h2>\n<table>\n<tr><th>Function</th><th>Findings</th><th>Files</th></tr>\n");
+        for (name, (findings, files)) in &self.function_totals {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+                escape_html(name),
+                findings,
+                files
+            ));
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>Top files by SQL count</h2>\n");
+        if self.top_sql_files.is_empty() {
+            html.push_str("<p>No PROC SQL blocks found (or sql_count was not run).</p>\n");
+        } else {
+            html.push_str("<table>\n<tr><th>File</th><th>SQL blocks</th></tr>\n");
+            for (file, count) in &self.top_sql_files {
+                html.push_str(&format!("<tr><td>{}</td><td class=\"num\">{}</td></tr>\n", escape_html(&file.path()), count));
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>Findings</h2>\n<table>\n<tr><th>#</th><th>File</th><th>Function</th><th>Result</th></tr>\n");
+        let mut snippets = String::new();
+        for (i, finding) in self.findings.iter().enumerate() {
+            let preview = preview(&finding.result);
+            let result = if preview == finding.result {
+                escape_html(&finding.result)
+            } else {
+                snippets.push_str(&format!(
+                    "<h3 id=\"snippet-{}\">#{} {} &mdash; {}</h3>\n<pre>{}</pre>\n",
+                    i + 1,
+                    i + 1,
+                    escape_html(&finding.func_nm),
+                    escape_html(&self.file_path(&finding.file_id)),
+                    escape_html(&finding.result)
+                ));
+                format!("<a href=\"#snippet-{}\">{}</a>", i + 1, escape_html(&preview))
+            };
+            html.push_str(&format!(
+                "<tr><td class=\"num\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                i + 1,
+                escape_html(&self.file_path(&finding.file_id)),
+                escape_html(&finding.func_nm),
+                result
+            ));
+        }
+        html.push_str("</table>\n");
+        if !snippets.is_empty() {
+            html.push_str("<h2>Extracted snippets</h2>\n");
+            html.push_str(&snippets);
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+impl Report<'_> {
+    // directory_rollups returns (directory, files, bytes, lines, SQL blocks, findings) per directory,
+    // in directory order. Lines and SQL blocks come from the line_count and sql_count findings.
+    pub fn directory_rollups(&self) -> Vec<(String, usize, u64, usize, usize, usize)> {
+        let directory_of: HashMap<&str, &str> = self.files.iter().map(|f| (f.uuid.as_str(), f.directory.as_str())).collect();
+        let mut rollups: BTreeMap<&str, (usize, u64, usize, usize, usize)> = BTreeMap::new();
+        for file in self.files {
+            let rollup = rollups.entry(file.directory.as_str()).or_default();
+            rollup.0 += 1;
+            rollup.1 += file.size;
+        }
+        for finding in self.findings {
+            let Some(rollup) = directory_of.get(finding.file_id.as_str()).and_then(|d| rollups.get_mut(d)) else {
+                continue;
+            };
+            rollup.4 += 1;
+            match finding.func_nm.as_str() {
+                "line_count" => rollup.2 += finding.result.parse::<usize>().unwrap_or(0),
+                "sql_count" => rollup.3 += finding.result.parse::<usize>().unwrap_or(0),
+                _ => {}
+            }
+        }
+        rollups
+            .into_iter()
+            .map(|(directory, (files, bytes, lines, sql, findings))| (directory.to_string(), files, bytes, lines, sql, findings))
+            .collect()
+    }
+
+    pub fn markdown(&self) -> String {
+        let mut md = String::new();
+        md.push_str("# SAS code scan report\n\n");
+        md.push_str(&format!("Generated {}\n\n", Local::now().format("%Y-%m-%d %H:%M:%S")));
+
+        md.push_str("## Overview\n\n| | |\n|---|---:|\n");
+        md.push_str(&format!("| Files scanned | {} |\n", self.files.len()));
+        md.push_str(&format!("| Total size (bytes) | {} |\n", self.total_bytes()));
+        md.push_str(&format!("| Findings | {} |\n", self.findings.len()));
+        for (name, rows) in self.tables {
+            md.push_str(&format!("| {} rows | {} |\n", escape_markdown(name), rows));
+        }
+
+        md.push_str("\n## Findings by function\n\n| Function | Findings | Files |\n|---|---:|---:|\n");
+        for (name, (findings, files)) in &self.function_totals {
+            md.push_str(&format!("| {} | {} | {} |\n", escape_markdown(name), findings, files));
+        }
+
+        md.push_str("\n## By directory\n\n| Directory | Files | Bytes | Lines | SQL blocks | Findings |\n|---|---:|---:|---:|---:|---:|\n");
+        for (directory, files, bytes, lines, sql, findings) in self.directory_rollups() {
+            md.push_str(&format!("| {} | {} | {} | {} | {} | {} |\n", escape_markdown(&directory), files, bytes, lines, sql, findings));
+        }
+
+        let notable: Vec<&Finding> = self.findings.iter().filter(|f| NOTABLE_FUNCTIONS.contains(&f.func_nm.as_str())).collect();
+        md.push_str("\n## Notable findings\n\n");
+        if notable.is_empty() {
+            md.push_str("No credentials, hardcoded paths or database connections found.\n");
+        } else {
+            md.push_str("| File | Function | Result |\n|---|---|---|\n");
+            for finding in notable.iter().take(NOTABLE_LIMIT) {
+                md.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    escape_markdown(&self.file_path(&finding.file_id)),
+                    escape_markdown(&finding.func_nm),
+                    escape_markdown(&preview(&finding.result))
+                ));
+            }
+            if notable.len() > NOTABLE_LIMIT {
+                md.push_str(&format!("\n{} more notable findings are in the detail output.\n", notable.len() - NOTABLE_LIMIT));
+            }
+        }
+        md
+    }
+}
+
+// escape_markdown keeps a value on one table cell: pipes are escaped and newlines become spaces
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+// preview returns the first line of a result, cut to PREVIEW_CHARS characters
+fn preview(result: &str) -> String {
+    let first_line = result.lines().next().unwrap_or("");
+    let mut preview: String = first_line.chars().take(PREVIEW_CHARS).collect();
+    if preview.len() < result.len() {
+        preview.push_str(" ...");
+    }
+    preview
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,162 @@
+use super::{extra_json, ResultSink, Run, RUN_HEADERS};
+use crate::parsers::Finding;
+use crate::scanner::FileInfo;
+use std::io::{self, BufWriter, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+/* -------------------------
+* SQLite output: the summary and detail tables are written to a single database file,
+* with indexes on uuid and func_nm so the results can be queried with SQL directly.
+* The statements are streamed into the sqlite3 command line tool inside one transaction,
+* so sqlite3 must be on the PATH.
+* In append mode every table also has run_id and run_timestamp columns, and each run adds its
+* rows to the tables already in the database.
+* --------------------------- */
+
+const CREATE_TABLES: &str = "\
+CREATE TABLE IF NOT EXISTS summary (
+    uuid TEXT PRIMARY KEY,
+    file_nm TEXT,
+    file_dir TEXT,
+    create_dt TEXT,
+    modify_dt TEXT,
+    size_bytes INTEGER
+);
+CREATE TABLE IF NOT EXISTS detail (
+    uuid TEXT,
+    func_nm TEXT,
+    result TEXT,
+    line_start INTEGER,
+    line_end INTEGER,
+    value TEXT,
+    extra TEXT
+);
+";
+
+const CREATE_RUN_TABLES: &str = "\
+CREATE TABLE IF NOT EXISTS summary (
+    run_id TEXT,
+    run_timestamp TEXT,
+    uuid TEXT,
+    file_nm TEXT,
+    file_dir TEXT,
+    create_dt TEXT,
+    modify_dt TEXT,
+    size_bytes INTEGER,
+    PRIMARY KEY (run_id, uuid)
+);
+CREATE TABLE IF NOT EXISTS detail (
+    run_id TEXT,
+    run_timestamp TEXT,
+    uuid TEXT,
+    func_nm TEXT,
+    result TEXT,
+    line_start INTEGER,
+    line_end INTEGER,
+    value TEXT,
+    extra TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_detail_run_id ON detail (run_id);
+";
+
+const CREATE_INDEXES: &str = "\
+CREATE INDEX IF NOT EXISTS idx_detail_uuid ON detail (uuid);
+CREATE INDEX IF NOT EXISTS idx_detail_func_nm ON detail (func_nm);
+";
+
+pub struct SqliteSink {
+    db_path: String,
+    child: Child,
+    stdin: BufWriter<ChildStdin>,
+    // the run values put in front of every row, in append mode
+    run_values: String,
+}
+
+impl SqliteSink {
+    // create opens the database; with a run (append mode) the rows are added to the run tables
+    pub fn create(db_path: &str, run: Option<&Run>) -> io::Result<SqliteSink> {
+        let mut child = Command::new("sqlite3")
+            .arg("-bail")
+            .arg(db_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("could not run sqlite3 (required for sqlite output): {}", e)))?;
+        let mut stdin = BufWriter::new(child.stdin.take().expect("sqlite3 stdin is piped"));
+        stdin.write_all(b"BEGIN;\n")?;
+        stdin.write_all(if run.is_some() { CREATE_RUN_TABLES } else { CREATE_TABLES }.as_bytes())?;
+        let run_values = match run {
+            Some(run) => format!("{}, {}, ", sql_string(&run.id), sql_string(&run.timestamp)),
+            None => String::new(),
+        };
+        Ok(SqliteSink {
+            db_path: db_path.to_string(),
+            child,
+            stdin,
+            run_values,
+        })
+    }
+}
+
+impl ResultSink for SqliteSink {
+    fn write_summary(&mut self, file_data: &[FileInfo]) -> io::Result<()> {
+        for file_info in file_data {
+            writeln!(
+                self.stdin,
+                "INSERT INTO summary VALUES ({}{}, {}, {}, {}, {}, {});",
+                self.run_values,
+                sql_string(&file_info.uuid),
+                sql_string(&file_info.name),
+                sql_string(&file_info.directory),
+                sql_string(&file_info.create_date.format("%Y-%m-%d %H:%M:%S").to_string()),
+                sql_string(&file_info.modify_date.format("%Y-%m-%d %H:%M:%S").to_string()),
+                file_info.size,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_finding(&mut self, finding: &Finding) -> io::Result<()> {
+        writeln!(
+            self.stdin,
+            "INSERT INTO detail VALUES ({}{}, {}, {}, {}, {}, {}, {});",
+            self.run_values,
+            sql_string(&finding.file_id),
+            sql_string(&finding.func_nm),
+            sql_string(&finding.result),
+            finding.line_start.map_or(String::from("NULL"), |line| line.to_string()),
+            finding.line_end.map_or(String::from("NULL"), |line| line.to_string()),
+            sql_string(&finding.value),
+            sql_string(&extra_json(&finding.extra)),
+        )
+    }
+
+    fn write_table(&mut self, name: &str, headers: &[&str], rows: &[Vec<String>]) -> io::Result<()> {
+        let run_headers: &[&str] = if self.run_values.is_empty() { &[] } else { &RUN_HEADERS };
+        // column names are quoted, as some are SQL keywords (the set and if counts of datastep_metrics)
+        let columns: Vec<String> = run_headers.iter().chain(headers).map(|header| format!("\"{}\" TEXT", header)).collect();
+        writeln!(self.stdin, "CREATE TABLE IF NOT EXISTS {} ({});", name, columns.join(", "))?;
+        for row in rows {
+            let values: Vec<String> = row.iter().map(|value| sql_string(value)).collect();
+            writeln!(self.stdin, "INSERT INTO {} VALUES ({}{});", name, self.run_values, values.join(", "))?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> io::Result<()> {
+        self.stdin.write_all(CREATE_INDEXES.as_bytes())?;
+        self.stdin.write_all(b"COMMIT;\n")?;
+        self.stdin.flush()?;
+        drop(self.stdin);
+        let status = self.child.wait()?;
+        if !status.success() {
+            return Err(io::Error::other(format!("sqlite3 failed writing {} ({})", self.db_path, status)));
+        }
+        Ok(())
+    }
+}
+
+// sql_string quotes a value as a SQL string literal
+fn sql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\0', "").replace('\'', "''"))
+}
@@ -0,0 +1,693 @@
+#[cfg(unix)]
+use crate::mmap::Mmap;
+use lexer::TokenKind;
+use std::fs;
+use std::collections::VecDeque;
+use std::io;
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+pub mod comments;
+pub mod complexity;
+pub mod credentials;
+pub mod datastep;
+pub mod dates;
+pub mod deprecated;
+pub mod duplicates;
+pub mod engines;
+pub mod filename;
+pub mod flatfiles;
+pub mod hosts;
+pub mod includes;
+pub mod jumps;
+pub mod lexer;
+pub mod libname;
+pub mod lineage;
+pub mod logs;
+pub mod macros;
+pub mod macrovars;
+pub mod migration;
+pub mod ods;
+pub mod options;
+pub mod passthrough;
+pub mod paths;
+pub mod patterns;
+pub mod pii;
+pub mod procs;
+pub mod remote;
+pub mod shell;
+pub mod sql;
+pub mod titles;
+pub mod variables;
+
+// FileContent holds the text of a file, read once and shared by every parse function.
+// `raw` is the file exactly as read, on the heap or memory-mapped (see Source). `text` is the code the parse functions work on: by default
+// comments are blanked out (see comments.rs), unless commented code is included.
+// `line_starts` holds the byte offset where each line of `text` begins.
+// `upper` is the uppercased copy of `text` and `unquoted` is `upper` with the string literals
+// blanked out; each is only built the first time a parse function asks for it, so a run whose
+// functions don't need them (e.g. a large mapped file with --functions line_count) holds only `text`.
+pub struct FileContent {
+    pub raw: Source,
+    pub text: String,
+    pub line_starts: Vec<usize>,
+    upper: OnceLock<String>,
+    unquoted: OnceLock<String>,
+}
+
+// Source is the raw text of a file: read onto the heap, or memory-mapped for very large files
+// so the file itself is not copied (only `text`, and `upper` and `unquoted` when they are used).
+// A map is checked to be UTF-8 once, when the file is mapped, and then read as a str without
+// checking again. The file must not change while it is mapped: if another process rewrites it
+// the text may no longer be UTF-8 (undefined behaviour for a str), and if it is truncated reading
+// the lost pages raises SIGBUS and kills the run. Inputs that may be written to during a run
+// should be scanned with --mmap-threshold 0.
+pub enum Source {
+    Text(String),
+    #[cfg(unix)]
+    Mapped(Mmap),
+}
+
+impl Source {
+    // mapped wraps a memory map, checking that it holds UTF-8 text
+    #[cfg(unix)]
+    pub fn mapped(map: Mmap) -> Result<Source, std::str::Utf8Error> {
+        std::str::from_utf8(&map)?;
+        Ok(Source::Mapped(map))
+    }
+}
+
+impl Deref for Source {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            Source::Text(text) => text,
+            // checked by Source::mapped; valid as long as the file is not changed (see Source)
+            #[cfg(unix)]
+            Source::Mapped(map) => unsafe { std::str::from_utf8_unchecked(map) },
+        }
+    }
+}
+
+impl FileContent {
+    pub fn load(file_path: &str, include_comments: bool) -> io::Result<FileContent> {
+        let raw = fs::read_to_string(file_path)?;
+        Ok(FileContent::from_text(raw, include_comments))
+    }
+
+    pub fn from_text(raw: String, include_comments: bool) -> FileContent {
+        FileContent::from_source(Source::Text(raw), include_comments)
+    }
+
+    // from_source builds the content from text that has already been checked to be UTF-8
+    pub fn from_source(raw: Source, include_comments: bool) -> FileContent {
+        let text = if include_comments { raw.to_string() } else { comments::strip_comments(&raw) };
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        FileContent { raw, text, line_starts, upper: OnceLock::new(), unquoted: OnceLock::new() }
+    }
+
+    // upper is the uppercased code, for case-insensitive matching
+    pub fn upper(&self) -> &str {
+        self.upper.get_or_init(|| self.text.to_uppercase())
+    }
+
+    // unquoted_upper is the uppercased code with the contents of string literals replaced by
+    // spaces, for matching keywords only where they are code: PUT "PROC SQL"; is not a PROC SQL
+    pub fn unquoted_upper(&self) -> &str {
+        self.unquoted.get_or_init(|| lexer::blank_strings(self.upper()))
+    }
+
+    // raw_lines returns lines start_line to end_line (1-based, inclusive) of the file as read,
+    // comments included
+    pub fn raw_lines(&self, start_line: usize, end_line: usize) -> String {
+        let count = (end_line + 1).saturating_sub(start_line.max(1));
+        self.raw.lines().skip(start_line.saturating_sub(1)).take(count).collect::<Vec<&str>>().join("\n")
+    }
+
+    // line_at returns the 1-based line number of a byte offset in `text`
+    pub fn line_at(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(line) => line + 1,
+            Err(line) => line,
+        }
+    }
+}
+
+// Statement is one semicolon-terminated SAS statement. `start` is the offset of its first
+// non-whitespace character in the file text and `end` is the offset just past its semicolon.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Statement<'a> {
+    pub start: usize,
+    pub end: usize,
+    pub text: &'a str,
+}
+
+impl Statement<'_> {
+    // keyword returns the first word of the statement, uppercased (e.g. DATA, PROC, RUN)
+    pub fn keyword(&self) -> String {
+        self.text
+            .split(|c: char| c.is_whitespace() || c == ';' || c == '(' || c == '=')
+            .next()
+            .unwrap_or("")
+            .to_uppercase()
+    }
+}
+
+// split_statements splits text into statements on the semicolons of its tokens (see lexer.rs), so
+// a semicolon in a string literal or a macro quoting function (%str(;)) does not end a statement.
+// Comments are split on their semicolons as before, so commented code that is analyzed
+// (--include-comments) still reads as statements.
+pub fn split_statements(text: &str) -> Vec<Statement<'_>> {
+    statements(text).collect()
+}
+
+// statements returns the statements of text one at a time, as they are read, the way
+// split_statements splits them
+pub fn statements(text: &str) -> impl Iterator<Item = Statement<'_>> + '_ {
+    let mut tokens = lexer::tokens(text);
+    // the ends of the statements read but not returned yet (a comment can hold several)
+    let mut ends: VecDeque<usize> = VecDeque::new();
+    let mut start = 0;
+    std::iter::from_fn(move || {
+        while ends.is_empty() {
+            let token = tokens.next()?;
+            match token.kind {
+                TokenKind::Semicolon => ends.push_back(token.end()),
+                TokenKind::Comment => ends.extend(token.text.match_indices(';').map(|(i, _)| token.start + i + 1)),
+                _ => {}
+            }
+        }
+        let end = ends.pop_front()?;
+        let raw = &text[start..end];
+        let leading = raw.len() - raw.trim_start().len();
+        start = end;
+        Some(Statement {
+            start: end - raw.len() + leading,
+            end,
+            text: &raw[leading..],
+        })
+    })
+}
+
+// split_word splits off the first whitespace separated word
+pub(crate) fn split_word(text: &str) -> (&str, &str) {
+    match text.find(char::is_whitespace) {
+        Some(i) => (&text[..i], &text[i..]),
+        None => (text, ""),
+    }
+}
+
+// quoted_end returns the offset just past a leading quoted string or parenthesized list
+pub(crate) fn quoted_end(text: &str) -> usize {
+    let close = match text.chars().next() {
+        Some('(') => ')',
+        Some(q) => q,
+        None => return 0,
+    };
+    match text[1..].find(close) {
+        Some(i) => i + 2,
+        None => text.len(),
+    }
+}
+
+// Finding is a single result produced by a parse function for a file. `result` is the
+// formatted (line, ...) tuple the detail output has always had; the same finding is also kept in
+// typed fields, so the output can be read without parsing the tuple: the lines it covers, its
+// main value (the matched text, name or count) and the other fields by name (`extra`, written as
+// a JSON object). A finding that does not set them has no lines and its result as its value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub file_id: String,
+    pub func_nm: String,
+    pub result: String,
+    pub line_start: Option<usize>,
+    pub line_end: Option<usize>,
+    pub value: String,
+    pub extra: Vec<(String, String)>,
+}
+
+impl Finding {
+    pub fn new(file_id: &str, func_nm: &str, result: String) -> Finding {
+        Finding {
+            file_id: file_id.to_string(),
+            func_nm: func_nm.to_string(),
+            value: result.clone(),
+            result,
+            line_start: None,
+            line_end: None,
+            extra: Vec::new(),
+        }
+    }
+
+    // at sets the line of a finding on a single line
+    pub fn at(self, line: usize) -> Finding {
+        self.lines(line, line)
+    }
+
+    // lines sets the first and last line a finding covers
+    pub fn lines(mut self, start: usize, end: usize) -> Finding {
+        self.line_start = Some(start);
+        self.line_end = Some(end);
+        self
+    }
+
+    // value sets the main value of a finding
+    pub fn value(mut self, value: impl ToString) -> Finding {
+        self.value = value.to_string();
+        self
+    }
+
+    // extra adds a named field to a finding
+    pub fn extra(mut self, name: &str, value: impl ToString) -> Finding {
+        self.extra.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    // extras adds a named field for each name and value, e.g. the columns and a row of a
+    // structured table
+    pub fn extras(mut self, names: &[&str], values: &[String]) -> Finding {
+        self.extra.extend(names.iter().zip(values).map(|(name, value)| (name.to_string(), value.clone())));
+        self
+    }
+}
+
+// FileContext is what a parser is given for each file: the file's uuid and its loaded content
+#[derive(Clone, Copy)]
+pub struct FileContext<'a> {
+    pub file_id: &'a str,
+    pub content: &'a FileContent,
+}
+
+// Parser is an analyzer run against every scanned file. It can carry state (precompiled
+// patterns, the list of scanned files, settings), so stateful and configurable analyzers (user
+// rules, plugins, find_file_name) are run the same way as the built-in parse functions.
+pub trait Parser {
+    // name is the name the findings are reported under, and that --functions selects
+    fn name(&self) -> &str;
+    // describe is the one line description shown by --list-functions
+    fn describe(&self) -> &str;
+    fn parse(&self, context: &FileContext) -> Vec<Finding>;
+}
+
+// ParseFunction is the plain function form of a built-in parser: it takes a file_id and the
+// loaded file content, and returns a Vec of findings for that file
+pub type ParseFunction = fn(&str, &FileContent) -> Vec<Finding>;
+
+// Builtin is a registered parse function as a Parser
+#[derive(Debug, Clone, Copy)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub function: ParseFunction,
+}
+
+impl Parser for Builtin {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn describe(&self) -> &str {
+        self.description
+    }
+
+    fn parse(&self, context: &FileContext) -> Vec<Finding> {
+        (self.function)(context.file_id, context.content)
+    }
+}
+
+// PARSE_FUNCTIONS is the registry of parse functions that can be selected by name: (name, description, function)
+pub const PARSE_FUNCTIONS: &[(&str, &str, ParseFunction)] = &[
+    ("line_count", "Counts the number of lines in a file", line_count),
+    ("line_breakdown", "Counts the code, comment and blank lines of a file separately", comments::line_breakdown),
+    ("sql_count", "Counts the number of PROC SQL blocks in a file", sql::sql_count),
+    ("get_sql", "Extracts PROC SQL blocks with their starting line", sql::get_sql),
+    ("get_sql_tables", "Lists each table referenced in PROC SQL FROM/JOIN clauses as libref and table", sql::get_sql_tables),
+    ("get_sql_statements", "Splits PROC SQL blocks into classified statements with target, source tables, joins and subqueries; also writes the sql_statements table", sql::statements::get_sql_statements),
+    ("get_sql_dml", "Lists each PROC SQL INSERT, UPDATE, DELETE and ALTER statement with its target table", sql::statements::get_sql_dml),
+    ("sql_dml_count", "Counts the PROC SQL SELECT, INSERT, UPDATE, DELETE and ALTER statements in a file", sql::statements::sql_dml_count),
+    ("get_sql_creates", "Lists the libref and name of each table and view created by PROC SQL CREATE TABLE/VIEW", sql::statements::get_sql_creates),
+    ("get_sql_into", "Lists the macro variables filled by PROC SQL SELECT ... INTO, with ranges and SEPARATED BY", sql::statements::get_sql_into),
+    ("get_libname", "Parses LIBNAME statements into libref, engine, path and options; also writes the libnames table", libname::get_libname),
+    ("get_filename", "Parses FILENAME statements into fileref, device type, target and options; also writes the filenames table", filename::get_filename),
+    ("get_pipes", "Extracts the shell command of each FILENAME ... PIPE statement", filename::get_pipes),
+    ("get_emails", "Lists the TO, CC and BCC recipients and SUBJECT of each FILENAME ... EMAIL statement", filename::get_emails),
+    ("find_dde", "Flags FILENAME ... DDE statements with the application, topic and item of the DDE link (Excel automation)", filename::find_dde),
+    ("get_options", "Splits OPTIONS statements into one row per system option and value; also writes the options table", options::get_options),
+    ("get_ods", "Lists the ODS destinations (PDF, EXCEL, HTML, RTF, ...) opened with FILE=, BODY= or PATH= and the output path", ods::get_ods),
+    ("get_titles", "Captures the text of TITLE and FOOTNOTE statements", titles::get_titles),
+    ("get_password", "Finds lines with PASSWORD= (but not &PASSWORD)", get_password),
+    ("export_count", "Counts PROC EXPORT statements", export_count),
+    ("export_other_count", "Counts EXPORT keywords outside PROC EXPORT statements (CALL EXPORT, options, ...)", export_other_count),
+    ("get_exports", "Lists each EXPORT keyword with its line, as PROC EXPORT or another EXPORT", get_exports),
+    ("null_count", "Counts occurrences of _NULL_", null_count),
+    ("find_date", "Finds dates (date and datetime literals, YYYY-MM-DD, MM/DD/YYYY, DDMONYYYY, two-digit years) with their format", dates::find_date),
+    ("get_macros", "Extracts %MACRO definitions with parameters, line range and body length", macros::get_macros),
+    ("get_includes", "Extracts %INCLUDE targets; also writes the includes dependency edge list", includes::get_includes),
+    ("get_datastep", "Extracts DATA step blocks with their starting line and output datasets", datastep::get_datastep),
+    ("get_datastep_metrics", "Counts the statements of each DATA step (SET, MERGE, IF, DO, OUTPUT, KEEP, DROP) and its deepest conditional/DO nesting; also writes the datastep_metrics table", datastep::statements::get_datastep_metrics),
+    ("get_infiles", "Extracts INFILE statements (path or fileref, DLM=, FIRSTOBS=, options) with the INPUT statement that reads them; also writes the infiles table", flatfiles::get_infiles),
+    ("get_outfiles", "Extracts FILE statements writing external paths or filerefs with their options and PUT count; also writes the outfiles table", flatfiles::get_outfiles),
+    ("get_variable_lists", "Extracts KEEP=/DROP=/RENAME= dataset options and KEEP/DROP/RENAME statements with their variables; also writes the variable_lists table", variables::get_variable_lists),
+    ("get_arrays", "Extracts ARRAY statements with the array name, dimension and member variables", datastep::arrays::get_arrays),
+    ("get_hash_objects", "Finds DECLARE HASH/HITER objects and the datasets loaded into them", datastep::hash::get_hash_objects),
+    ("get_retain_lag", "Flags RETAIN statements, sum statements and LAG/DIF calls, which depend on row order", datastep::retain::get_retain_lag),
+    ("get_do_nesting", "Counts the DO ... END blocks of the DATA steps in a file and their deepest nesting", datastep::statements::get_do_nesting),
+    ("complexity", "Scores a file by its branches and loops: 1 + IF, ELSE, DO loop, SELECT, WHEN and %IF counts", complexity::complexity),
+    ("get_lineage", "Records the datasets read and written by each DATA step and PROC SQL statement", lineage::get_lineage),
+    ("proc_inventory", "Counts each PROC <name> used in a file, one row per procedure", procs::proc_inventory),
+    ("get_import_export", "Parses PROC IMPORT/EXPORT steps into dataset, file, DBMS, sheet and range; also writes the import_export table", procs::get_import_export),
+    ("get_formats", "Inventories the formats defined by PROC FORMAT (VALUE, INVALUE, PICTURE) and CNTLIN= datasets", procs::get_formats),
+    ("get_datasets_operations", "Flags the DELETE, CHANGE, AGE and SAVE statements and KILL option of PROC DATASETS with the library and members affected", procs::get_datasets_operations),
+    ("get_fcmp_functions", "Lists the functions and subroutines defined in PROC FCMP with their arguments, return type and OUTLIB= package", procs::get_fcmp_functions),
+    ("find_paths", "Flags hardcoded Windows, UNC and Unix filesystem paths", paths::find_paths),
+    ("find_shell_commands", "Flags X, SYSTASK COMMAND, %SYSEXEC and CALL SYSTEM operating system commands with the command text", shell::find_shell_commands),
+    ("find_hosts", "Flags hardcoded IP addresses, SERVER=/HOST= host names and URL hosts", hosts::find_hosts),
+    ("find_sas_connect", "Flags SAS/CONNECT SIGNON, RSUBMIT/ENDRSUBMIT, %SYSLPUT/%SYSRPUT and PROC UPLOAD/DOWNLOAD with the remote session", remote::find_sas_connect),
+    ("find_jumps", "Flags %GOTO, %label:, and DATA step GOTO and LINK statements with their target", jumps::find_jumps),
+    ("get_connections", "Parses PROC SQL CONNECT TO statements into DBMS, alias, server, path and schema", passthrough::get_connections),
+    ("get_engines", "Lists the engine of each LIBNAME and the DBMS of each CONNECT statement; also writes the engines table of file counts per engine", engines::get_engines),
+    ("get_passthrough", "Extracts the native SQL of EXECUTE BY and CONNECTION TO pass-through sections", passthrough::get_passthrough),
+    ("get_macro_calls", "Records each %name macro invocation with its calling macro, for the macro call graph", macros::get_macro_calls),
+    ("get_let", "Catalogs %LET macro variable assignments with their values", macrovars::get_let),
+    ("get_symput", "Finds CALL SYMPUT/SYMPUTX statements with the macro variable name and value expressions", macrovars::get_symput),
+    ("get_sysfunc", "Finds %SYSFUNC/%QSYSFUNC calls with the function called, its arguments and the result format", macrovars::get_sysfunc),
+    ("get_auto_macro_vars", "Finds references to automatic macro variables such as &SYSDATE9, &SYSUSERID and &SYSHOSTNAME", macrovars::get_auto_macro_vars),
+    ("get_credentials", "Flags passwords, user names, auth domains and inline credentials with a rule id and severity", credentials::get_credentials),
+    ("find_pii", "Flags SSNs, phone numbers and email addresses in code and DATALINES/CARDS data, with a redacted sample", pii::find_pii),
+    ("get_deprecated", "Flags deprecated syntax (PROC CONVERT, PROC ACCESS/DBLOAD, V5/V6 engines, %INC of line numbers, GOTO, %MACRO without %MEND) with a rule id", deprecated::get_deprecated),
+];
+
+// TableFunction returns structured rows for a parse function whose results have several fields
+pub type TableFunction = fn(&FileContent) -> Vec<Vec<String>>;
+
+// STRUCTURED_TABLES are extra output tables written when their parse function runs, with one
+// column per field so they can be joined: (parse function name, table name, columns, function).
+// Each row is written after the uuid of the file it came from.
+pub const STRUCTURED_TABLES: &[(&str, &str, &[&str], TableFunction)] = &[
+    ("get_sql_statements", "sql_statements", &sql::statements::SQL_STATEMENT_COLUMNS, sql::statements::sql_statement_rows),
+    ("get_datastep_metrics", "datastep_metrics", &datastep::statements::DATASTEP_METRIC_COLUMNS, datastep::statements::datastep_metric_rows),
+    ("get_libname", "libnames", &libname::LIBNAME_COLUMNS, libname::libname_rows),
+    ("get_filename", "filenames", &filename::FILENAME_COLUMNS, filename::filename_rows),
+    ("get_options", "options", &options::OPTION_COLUMNS, options::option_rows),
+    ("get_infiles", "infiles", &flatfiles::INFILE_COLUMNS, flatfiles::infile_rows),
+    ("get_outfiles", "outfiles", &flatfiles::OUTFILE_COLUMNS, flatfiles::outfile_rows),
+    ("get_variable_lists", "variable_lists", &variables::VARIABLE_LIST_COLUMNS, variables::variable_list_rows),
+    ("get_import_export", "import_export", &procs::IMPORT_EXPORT_COLUMNS, procs::import_export_rows),
+    ("get_let", "macro_variables", &macrovars::MACRO_VARIABLE_COLUMNS, macrovars::macro_variable_rows),
+    ("get_connections", "connections", &passthrough::CONNECTION_COLUMNS, passthrough::connection_rows),
+    ("log_summary", "log_summary", &logs::LOG_SUMMARY_COLUMNS, logs::log_summary_rows),
+];
+
+// StreamFunction is the streaming form of a parse function: each finding is handed to `emit`
+// (which writes it to the output) as soon as it is found, instead of being collected first
+pub type StreamFunction = fn(&str, &FileContent, &mut dyn FnMut(Finding) -> io::Result<()>) -> io::Result<()>;
+
+// STREAMING_FUNCTIONS are the parse functions with a streaming form, used by --stream runs:
+// (parse function name, function). The others are run as usual and their findings written after.
+pub const STREAMING_FUNCTIONS: &[(&str, StreamFunction)] = &[("get_sql", sql::stream_sql)];
+
+// stream_function returns the streaming form of a parse function, if it has one
+pub fn stream_function(name: &str) -> Option<StreamFunction> {
+    STREAMING_FUNCTIONS.iter().find(|(function_name, _)| *function_name == name).map(|(_, function)| *function)
+}
+
+// Mode is what the scanned files are: SAS programs (the default) or the logs SAS wrote
+// running them (--mode logs), which have their own parse functions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Source,
+    Logs,
+}
+
+impl Mode {
+    // functions returns the parse functions of the mode: (name, description, function)
+    pub fn functions(&self) -> &'static [(&'static str, &'static str, ParseFunction)] {
+        match self {
+            Mode::Source => PARSE_FUNCTIONS,
+            Mode::Logs => logs::LOG_FUNCTIONS,
+        }
+    }
+
+    // extensions returns the file extensions scanned by default in the mode
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            Mode::Source => crate::scanner::DEFAULT_EXTENSIONS,
+            Mode::Logs => &["log"],
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Mode, String> {
+        match s.to_lowercase().as_str() {
+            "source" => Ok(Mode::Source),
+            "logs" | "log" => Ok(Mode::Logs),
+            _ => Err(format!("Unknown mode: {} (expected source or logs)", s)),
+        }
+    }
+}
+
+// is_builtin tests whether a name is taken by a built-in parser of any mode
+pub fn is_builtin(name: &str) -> bool {
+    name == FIND_FILE_NAME.0 || [Mode::Source, Mode::Logs].iter().any(|mode| mode.functions().iter().any(|(function, _, _)| *function == name))
+}
+
+// check_extra_names validates the names of the parsers run next to the built-in ones (plugin
+// functions and --rules): each must be unique and must not be the name of a built-in parser
+pub fn check_extra_names(parsers: &[Box<dyn Parser>]) -> Result<(), String> {
+    for (i, parser) in parsers.iter().enumerate() {
+        let name = parser.name();
+        if is_builtin(name) {
+            return Err(format!("{} is the name of a built-in parse function", name));
+        }
+        if parsers[..i].iter().any(|other| other.name() == name) {
+            return Err(format!("{} is defined more than once by the plugins and rules", name));
+        }
+    }
+    Ok(())
+}
+
+// builtin returns a registry entry as a Parser
+fn builtin(&(name, description, function): &(&'static str, &'static str, ParseFunction)) -> Box<dyn Parser> {
+    Box::new(Builtin { name, description, function })
+}
+
+// default_functions returns the parse functions that are run against every file
+pub fn default_functions() -> Vec<Box<dyn Parser>> {
+    PARSE_FUNCTIONS.iter().map(builtin).collect()
+}
+
+// select_functions looks up parse functions by name, keeping the order they were given in
+pub fn select_functions(names: &[String]) -> Result<Vec<Box<dyn Parser>>, String> {
+    select_mode_functions(Mode::Source, names)
+}
+
+// select_mode_functions looks up the parse functions of a mode by name
+pub fn select_mode_functions(mode: Mode, names: &[String]) -> Result<Vec<Box<dyn Parser>>, String> {
+    let mut functions: Vec<Box<dyn Parser>> = Vec::new();
+    for name in names {
+        match mode.functions().iter().find(|(function_name, _, _)| function_name == name) {
+            Some(entry) => functions.push(builtin(entry)),
+            None => return Err(format!("Unknown parse function: {} (use --list-functions to see the available functions)", name)),
+        }
+    }
+    Ok(functions)
+}
+
+/* -------------------------
+* Parse Functions: These are the functions that will be used to parse the files.
+* They each perform a unique analysis on the file content, which is read once per file
+* (with comments blanked out unless commented code is included):
+* - line_count: Counts the number of lines in a file.
+* - line_breakdown: Counts the code, comment and blank lines of a file separately (comments.rs)
+* - sql_count: Counts the number of SQL blocks in a file. (sql.rs)
+* - get_sql: Extracts SQL blocks from a file. (sql.rs)
+* - get_sql_tables: return each libref.table referenced in a PROC SQL FROM or JOIN clause (sql.rs)
+* - get_sql_statements: return each PROC SQL statement as line, kind (CREATE TABLE AS, SELECT, INSERT, ...),
+*   target table, source tables and its join, subquery and set operator counts (sql/statements.rs)
+* - get_sql_dml: return each PROC SQL INSERT, UPDATE, DELETE and ALTER statement as line, kind and target (sql/statements.rs)
+* - sql_dml_count: count the PROC SQL SELECT, INSERT, UPDATE, DELETE and ALTER statements of a file (sql/statements.rs)
+* - get_sql_creates: return each PROC SQL CREATE TABLE/VIEW target as line, kind, libref and name (sql/statements.rs)
+* - get_sql_into: return each macro variable (or range) filled by SELECT ... INTO as line, target and separator (sql/statements.rs)
+* - get_libname: parses each LIBNAME statement into libref, engine, path and options. (libname.rs)
+* - get_filename: parses each FILENAME statement into fileref, device type, target path/URL and options. (filename.rs)
+* - get_pipes: return each FILENAME ... PIPE statement as line, fileref and the piped shell command. (filename.rs)
+* - get_emails: return each FILENAME ... EMAIL statement as line, fileref, TO, CC and BCC recipients and subject (filename.rs)
+* - find_dde: return each FILENAME ... DDE statement as line, fileref, application, topic and item (filename.rs)
+* - get_options: return each system option set by an OPTIONS statement as line, option and value (options.rs)
+* - get_ods: return each ODS statement that opens a destination with an output file as line, destination and path (ods.rs)
+* - get_titles: return each TITLE/FOOTNOTE statement as line, statement (TITLE2, FOOTNOTE, ...) and text (titles.rs)
+* - get_password: return line number, column and original text of any line that has password = (but not &password)
+* - export_count: return the number of PROC EXPORT statements.
+* - export_other_count: return the number of other EXPORT keywords (CALL EXPORT, EXPORT= options, ...).
+* - get_exports: return each EXPORT keyword and its line, as PROC EXPORT or EXPORT. EXPORT is matched as a
+*   whole word in code, so export_data, 'export.csv' and EXPORT in a comment are not counted.
+* - get_null_count: return number of lines that have _null_ (outside string literals)
+* - find_date: return each date with its line, column and format: SAS date and datetime literals ('01JAN2020'd),
+*   YYYY-MM-DD, MM/DD/YYYY, DDMONYYYY and their two-digit year forms (dates.rs)
+* - get_macros: return each %MACRO ... %MEND definition with its parameters, start/end line and body length (macros.rs)
+* - get_includes: return each %INCLUDE target and its line number (includes.rs)
+* - get_datastep: return each DATA ... RUN; block with its starting line and output dataset names (datastep.rs)
+* - get_datastep_metrics: return each DATA step as line, output datasets, statement count, the SET, MERGE, IF,
+*   DO, OUTPUT, KEEP and DROP counts and the deepest IF/DO nesting (datastep/statements.rs)
+* - get_infiles: return each INFILE statement as line, source path or fileref, kind, DLM=, FIRSTOBS=, options and
+*   the line and variables of the INPUT statement that reads it (flatfiles.rs)
+* - get_outfiles: return each FILE statement writing an external file as line, target path or fileref, kind,
+*   options and the number of PUT statements writing to it (flatfiles.rs)
+* - get_variable_lists: return each KEEP=, DROP=, RENAME= dataset option and KEEP/DROP/RENAME statement as line,
+*   kind, dataset and variables (variables.rs)
+* - get_arrays: return each ARRAY statement as line, array name, dimension and member variables (datastep/arrays.rs)
+* - get_hash_objects: return each hash object or iterator as line, kind (HASH, HITER), name and the dataset loaded
+*   into it (datastep/hash.rs)
+* - get_retain_lag: return each RETAIN statement, sum statement and LAG/LAGn/DIF/DIFn call as line, kind and
+*   variables or argument (datastep/retain.rs)
+* - get_do_nesting: return the number of DO ... END blocks in the file's DATA steps and their deepest nesting
+*   (datastep/statements.rs)
+* - complexity: return a cyclomatic-style score: 1 plus the number of IF, ELSE, DO loop, SELECT, WHEN and %IF (complexity.rs)
+* - get_lineage: return input -> output dataset pairs for each DATA step and PROC SQL statement (lineage.rs)
+* - proc_inventory: return each procedure used in the file with its number of PROC statements (procs.rs)
+* - get_import_export: return each PROC IMPORT/EXPORT with its dataset, file, DBMS, sheet and range (procs.rs)
+* - get_formats: return each VALUE/INVALUE/PICTURE format of a PROC FORMAT step and each CNTLIN= dataset as line,
+*   kind, name and format library (procs.rs)
+* - get_datasets_operations: return each DELETE, CHANGE, AGE, SAVE and KILL of a PROC DATASETS step as line,
+*   operation, library and members (procs.rs)
+* - get_fcmp_functions: return each PROC FCMP FUNCTION and SUBROUTINE as line, kind, name, arguments, return
+*   type and OUTLIB= package (procs.rs)
+* - get_connections: return each PROC SQL CONNECT TO statement as line, DBMS, alias, server, path, schema
+*   and options (passthrough.rs)
+* - get_engines: return the engine of each LIBNAME and the DBMS of each CONNECT statement with its line;
+*   the engines table totals them across files (engines.rs)
+* - get_passthrough: return the native SQL of each explicit pass-through section (EXECUTE ... BY,
+*   CONNECTION TO) with its line, kind and target DBMS (passthrough.rs)
+* - get_macro_calls: return each macro invocation as line, caller and callee; across files these make the
+*   macro_calls edge list (macros.rs)
+* - get_let: return each %LET assignment as line, macro variable name and value (macrovars.rs)
+* - get_symput: return each CALL SYMPUT/SYMPUTX as line, routine, name expression and value expression (macrovars.rs)
+* - get_sysfunc: return each %SYSFUNC/%QSYSFUNC call as line, macro function, function, arguments and format (macrovars.rs)
+* - get_auto_macro_vars: return each reference to an automatic macro variable (&SYSDATE, &SYSUSERID, ...) with its line (macrovars.rs)
+* - get_credentials: return each credential rule hit (PASSWORD=, PW=, USER=, AUTHDOMAIN=, IDENTITY=, inline
+*   connection credentials, ...) with its line, rule id and severity; secrets are redacted (credentials.rs)
+* - find_pii: return each SSN, phone number or email address in the code or in DATALINES/CARDS data as line,
+*   rule id, where it was found (CODE or DATALINES) and a redacted sample (pii.rs)
+* - get_deprecated: return each deprecated syntax rule hit (PROC CONVERT, V5/V6 engines, GOTO, %MACRO without
+*   %MEND, ...) as line, rule id and the matched code (deprecated.rs)
+* - find_paths: return each hardcoded Windows (C:\), UNC (\\server\share) or Unix (/sasdata/...) path and its line (paths.rs)
+* - find_shell_commands: return each X statement, SYSTASK COMMAND, %SYSEXEC and CALL SYSTEM as line, kind and
+*   the command it runs (shell.rs)
+* - find_hosts: return each hardcoded IP address, SERVER=/HOST= host name and URL host with its line (hosts.rs)
+* - find_sas_connect: return each SIGNON, SIGNOFF, RSUBMIT, ENDRSUBMIT, WAITFOR, %SYSLPUT, %SYSRPUT and
+*   PROC UPLOAD/DOWNLOAD as line, statement and the remote session or macro variable it names (remote.rs)
+* - find_jumps: return each %GOTO, %label:, GOTO and LINK as line, kind and target label (jumps.rs)
+* With --mode logs, .log files are scanned with the log parse functions instead: log_counts, log_errors,
+* log_times, log_program and log_summary (logs.rs)
+* --------------------------- */
+
+pub fn line_count(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    let line_count = content.text.lines().count();
+    vec![Finding::new(file_id, "line_count", line_count.to_string())]
+}
+
+pub fn get_password(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    let mut results: Vec<Finding> = Vec::new();
+    for (line_number, line) in content.text.lines().enumerate() {
+        if patterns::PASSWORD_MACRO_VARIABLE.is_match(line) {
+            continue;
+        }
+        if let Some(m) = patterns::PASSWORD_OPTION.find(line) {
+            let column = column_at(line, m.start());
+            results.push(Finding::new(file_id, "get_password", format!("({}, {}, {})", line_number + 1, column, line)).at(line_number + 1).value(line).extra("column", column));
+        }
+    }
+    results
+}
+
+// find_exports returns the line of each EXPORT word in the code, and whether it is the procedure
+// name of a PROC EXPORT statement
+pub fn find_exports(content: &FileContent) -> Vec<(usize, bool)> {
+    let mut exports = Vec::new();
+    let mut after_proc = false;
+    let mut statement_start = true;
+    for token in lexer::tokens(&content.text) {
+        if token.kind == TokenKind::Comment {
+            continue;
+        }
+        if token.is_word("EXPORT") {
+            exports.push((content.line_at(token.start), after_proc));
+        }
+        after_proc = statement_start && token.is_word("PROC");
+        statement_start = token.kind == TokenKind::Semicolon;
+    }
+    exports
+}
+
+pub fn export_count(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    let count = find_exports(content).iter().filter(|(_, is_proc)| *is_proc).count();
+    vec![Finding::new(file_id, "export_count", count.to_string())]
+}
+
+pub fn export_other_count(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    let count = find_exports(content).iter().filter(|(_, is_proc)| !is_proc).count();
+    vec![Finding::new(file_id, "export_other_count", count.to_string())]
+}
+
+pub fn get_exports(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_exports(content)
+        .iter()
+        .map(|(line, is_proc)| {
+            let kind = if *is_proc { "PROC EXPORT" } else { "EXPORT" };
+            Finding::new(file_id, "get_exports", format!("({}, {})", line, kind)).at(*line).value(kind)
+        })
+        .collect()
+}
+
+pub fn null_count(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    let count = content.unquoted_upper().matches("_NULL_").count();
+    vec![Finding::new(file_id, "null_count", count.to_string())]
+}
+
+// column_at returns the 1-based column, in characters, of the byte offset in line
+pub fn column_at(line: &str, offset: usize) -> usize {
+    line[..offset].chars().count() + 1
+}
+
+// FIND_FILE_NAME is the name and description of the find_file_name parser. It needs the list of
+// scanned files, so it is not part of PARSE_FUNCTIONS or default_functions: a run that selects it
+// builds a FileNameParser once the files are listed.
+pub const FIND_FILE_NAME: (&str, &str) = ("find_file_name", "Finds lines that mention the name of a scanned file");
+
+// FileNameParser is find_file_name with the names of the scanned files
+pub struct FileNameParser {
+    pub file_list: Vec<String>,
+}
+
+impl Parser for FileNameParser {
+    fn name(&self) -> &str {
+        FIND_FILE_NAME.0
+    }
+
+    fn describe(&self) -> &str {
+        FIND_FILE_NAME.1
+    }
+
+    fn parse(&self, context: &FileContext) -> Vec<Finding> {
+        find_file_name(context.file_id, context.content, &self.file_list)
+    }
+}
+
+pub fn find_file_name(file_id: &str, content: &FileContent, file_list: &[String]) -> Vec<Finding> {
+    let mut results: Vec<Finding> = Vec::new();
+    for (line_number, line) in content.text.lines().enumerate() {
+        for file_name in file_list {
+            if line.contains(file_name.as_str()) {
+                results.push(Finding::new(file_id, "find_file_name", format!("({}):{}", line_number + 1, line)).at(line_number + 1).value(line).extra("file_name", file_name));
+                break;
+            }
+        }
+    }
+    results
+}
@@ -0,0 +1,92 @@
+use super::lexer::{self, TokenKind};
+use super::{FileContent, Finding};
+
+/* -------------------------
+* Comment handling: SAS has three kinds of comment
+* - /* ... */ block comments, anywhere outside a quoted string
+* - * ... ; statement comments, where * starts a statement
+* - %* ... ; macro comments, where %* starts a statement
+* comment_spans finds them (they are tokens of the SAS tokenizer, lexer.rs), and strip_comments blanks them out so the parse functions only
+* see code. Comment characters are replaced with spaces (newlines are kept), so byte offsets
+* and line numbers in the stripped text match the original file.
+* - line_breakdown: the number of code, comment and blank lines of a file. A line with any code on
+*   it is a code line, even with a comment after the code; a line that is only comment (or the
+*   inside of a comment spanning lines) is a comment line. The original text is read, so commented
+*   code counts as comment with or without --include-comments.
+* --------------------------- */
+
+// comment_spans returns the (start, end) byte ranges of every comment in the text
+pub fn comment_spans(text: &str) -> Vec<(usize, usize)> {
+    lexer::tokens(text)
+        .filter(|token| token.kind == TokenKind::Comment)
+        .map(|token| (token.start, token.end()))
+        .collect()
+}
+
+// strip_comments returns the text with every comment replaced by spaces, keeping newlines
+pub fn strip_comments(text: &str) -> String {
+    let spans = comment_spans(text);
+    if spans.is_empty() {
+        return text.to_string();
+    }
+    let mut stripped = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, end) in spans {
+        stripped.push_str(&text[last..start]);
+        for c in text[start..end].chars() {
+            if c == '\n' || c == '\r' {
+                stripped.push(c);
+            } else {
+                // keep the byte length so offsets still line up with the original text
+                stripped.extend(std::iter::repeat_n(' ', c.len_utf8()));
+            }
+        }
+        last = end;
+    }
+    stripped.push_str(&text[last..]);
+    stripped
+}
+
+// LineCounts is the line breakdown of a file; code + comment + blank is its line count
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineCounts {
+    pub code: usize,
+    pub comment: usize,
+    pub blank: usize,
+}
+
+// count_lines classifies each line of the file as read by the comment spans it holds, rather
+// than by a comment-stripped copy of the file, which for a memory-mapped file would be one more
+// full copy on the heap
+pub fn count_lines(content: &FileContent) -> LineCounts {
+    let mut counts = LineCounts { code: 0, comment: 0, blank: 0 };
+    let spans = comment_spans(&content.raw);
+    let mut spans = spans.iter().peekable();
+    let mut start = 0;
+    for line in content.raw.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            counts.blank += 1;
+        } else {
+            // a line is code when a character that is not whitespace is outside every comment
+            let is_code = line.char_indices().filter(|(_, c)| !c.is_whitespace()).any(|(i, _)| {
+                let offset = start + i;
+                while spans.next_if(|(_, end)| *end <= offset).is_some() {}
+                spans.peek().is_none_or(|(span_start, _)| *span_start > offset)
+            });
+            match is_code {
+                true => counts.code += 1,
+                false => counts.comment += 1,
+            }
+        }
+        start += line.len();
+    }
+    counts
+}
+
+pub fn line_breakdown(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    let counts = count_lines(content);
+    vec![Finding::new(file_id, "line_breakdown", format!("({}, {}, {})", counts.code, counts.comment, counts.blank))
+        .value(counts.code)
+        .extra("comment", counts.comment)
+        .extra("blank", counts.blank)]
+}
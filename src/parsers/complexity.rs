@@ -0,0 +1,65 @@
+use super::datastep::find_datasteps;
+use super::datastep::statements::clause_keywords;
+use super::lexer::{self, TokenKind};
+use super::{split_statements, FileContent, Finding};
+
+/* -------------------------
+* Complexity parse functions:
+* - complexity: returns a cyclomatic-style complexity score for the file, to rank files by how
+*   much work they are to remediate: 1, plus one for every IF, ELSE, DO loop, SELECT, WHEN and
+*   %IF, i.e. for every branch and loop in the code. A plain DO; block only groups statements
+*   (the IF or WHEN that opens it is already counted), so only the iterative DO loops (DO i = ...,
+*   DO WHILE, DO UNTIL, DO OVER) count. IF, ELSE, SELECT, WHEN and DO are only counted in DATA
+*   steps, as in PROC SQL they are not branches (SELECT is a query, CASE WHEN an expression);
+*   %IF is counted anywhere in the file.
+* --------------------------- */
+
+// ComplexityCounts are the branches and loops of a file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComplexityCounts {
+    pub ifs: usize,
+    pub elses: usize,
+    pub do_loops: usize,
+    pub selects: usize,
+    pub whens: usize,
+    pub macro_ifs: usize,
+}
+
+impl ComplexityCounts {
+    pub fn score(&self) -> usize {
+        1 + self.ifs + self.elses + self.do_loops + self.selects + self.whens + self.macro_ifs
+    }
+}
+
+pub fn count_branches(content: &FileContent) -> ComplexityCounts {
+    let mut counts = ComplexityCounts::default();
+    let steps = find_datasteps(content);
+    for statement in steps.iter().flat_map(|step| split_statements(&step.text)) {
+        for keyword in clause_keywords(statement.text) {
+            match keyword.as_str() {
+                "IF" => counts.ifs += 1,
+                "ELSE" => counts.elses += 1,
+                "SELECT" => counts.selects += 1,
+                "WHEN" => counts.whens += 1,
+                "DO" if is_do_loop(statement.text) => counts.do_loops += 1,
+                _ => {}
+            }
+        }
+    }
+    counts.macro_ifs = lexer::tokens(&content.text)
+        .filter(|token| token.kind == TokenKind::MacroCall && token.text.eq_ignore_ascii_case("%IF"))
+        .count();
+    counts
+}
+
+// is_do_loop says whether the DO of a statement starts a loop, i.e. has something after it: the
+// DO of "do i = 1 to 3;" but not of "if x then do;"
+fn is_do_loop(text: &str) -> bool {
+    let mut tokens = lexer::tokens(text).filter(|token| token.kind != TokenKind::Comment);
+    tokens.find(|token| token.is_word("DO")).is_some()
+        && tokens.next().is_some_and(|next| next.kind != TokenKind::Semicolon && next.text != "=")
+}
+
+pub fn complexity(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    vec![Finding::new(file_id, "complexity", count_branches(content).score().to_string())]
+}
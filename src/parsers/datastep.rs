@@ -0,0 +1,135 @@
+use super::lexer::{self, TokenKind};
+use super::{split_statements, FileContent, Finding};
+
+pub mod arrays;
+pub mod hash;
+pub mod retain;
+pub mod statements;
+
+/* -------------------------
+* DATA step parse functions:
+* - get_datastep: mirroring get_sql, captures each DATA ...; ... RUN; block with its starting
+*   line and the output dataset names from the DATA statement. A step that has no RUN; is
+*   closed by the next DATA or PROC statement, or the end of the file, as SAS does.
+* - get_datastep_metrics: statement counts and nesting depth of each step (statements.rs).
+* - get_arrays: the ARRAY statements of each step (arrays.rs).
+* - get_hash_objects: the hash objects and iterators the steps declare (hash.rs).
+* - get_retain_lag: the RETAIN statements, sum statements and LAG/DIF calls of each step (retain.rs).
+* find_inline_data finds the data lines that follow a DATALINES or CARDS statement (LINES too), up
+* to the line with the closing semicolon (;;;; for DATALINES4, CARDS4 and LINES4), from the Data
+* tokens of the lexer.
+* --------------------------- */
+
+// DataStep is a single DATA step block found in a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataStep {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub outputs: Vec<String>,
+    pub text: String,
+}
+
+pub fn find_datasteps(content: &FileContent) -> Vec<DataStep> {
+    let mut steps: Vec<DataStep> = Vec::new();
+    // (start offset, end offset of the last statement seen, output datasets)
+    let mut open: Option<(usize, usize, Vec<String>)> = None;
+
+    for statement in split_statements(&content.text) {
+        let keyword = statement.keyword();
+        if keyword == "RUN" {
+            if let Some((start, _, outputs)) = open.take() {
+                steps.push(build_step(content, start, statement.end, outputs));
+            }
+            continue;
+        }
+        if keyword == "DATA" || keyword == "PROC" {
+            if let Some((start, last_end, outputs)) = open.take() {
+                steps.push(build_step(content, start, last_end, outputs));
+            }
+            if keyword == "DATA" {
+                let outputs = dataset_names(&statement.text[4..statement.text.len() - 1]);
+                open = Some((statement.start, statement.end, outputs));
+            }
+            continue;
+        }
+        if let Some((_, last_end, _)) = open.as_mut() {
+            *last_end = statement.end;
+        }
+    }
+    if let Some((start, last_end, outputs)) = open {
+        steps.push(build_step(content, start, last_end, outputs));
+    }
+    steps
+}
+
+fn build_step(content: &FileContent, start: usize, end: usize, outputs: Vec<String>) -> DataStep {
+    DataStep {
+        start_line: content.line_at(start),
+        end_line: content.line_at(end.saturating_sub(1)),
+        outputs,
+        text: content.text[start..end].to_string(),
+    }
+}
+
+// dataset_names returns the dataset names in a dataset list such as "a b(keep=x) lib.c / view=v",
+// leaving out the parenthesized dataset options, NAME=value options and anything after the slash
+pub fn dataset_names(list: &str) -> Vec<String> {
+    let list = list.split('/').next().unwrap_or("");
+    let mut names: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    for c in list.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if depth > 0 => {}
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    names.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        names.push(current);
+    }
+    // statement options such as END=eof or NOBS=n are not datasets
+    names.retain(|name| !name.contains('='));
+    names
+}
+
+// InlineData is the block of data lines of a DATALINES or CARDS statement (1-based, inclusive)
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineData {
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl InlineData {
+    pub fn contains(&self, line: usize) -> bool {
+        self.start_line <= line && line <= self.end_line
+    }
+}
+
+pub fn find_inline_data(content: &FileContent) -> Vec<InlineData> {
+    lexer::tokens(&content.text)
+        .filter(|token| token.kind == TokenKind::Data)
+        .map(|token| InlineData {
+            start_line: content.line_at(token.start),
+            end_line: content.line_at(token.end() - 1),
+        })
+        .collect()
+}
+
+pub fn get_datastep(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_datasteps(content)
+        .iter()
+        .map(|step| {
+            Finding::new(file_id, "get_datastep", format!("({}, {}, {})", step.start_line, step.outputs.join(" "), step.text))
+                .lines(step.start_line, step.end_line)
+                .value(&step.text)
+                .extra("outputs", step.outputs.join(" "))
+        })
+        .collect()
+}
@@ -0,0 +1,85 @@
+use super::find_datasteps;
+use crate::parsers::{split_statements, FileContent, Finding};
+
+/* -------------------------
+* ARRAY statement parser:
+* - get_arrays: returns each ARRAY statement of a DATA step as line, array name, dimension and
+*   member variables. Arrays are how a DATA step walks wide tables, and each one usually becomes a
+*   reshape (or a list of columns) when the step is converted.
+* The dimension is written as in the braces: 12, *, 1:5 or 2,3 for a two-dimensional array, and is
+* empty for an implicit array. The members are the variable list as written (x1-x12, _numeric_,
+* _temporary_), without the $ and length and the initial values; when there is no list the
+* members are the variables SAS names after the array (name1, name2, ...), left empty here.
+* --------------------------- */
+
+// ArrayDefinition is one ARRAY statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrayDefinition {
+    pub line: usize,
+    pub name: String,
+    pub dimension: String,
+    pub members: String,
+}
+
+pub fn find_arrays(content: &FileContent) -> Vec<ArrayDefinition> {
+    let mut arrays: Vec<ArrayDefinition> = Vec::new();
+    for step in find_datasteps(content) {
+        for statement in split_statements(&step.text) {
+            if statement.keyword() != "ARRAY" {
+                continue;
+            }
+            let body = statement.text["ARRAY".len()..statement.text.len() - 1].trim_start();
+            let name_end = body.find(|c: char| c.is_whitespace() || matches!(c, '{' | '[' | '(')).unwrap_or(body.len());
+            let name = &body[..name_end];
+            if name.is_empty() || name.starts_with('=') {
+                continue;
+            }
+            let mut rest = body[name_end..].trim_start();
+            let mut dimension = "";
+            if let Some(close) = rest.chars().next().and_then(closing_bracket) {
+                let end = rest.find(close).unwrap_or(rest.len());
+                dimension = rest[1..end].trim();
+                rest = rest.get(end + 1..).unwrap_or("");
+            }
+            // the $ of a character array and its length
+            let mut words = rest.trim_start();
+            if let Some(after) = words.strip_prefix('$') {
+                words = after.trim_start();
+            }
+            let length_end = words.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(words.len());
+            if length_end > 0 && words[length_end..].starts_with(|c: char| c.is_whitespace() || c == '(') {
+                words = &words[length_end..];
+            }
+            let members = words.split('(').next().unwrap_or("");
+            arrays.push(ArrayDefinition {
+                line: step.start_line + step.text[..statement.start].matches('\n').count(),
+                name: name.to_string(),
+                dimension: dimension.split_whitespace().collect::<Vec<&str>>().join(""),
+                members: members.split_whitespace().collect::<Vec<&str>>().join(" "),
+            });
+        }
+    }
+    arrays
+}
+
+fn closing_bracket(open: char) -> Option<char> {
+    match open {
+        '{' => Some('}'),
+        '[' => Some(']'),
+        '(' => Some(')'),
+        _ => None,
+    }
+}
+
+pub fn get_arrays(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_arrays(content)
+        .iter()
+        .map(|a| {
+            Finding::new(file_id, "get_arrays", format!("({}, {}, {}, {})", a.line, a.name, a.dimension, a.members))
+                .at(a.line)
+                .value(&a.name)
+                .extra("dimension", &a.dimension)
+                .extra("members", &a.members)
+        })
+        .collect()
+}
@@ -0,0 +1,57 @@
+use crate::parsers::macrovars::call_arguments;
+use crate::parsers::patterns::HASH_DECLARE;
+use crate::parsers::{FileContent, Finding};
+
+/* -------------------------
+* Hash object parser:
+* - get_hash_objects: returns each hash object and hash iterator a DATA step declares
+*   (DECLARE HASH h(...), DCL HITER hi('h'), h = _NEW_ HASH(...)) as line, kind (HASH or HITER),
+*   object name and the dataset loaded into it by the DATASET: argument (for an iterator, the hash
+*   object it walks). Hash lookups have no direct SQL or dataframe equivalent, so every step that
+*   uses one needs to be translated by hand.
+* --------------------------- */
+
+// HashObject is one hash object or hash iterator declaration
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashObject {
+    pub line: usize,
+    pub kind: String,
+    pub name: String,
+    pub dataset: String,
+}
+
+pub fn find_hash_objects(content: &FileContent) -> Vec<HashObject> {
+    let mut objects: Vec<HashObject> = Vec::new();
+    for caps in HASH_DECLARE.captures_iter(&content.text) {
+        let whole = caps.get(0).unwrap();
+        let kind = caps.get(1).or(caps.get(4)).map_or("", |m| m.as_str()).to_uppercase();
+        let name = caps.get(2).or(caps.get(3)).map_or("", |m| m.as_str());
+        let arguments = match caps.get(5) {
+            Some(paren) => call_arguments(&content.text[paren.end()..]),
+            None => Vec::new(),
+        };
+        let dataset = match kind.as_str() {
+            "HITER" => arguments.first().map(String::as_str),
+            _ => arguments.iter().find_map(|argument| {
+                let (name, value) = argument.split_once(':')?;
+                name.trim().eq_ignore_ascii_case("DATASET").then_some(value)
+            }),
+        };
+        let dataset = dataset.unwrap_or("").trim().trim_matches(['\'', '"']).to_string();
+        objects.push(HashObject { line: content.line_at(whole.start()), kind, name: name.to_string(), dataset });
+    }
+    objects
+}
+
+pub fn get_hash_objects(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_hash_objects(content)
+        .iter()
+        .map(|h| {
+            Finding::new(file_id, "get_hash_objects", format!("({}, {}, {}, {})", h.line, h.kind, h.name, h.dataset))
+                .at(h.line)
+                .value(&h.name)
+                .extra("kind", &h.kind)
+                .extra("dataset", &h.dataset)
+        })
+        .collect()
+}
@@ -0,0 +1,72 @@
+use super::find_datasteps;
+use crate::parsers::lexer::{self, TokenKind};
+use crate::parsers::macrovars::call_arguments;
+use crate::parsers::{split_statements, FileContent, Finding};
+
+/* -------------------------
+* Row order parser:
+* - get_retain_lag: flags the DATA step constructs that depend on the order of the rows, as line,
+*   kind and detail: RETAIN statements (with their variable list), sum statements (total + x;,
+*   which retain total implicitly; the detail is the variable) and LAG/LAGn and DIF/DIFn calls
+*   (with their argument). These carry values from one row to the next, which set-based SQL and
+*   dataframe code do not do, so they are the hardest part of converting a DATA step.
+* --------------------------- */
+
+// RowOrderUse is one RETAIN statement, sum statement or LAG/DIF call
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowOrderUse {
+    pub line: usize,
+    pub kind: String,
+    pub detail: String,
+}
+
+pub fn find_row_order(content: &FileContent) -> Vec<RowOrderUse> {
+    let mut uses: Vec<RowOrderUse> = Vec::new();
+    for step in find_datasteps(content) {
+        for statement in split_statements(&step.text) {
+            let line = step.start_line + step.text[..statement.start].matches('\n').count();
+            let body = &statement.text[..statement.text.len() - 1];
+            let name_end = body.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(body.len());
+            let (name, rest) = (&body[..name_end], body[name_end..].trim_start());
+            if name.eq_ignore_ascii_case("RETAIN") && !rest.starts_with('=') {
+                uses.push(RowOrderUse {
+                    line,
+                    kind: String::from("RETAIN"),
+                    detail: rest.split_whitespace().collect::<Vec<&str>>().join(" "),
+                });
+            } else if !name.is_empty()
+                && rest.starts_with('+')
+                && !["PUT", "INPUT"].iter().any(|keyword| name.eq_ignore_ascii_case(keyword))
+            {
+                // a sum statement; PUT +3 and INPUT +3 move the column pointer
+                uses.push(RowOrderUse { line, kind: String::from("SUM"), detail: name.to_string() });
+            }
+        }
+        let tokens: Vec<_> = lexer::tokens(&step.text).filter(|token| token.kind != TokenKind::Comment).collect();
+        for pair in tokens.windows(2) {
+            let (word, next) = (&pair[0], &pair[1]);
+            if word.kind != TokenKind::Word || next.text != "(" {
+                continue;
+            }
+            let function = word.text.to_uppercase();
+            let number = function.strip_prefix("LAG").or_else(|| function.strip_prefix("DIF"));
+            if !number.is_some_and(|n| n.chars().all(|c| c.is_ascii_digit())) {
+                continue;
+            }
+            uses.push(RowOrderUse {
+                line: step.start_line + step.text[..word.start].matches('\n').count(),
+                kind: function,
+                detail: call_arguments(&step.text[next.end()..]).first().cloned().unwrap_or_default(),
+            });
+        }
+    }
+    uses.sort_by_key(|row_order_use| row_order_use.line);
+    uses
+}
+
+pub fn get_retain_lag(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_row_order(content)
+        .iter()
+        .map(|u| Finding::new(file_id, "get_retain_lag", format!("({}, {}, {})", u.line, u.kind, u.detail)).at(u.line).value(&u.detail).extra("kind", &u.kind))
+        .collect()
+}
@@ -0,0 +1,199 @@
+use super::{find_datasteps, DataStep};
+use crate::parsers::lexer;
+use crate::parsers::{split_statements, split_word, FileContent, Finding};
+
+/* -------------------------
+* DATA step statement parser:
+* - get_datastep_metrics: reads the statements of each DATA step and returns its structural metrics:
+*   the number of statements, how many of them are SET, MERGE, IF, DO, OUTPUT, KEEP and DROP
+*   statements, and the deepest nesting of conditional and DO blocks.
+* A statement embedded in IF ... THEN, ELSE, WHEN (...) or OTHERWISE (if x then output;) is counted
+* too. Nesting goes up for every DO (IF ... THEN DO, ELSE DO, WHEN (...) DO and the DO loops) and
+* SELECT block and down at its END; a conditional statement without a block is one level below the
+* block it is in, so a step with a single plain IF has depth 1.
+* - get_do_nesting: returns one row per file with the number of DO ... END blocks in its DATA steps
+*   and the deepest nesting of DO blocks inside one another, to rank files by how hard their steps
+*   are to convert. Only DO blocks count towards this depth (SELECT blocks are matched to their END
+*   but not counted); %DO is macro code and is not counted.
+* --------------------------- */
+
+// the statements counted in each step, in column order
+pub const COUNTED_STATEMENTS: [&str; 7] = ["SET", "MERGE", "IF", "DO", "OUTPUT", "KEEP", "DROP"];
+
+pub const DATASTEP_METRIC_COLUMNS: [&str; 11] =
+    ["line", "outputs", "statements", "set", "merge", "if", "do", "output", "keep", "drop", "max_depth"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepMetrics {
+    pub line: usize,
+    pub outputs: Vec<String>,
+    // statements between the DATA statement and RUN
+    pub statements: usize,
+    // how many of each COUNTED_STATEMENTS the step has
+    pub counts: [usize; 7],
+    pub max_depth: usize,
+}
+
+impl StepMetrics {
+    pub fn from_step(step: &DataStep) -> StepMetrics {
+        let mut metrics = StepMetrics {
+            line: step.start_line,
+            outputs: step.outputs.clone(),
+            statements: 0,
+            counts: [0; 7],
+            max_depth: 0,
+        };
+        let mut depth = 0;
+        for statement in split_statements(&step.text) {
+            let keywords = clause_keywords(statement.text);
+            if matches!(keywords.first().map(String::as_str), Some("DATA") | Some("RUN")) {
+                continue;
+            }
+            metrics.statements += 1;
+            for keyword in &keywords {
+                if let Some(i) = COUNTED_STATEMENTS.iter().position(|counted| counted == keyword) {
+                    metrics.counts[i] += 1;
+                }
+            }
+            let conditional = keywords.iter().any(|keyword| keyword == "IF" || keyword == "ELSE");
+            match keywords.last().map(String::as_str) {
+                Some("DO") | Some("SELECT") => {
+                    depth += 1;
+                    metrics.max_depth = metrics.max_depth.max(depth);
+                }
+                Some("END") => depth = depth.saturating_sub(1),
+                _ if conditional => metrics.max_depth = metrics.max_depth.max(depth + 1),
+                _ => {}
+            }
+        }
+        metrics
+    }
+
+    pub fn record(&self) -> Vec<String> {
+        let mut record = vec![self.line.to_string(), self.outputs.join(" "), self.statements.to_string()];
+        record.extend(self.counts.iter().map(|count| count.to_string()));
+        record.push(self.max_depth.to_string());
+        record
+    }
+}
+
+// clause_keywords returns the first word of a statement, uppercased, followed by the first words
+// of the statements embedded in it by IF ... THEN, ELSE, WHEN (...) and OTHERWISE: "else if x then
+// output;" gives ELSE, IF, OUTPUT
+pub(crate) fn clause_keywords(text: &str) -> Vec<String> {
+    let mut keywords: Vec<String> = Vec::new();
+    let mut rest = text.trim();
+    loop {
+        let (word, after) = split_word(rest);
+        let keyword = word.split([';', '(', '=']).next().unwrap_or("").to_uppercase();
+        if keyword.is_empty() {
+            break;
+        }
+        keywords.push(keyword.clone());
+        rest = match keyword.as_str() {
+            "ELSE" | "OTHERWISE" => after.trim_start(),
+            "WHEN" => match group_end(&rest[keyword.len()..]) {
+                Some(offset) => rest[keyword.len() + offset..].trim_start(),
+                None => break,
+            },
+            "IF" => match then_offset(after) {
+                Some(offset) => after[offset..].trim_start(),
+                None => break,
+            },
+            _ => break,
+        };
+    }
+    keywords
+}
+
+// group_end returns the offset just past the parenthesized group at the start of text (after any
+// whitespace), such as the values of a WHEN statement
+fn group_end(text: &str) -> Option<usize> {
+    let start = text.len() - text.trim_start().len();
+    if !text[start..].starts_with('(') {
+        return None;
+    }
+    let mut depth = 0;
+    for token in lexer::tokens(&text[start..]) {
+        match token.text {
+            "(" => depth += 1,
+            ")" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(start + token.end());
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// then_offset returns the offset just past the THEN keyword of an IF condition (not one in a string)
+fn then_offset(condition: &str) -> Option<usize> {
+    lexer::tokens(condition).find(|token| token.is_word("THEN")).map(|token| token.end())
+}
+
+// DoNesting is the DO block count and deepest DO nesting of a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct DoNesting {
+    pub blocks: usize,
+    pub max_depth: usize,
+}
+
+pub fn find_do_nesting(content: &FileContent) -> DoNesting {
+    let mut nesting = DoNesting { blocks: 0, max_depth: 0 };
+    for step in find_datasteps(content) {
+        // the open blocks: true for DO, false for SELECT
+        let mut open: Vec<bool> = Vec::new();
+        for statement in split_statements(&step.text) {
+            let keywords = clause_keywords(statement.text);
+            // do = 1; and end = 1; assign variables
+            let (first, rest) = split_word(statement.text.trim());
+            if rest.trim_start().starts_with('=') || first.contains('=') {
+                continue;
+            }
+            match keywords.last().map(String::as_str) {
+                Some("DO") => {
+                    open.push(true);
+                    nesting.blocks += 1;
+                    nesting.max_depth = nesting.max_depth.max(open.iter().filter(|is_do| **is_do).count());
+                }
+                Some("SELECT") => open.push(false),
+                Some("END") => {
+                    open.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+    nesting
+}
+
+pub fn get_do_nesting(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    let nesting = find_do_nesting(content);
+    vec![Finding::new(file_id, "get_do_nesting", format!("({}, {})", nesting.blocks, nesting.max_depth))
+        .value(nesting.blocks)
+        .extra("max_depth", nesting.max_depth)]
+}
+
+pub fn find_step_metrics(content: &FileContent) -> Vec<StepMetrics> {
+    find_datasteps(content).iter().map(StepMetrics::from_step).collect()
+}
+
+pub fn get_datastep_metrics(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_step_metrics(content)
+        .iter()
+        .map(|m| {
+            let record = m.record();
+            Finding::new(file_id, "get_datastep_metrics", format!("({})", record.join(", ")))
+                .at(m.line)
+                .value(&record[1])
+                .extras(&DATASTEP_METRIC_COLUMNS[2..], &record[2..])
+        })
+        .collect()
+}
+
+pub fn datastep_metric_rows(content: &FileContent) -> Vec<Vec<String>> {
+    find_step_metrics(content).iter().map(StepMetrics::record).collect()
+}
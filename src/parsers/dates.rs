@@ -0,0 +1,72 @@
+use super::patterns::{DATETIME_LITERAL, DATE_LITERAL, DDMONYY, DDMONYYYY, ISO_DATE, US_DATE, US_DATE_SHORT};
+use super::{column_at, FileContent, Finding};
+use regex::Regex;
+
+/* -------------------------
+* Date parse functions:
+* - find_date: finds the dates written in the code, with their line, column and the format they
+*   are written in:
+*   - DATETIME_LITERAL: a SAS datetime literal, '01JAN2020:10:30:00'dt
+*   - DATE_LITERAL: a SAS date literal, '01JAN2020'd or '01JAN20'd
+*   - YYYY-MM-DD
+*   - MM/DD/YYYY and MM/DD/YY
+*   - DDMONYYYY and DDMONYY (01JAN2020, 01JAN20)
+* The formats are tried in that order and a date is reported once, in the first format that
+* matches it, so the 01JAN2020 in '01JAN2020'd is a DATE_LITERAL and not also a DDMONYYYY.
+* Hardcoded dates are what make a program need editing every period it is run for.
+* --------------------------- */
+
+// DateMatch is one date and the format it is written in
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateMatch {
+    pub line: usize,
+    pub column: usize,
+    pub format: &'static str,
+    pub text: String,
+}
+
+pub fn find_dates(content: &FileContent) -> Vec<DateMatch> {
+    let rules: [(&str, &Regex); 7] = [
+        ("DATETIME_LITERAL", &DATETIME_LITERAL),
+        ("DATE_LITERAL", &DATE_LITERAL),
+        ("YYYY-MM-DD", &ISO_DATE),
+        ("MM/DD/YYYY", &US_DATE),
+        ("MM/DD/YY", &US_DATE_SHORT),
+        ("DDMONYYYY", &DDMONYYYY),
+        ("DDMONYY", &DDMONYY),
+    ];
+    let mut dates: Vec<DateMatch> = Vec::new();
+    for (line_number, line) in content.text.lines().enumerate() {
+        // the byte ranges of the dates already found on the line
+        let mut found: Vec<(usize, usize)> = Vec::new();
+        for (format, re) in &rules {
+            for m in re.find_iter(line) {
+                if found.iter().any(|&(start, end)| m.start() < end && start < m.end()) {
+                    continue;
+                }
+                found.push((m.start(), m.end()));
+                dates.push(DateMatch {
+                    line: line_number + 1,
+                    column: column_at(line, m.start()),
+                    format,
+                    text: m.as_str().to_string(),
+                });
+            }
+        }
+    }
+    dates.sort_by_key(|date| (date.line, date.column));
+    dates
+}
+
+pub fn find_date(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_dates(content)
+        .iter()
+        .map(|d| {
+            Finding::new(file_id, "find_date", format!("({}, {}, {}, {})", d.line, d.column, d.format, d.text))
+                .at(d.line)
+                .value(&d.text)
+                .extra("column", d.column)
+                .extra("format", d.format)
+        })
+        .collect()
+}
@@ -0,0 +1,102 @@
+use super::lexer;
+use super::macros::find_unclosed_macros;
+use super::patterns::DEPRECATED_RULES;
+use super::{FileContent, Finding};
+
+/* -------------------------
+* Deprecated syntax parse functions:
+* - get_deprecated: runs every deprecated syntax rule against the code and returns
+*   (line, rule id, match) for each hit, so modernization work can target the legacy constructs
+*   explicitly; the rule's description goes with the finding. Matches inside string literals and
+*   comments are skipped, and so is a match that is part of a macro statement (%GOTO is macro
+*   code, not the DATA step GOTO). The patterns are case-insensitive.
+*   DEP006 is not a pattern: it is each %MACRO statement that no %MEND closes, which older SAS
+*   releases tolerated at the end of an included file.
+* --------------------------- */
+
+// DeprecatedRule is one legacy construct the deprecated syntax scanner looks for
+pub struct DeprecatedRule {
+    pub id: &'static str,
+    pub description: &'static str,
+    pub pattern: &'static str,
+}
+
+pub fn deprecated_rules() -> Vec<DeprecatedRule> {
+    vec![
+        DeprecatedRule {
+            id: "DEP001",
+            description: "PROC CONVERT (BMDP, OSIRIS and SPSS files; replaced by PROC IMPORT and library engines)",
+            pattern: r"\bPROC\s+CONVERT\b",
+        },
+        DeprecatedRule {
+            id: "DEP002",
+            description: "PROC ACCESS or PROC DBLOAD (SAS/ACCESS descriptors; replaced by LIBNAME engines)",
+            pattern: r"\bPROC\s+(?:ACCESS|DBLOAD)\b",
+        },
+        DeprecatedRule {
+            id: "DEP003",
+            description: "Version 5 or 6 library engine (V5, V6, V604 ... V612, V6TAPE)",
+            pattern: r"\bLIBNAME\s+[A-Z_]\w*\s+V[56]\w*\b|\bENGINE\s*=\s*V[56]\w*\b",
+        },
+        DeprecatedRule {
+            id: "DEP004",
+            description: "%INCLUDE of input line numbers (%INC 12-20;), the Version 6 interactive shortcut",
+            pattern: r"%INC(?:LUDE)?\s+\d+(?:\s*-\s*\d+)?(?:\s+\d+(?:\s*-\s*\d+)?)*\s*(?:/[^;]*)?;",
+        },
+        DeprecatedRule {
+            id: "DEP005",
+            description: "GOTO or GO TO statement",
+            pattern: r"\bGO\s*TO\s+[A-Z_]\w*\s*;",
+        },
+    ]
+}
+
+// DeprecatedHit is one match of a deprecated syntax rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeprecatedHit {
+    pub line: usize,
+    pub rule_id: &'static str,
+    pub description: &'static str,
+    pub matched: String,
+}
+
+const UNCLOSED_MACRO: &str = "%MACRO without %MEND, tolerated by older releases at the end of an included file";
+
+pub fn find_deprecated(content: &FileContent) -> Vec<DeprecatedHit> {
+    // the code with string literals blanked out; it keeps the offsets of text, which the
+    // uppercased code does not where uppercasing changes the length (ß is SS)
+    let code = lexer::blank_strings(&content.text);
+    let mut hits: Vec<DeprecatedHit> = Vec::new();
+    for (rule, re) in DEPRECATED_RULES.iter() {
+        for m in re.find_iter(&code) {
+            if !m.as_str().starts_with('%') && code[..m.start()].ends_with('%') {
+                continue;
+            }
+            let matched = &content.text[m.range()];
+            hits.push(DeprecatedHit {
+                line: content.line_at(m.start()),
+                rule_id: rule.id,
+                description: rule.description,
+                matched: matched.split_whitespace().collect::<Vec<&str>>().join(" "),
+            });
+        }
+    }
+    for (line, name) in find_unclosed_macros(content) {
+        hits.push(DeprecatedHit { line, rule_id: "DEP006", description: UNCLOSED_MACRO, matched: format!("%MACRO {} without %MEND", name) });
+    }
+    hits.sort_by_key(|hit| hit.line);
+    hits
+}
+
+pub fn get_deprecated(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_deprecated(content)
+        .iter()
+        .map(|hit| {
+            Finding::new(file_id, "get_deprecated", format!("({}, {}, {})", hit.line, hit.rule_id, hit.matched))
+                .at(hit.line)
+                .value(&hit.matched)
+                .extra("rule_id", hit.rule_id)
+                .extra("description", hit.description)
+        })
+        .collect()
+}
@@ -0,0 +1,138 @@
+use super::FileContent;
+use crate::scanner::FileInfo;
+use crate::sha1::sha1;
+use std::collections::{HashMap, HashSet};
+
+/* -------------------------
+* Cross-file duplicate code detection (with --duplicates):
+* - find_shingles: normalizes the code lines of a file (comments removed, whitespace collapsed,
+*   uppercased, blank lines dropped) and fingerprints every run of SHINGLE_LINES consecutive
+*   code lines, keeping the source lines each run covers
+* - find_duplicates: matches the fingerprints of every scanned file against the others, joining
+*   consecutive matching runs into one region, and returns each pair of copied regions with the
+*   number of code lines they share
+* Copies within one file are not reported, and neither are copies shorter than SHINGLE_LINES
+* code lines. A region copied into several files is reported once per pair of files.
+* --------------------------- */
+
+pub const DUPLICATE_HEADERS: [&str; 9] =
+    ["uuid", "file", "start_line", "end_line", "other_uuid", "other_file", "other_start_line", "other_end_line", "lines"];
+
+// how many consecutive code lines are fingerprinted together
+pub const SHINGLE_LINES: usize = 10;
+
+// Shingle is the fingerprint of SHINGLE_LINES consecutive code lines, starting on line and
+// ending on end_line
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shingle {
+    pub line: usize,
+    pub end_line: usize,
+    pub hash: u64,
+}
+
+// DuplicateRegion is one block of code found in two files
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateRegion {
+    pub uuid: String,
+    pub file: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub other_uuid: String,
+    pub other_file: String,
+    pub other_start_line: usize,
+    pub other_end_line: usize,
+    // the code lines the regions share, not counting blank lines and comments
+    pub lines: usize,
+}
+
+impl DuplicateRegion {
+    pub fn record(&self) -> Vec<String> {
+        vec![
+            self.uuid.clone(),
+            self.file.clone(),
+            self.start_line.to_string(),
+            self.end_line.to_string(),
+            self.other_uuid.clone(),
+            self.other_file.clone(),
+            self.other_start_line.to_string(),
+            self.other_end_line.to_string(),
+            self.lines.to_string(),
+        ]
+    }
+}
+
+// normalized_lines returns the code lines of the text with their line numbers, uppercased and
+// with their whitespace collapsed, leaving out blank lines
+fn normalized_lines(text: &str) -> Vec<(usize, String)> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.split_whitespace().collect::<Vec<&str>>().join(" ").to_uppercase()))
+        .filter(|(_, line)| !line.is_empty())
+        .collect()
+}
+
+pub fn find_shingles(content: &FileContent) -> Vec<Shingle> {
+    normalized_lines(&content.text)
+        .windows(SHINGLE_LINES)
+        .map(|window| {
+            let code: Vec<&str> = window.iter().map(|(_, line)| line.as_str()).collect();
+            let digest = sha1(code.join("\n").as_bytes());
+            Shingle {
+                line: window[0].0,
+                end_line: window[SHINGLE_LINES - 1].0,
+                hash: u64::from_be_bytes(digest[..8].try_into().unwrap()),
+            }
+        })
+        .collect()
+}
+
+// find_duplicates pairs up the matching shingles of different files and joins each run of
+// consecutive matches (shingle i and j, i + 1 and j + 1, ...) into one region
+pub fn find_duplicates(files: &[(&FileInfo, Vec<Shingle>)]) -> Vec<DuplicateRegion> {
+    let mut by_hash: HashMap<u64, Vec<(usize, usize)>> = HashMap::new();
+    for (f, (_, shingles)) in files.iter().enumerate() {
+        for (i, shingle) in shingles.iter().enumerate() {
+            by_hash.entry(shingle.hash).or_default().push((f, i));
+        }
+    }
+    // every matching pair of shingles in two different files: (file, shingle, other file, other shingle)
+    let mut matches: HashSet<(usize, usize, usize, usize)> = HashSet::new();
+    for occurrences in by_hash.values() {
+        for (k, &(f, i)) in occurrences.iter().enumerate() {
+            for &(g, j) in &occurrences[k + 1..] {
+                if f < g {
+                    matches.insert((f, i, g, j));
+                } else if g < f {
+                    matches.insert((g, j, f, i));
+                }
+            }
+        }
+    }
+    let mut starts: Vec<&(usize, usize, usize, usize)> = matches
+        .iter()
+        .filter(|&&(f, i, g, j)| i == 0 || j == 0 || !matches.contains(&(f, i - 1, g, j - 1)))
+        .collect();
+    starts.sort();
+
+    let mut regions: Vec<DuplicateRegion> = Vec::new();
+    for &(f, i, g, j) in starts {
+        let mut run = 1;
+        while matches.contains(&(f, i + run, g, j + run)) {
+            run += 1;
+        }
+        let (file, shingles) = &files[f];
+        let (other, other_shingles) = &files[g];
+        regions.push(DuplicateRegion {
+            uuid: file.uuid.clone(),
+            file: file.path(),
+            start_line: shingles[i].line,
+            end_line: shingles[i + run - 1].end_line,
+            other_uuid: other.uuid.clone(),
+            other_file: other.path(),
+            other_start_line: other_shingles[j].line,
+            other_end_line: other_shingles[j + run - 1].end_line,
+            lines: SHINGLE_LINES + run - 1,
+        });
+    }
+    regions
+}
@@ -0,0 +1,85 @@
+use super::libname::find_libnames;
+use super::passthrough::find_connections;
+use super::{FileContent, Finding};
+use crate::scanner::FileInfo;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+
+/* -------------------------
+* SAS/ACCESS engine inventory:
+* - get_engines: return each engine a file uses as line, statement (LIBNAME or CONNECT) and engine:
+*   the engine of every LIBNAME statement and the DBMS of every PROC SQL CONNECT TO. CONNECT USING
+*   libref is reported with the engine of that libref's LIBNAME in the same file, when there is one.
+*   The default BASE engine and its version aliases (V9, V8, V7) are not reported, nor are
+*   LIBNAME CLEAR and LIST statements.
+* engine_counts then totals the engines of every scanned file into the engines table: one row per
+*   engine with the number of files using it and its number of references, most used first.
+* --------------------------- */
+
+pub const ENGINE_HEADERS: [&str; 3] = ["engine", "files", "references"];
+
+// the engines of ordinary SAS libraries, which need no database
+const BASE_ENGINES: &[&str] = &["BASE", "V9", "V8", "V7"];
+
+// EngineUse is one LIBNAME or CONNECT statement naming an engine
+#[derive(Debug, Clone, PartialEq)]
+pub struct EngineUse {
+    pub line: usize,
+    pub statement: String,
+    pub engine: String,
+}
+
+pub fn find_engines(content: &FileContent) -> Vec<EngineUse> {
+    let libnames = find_libnames(content);
+    let mut engines: Vec<EngineUse> = Vec::new();
+    for libname in &libnames {
+        if libname.engine.is_empty() || BASE_ENGINES.contains(&libname.engine.as_str()) {
+            continue;
+        }
+        if matches!(libname.engine.as_str(), "CLEAR" | "LIST") || libname.libref.eq_ignore_ascii_case("_ALL_") {
+            continue;
+        }
+        engines.push(EngineUse { line: libname.line, statement: String::from("LIBNAME"), engine: libname.engine.clone() });
+    }
+    for connection in find_connections(content) {
+        let engine = match connection.using {
+            true => match libnames.iter().rev().find(|l| l.libref.eq_ignore_ascii_case(&connection.dbms) && l.line < connection.line) {
+                Some(libname) => libname.engine.clone(),
+                None => continue,
+            },
+            false => connection.dbms,
+        };
+        engines.push(EngineUse { line: connection.line, statement: String::from("CONNECT"), engine });
+    }
+    engines.sort_by_key(|engine| engine.line);
+    engines
+}
+
+pub fn get_engines(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_engines(content)
+        .iter()
+        .map(|e| Finding::new(file_id, "get_engines", format!("({}, {}, {})", e.line, e.statement, e.engine)).at(e.line).value(&e.engine).extra("statement", &e.statement))
+        .collect()
+}
+
+// engine_counts returns the engines table rows: engine, files using it and references, the engine
+// used by the most files first
+pub fn engine_counts(files: &[(&FileInfo, Vec<EngineUse>)]) -> Vec<Vec<String>> {
+    // engine -> (files, references)
+    let mut counts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for (_, engines) in files {
+        let mut seen: HashSet<&str> = HashSet::new();
+        for engine in engines {
+            let count = counts.entry(&engine.engine).or_default();
+            count.1 += 1;
+            if seen.insert(&engine.engine) {
+                count.0 += 1;
+            }
+        }
+    }
+    let mut rows: Vec<(&str, (usize, usize))> = counts.into_iter().collect();
+    rows.sort_by_key(|(_, count)| Reverse(*count));
+    rows.into_iter()
+        .map(|(engine, (files, references))| vec![engine.to_string(), files.to_string(), references.to_string()])
+        .collect()
+}
@@ -0,0 +1,250 @@
+use super::patterns::EMAIL_OPTION;
+use super::{quoted_end, split_statements, split_word, FileContent, Finding};
+
+/* -------------------------
+* FILENAME parse functions:
+* - get_filename: parses each FILENAME statement into fileref, device type (DISK, PIPE, EMAIL,
+*   URL, FTP, ...), the target path, URL or command, and the remaining options. These point at
+*   the external dependencies of a program. A FILENAME with a quoted target and no device type
+*   is a DISK file.
+* - get_pipes: returns each FILENAME ... PIPE statement as line, fileref and the shell command it
+*   runs. A pipe runs an operating system command every time the file is read or written, so it
+*   is a security concern and a portability blocker of its own, apart from the other filerefs.
+* - get_emails: returns each FILENAME ... EMAIL statement as line, fileref, the TO, CC and BCC
+*   recipients (space separated, a quoted target after EMAIL is a TO address) and the SUBJECT, so
+*   every program that sends email, and to whom, is cataloged.
+* - find_dde: flags each FILENAME ... DDE statement as line, fileref and its DDE triplet split into
+*   application, topic and item ('excel|[book.xlsx]Sheet1!r1c1:r10c5' is EXCEL, [book.xlsx]Sheet1
+*   and r1c1:r10c5; 'excel|system' is the command channel). DDE drives a desktop application, so
+*   it does not work on a server and has to be replaced before a migration.
+* --------------------------- */
+
+pub const FILENAME_COLUMNS: [&str; 5] = ["line", "fileref", "device", "target", "options"];
+
+// device types (and the CLEAR/LIST keywords) that can follow the fileref
+const DEVICE_TYPES: &[&str] = &[
+    "DISK", "PIPE", "EMAIL", "URL", "FTP", "SFTP", "SOCKET", "TEMP", "DUMMY", "CATALOG", "DDE", "CLIPBRD",
+    "ZIP", "TERMINAL", "PRINTER", "PLOTTER", "TAPE", "WEBDAV", "HADOOP", "S3", "GIT", "CLEAR", "LIST",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filename {
+    pub line: usize,
+    pub fileref: String,
+    pub device: String,
+    pub target: String,
+    pub options: String,
+}
+
+pub fn find_filenames(content: &FileContent) -> Vec<Filename> {
+    let mut filenames: Vec<Filename> = Vec::new();
+    for statement in split_statements(&content.text) {
+        if statement.keyword() != "FILENAME" {
+            continue;
+        }
+        let body = statement.text["FILENAME".len()..statement.text.len() - 1].trim();
+        let (fileref, rest) = split_word(body);
+        let mut rest = rest.trim_start();
+
+        let mut device = String::new();
+        let (word, after) = split_word(rest);
+        if DEVICE_TYPES.contains(&word.to_uppercase().as_str()) {
+            device = word.to_uppercase();
+            rest = after.trim_start();
+        }
+
+        let mut target = String::new();
+        if rest.starts_with(['\'', '"', '(']) {
+            let end = quoted_end(rest);
+            target = strip_quotes(&rest[..end]).to_string();
+            rest = rest[end..].trim_start();
+        }
+        if device.is_empty() && !target.is_empty() {
+            device = String::from("DISK");
+        }
+
+        filenames.push(Filename {
+            line: content.line_at(statement.start),
+            fileref: fileref.to_string(),
+            device,
+            target,
+            options: rest.split_whitespace().collect::<Vec<&str>>().join(" "),
+        });
+    }
+    filenames
+}
+
+// strip_quotes removes the quotes around a quoted target, leaving any quotes inside it
+pub(crate) fn strip_quotes(target: &str) -> &str {
+    match target.chars().next() {
+        Some(q @ ('\'' | '"')) => {
+            let inner = &target[1..];
+            inner.strip_suffix(q).unwrap_or(inner)
+        }
+        _ => target,
+    }
+}
+
+// find_pipes returns the FILENAME statements of the PIPE device, with the command in target (an
+// unquoted command, such as a macro variable, is the first word after PIPE)
+pub fn find_pipes(content: &FileContent) -> Vec<Filename> {
+    let mut pipes: Vec<Filename> = find_filenames(content).into_iter().filter(|f| f.device == "PIPE").collect();
+    for pipe in pipes.iter_mut().filter(|pipe| pipe.target.is_empty()) {
+        let (command, options) = split_word(&pipe.options);
+        pipe.target = command.to_string();
+        pipe.options = options.trim_start().to_string();
+    }
+    pipes
+}
+
+pub fn get_pipes(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_pipes(content)
+        .iter()
+        .map(|f| Finding::new(file_id, "get_pipes", format!("({}, {}, {})", f.line, f.fileref, f.target)).at(f.line).value(&f.target).extra("fileref", &f.fileref))
+        .collect()
+}
+
+// Email is a FILENAME ... EMAIL statement with its recipients and subject
+#[derive(Debug, Clone, PartialEq)]
+pub struct Email {
+    pub line: usize,
+    pub fileref: String,
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub bcc: Vec<String>,
+    pub subject: String,
+}
+
+pub fn find_emails(content: &FileContent) -> Vec<Email> {
+    let mut emails: Vec<Email> = Vec::new();
+    for filename in find_filenames(content).into_iter().filter(|f| f.device == "EMAIL") {
+        let mut email = Email {
+            line: filename.line,
+            fileref: filename.fileref,
+            to: Vec::new(),
+            cc: Vec::new(),
+            bcc: Vec::new(),
+            subject: String::new(),
+        };
+        if !filename.target.is_empty() {
+            email.to.push(filename.target);
+        }
+        for caps in EMAIL_OPTION.captures_iter(&filename.options) {
+            let value = &caps[2];
+            let list = match caps[1].to_uppercase().as_str() {
+                "TO" => &mut email.to,
+                "CC" => &mut email.cc,
+                "BCC" => &mut email.bcc,
+                _ => {
+                    // a doubled quote in a quoted subject is one quote
+                    let quote = value.chars().next().filter(|c| *c == '\'' || *c == '"');
+                    email.subject = match quote {
+                        Some(q) => strip_quotes(value).replace(&format!("{}{}", q, q), &q.to_string()),
+                        None => value.to_string(),
+                    };
+                    continue;
+                }
+            };
+            list.extend(addresses(value));
+        }
+        emails.push(email);
+    }
+    emails
+}
+
+// addresses splits a recipient option value, a quoted address or a parenthesized list of them
+fn addresses(value: &str) -> Vec<String> {
+    let list = value.trim_start_matches('(').trim_end_matches(')');
+    let mut addresses: Vec<String> = Vec::new();
+    let mut rest = list.trim_start();
+    while !rest.is_empty() {
+        let end = if rest.starts_with(['\'', '"']) { quoted_end(rest) } else { split_word(rest).0.len() };
+        addresses.push(strip_quotes(&rest[..end]).to_string());
+        rest = rest[end..].trim_start();
+    }
+    addresses
+}
+
+pub fn get_emails(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_emails(content)
+        .iter()
+        .map(|e| {
+            Finding::new(
+                file_id,
+                "get_emails",
+                format!("({}, {}, {}, {}, {}, {})", e.line, e.fileref, e.to.join(" "), e.cc.join(" "), e.bcc.join(" "), e.subject),
+            )
+            .at(e.line)
+            .value(e.to.join(" "))
+            .extra("fileref", &e.fileref)
+            .extra("cc", e.cc.join(" "))
+            .extra("bcc", e.bcc.join(" "))
+            .extra("subject", &e.subject)
+        })
+        .collect()
+}
+
+// DdeLink is a FILENAME ... DDE statement with its application|topic!item triplet split up
+#[derive(Debug, Clone, PartialEq)]
+pub struct DdeLink {
+    pub line: usize,
+    pub fileref: String,
+    pub application: String,
+    pub topic: String,
+    pub item: String,
+}
+
+pub fn find_dde_links(content: &FileContent) -> Vec<DdeLink> {
+    find_filenames(content)
+        .into_iter()
+        .filter(|f| f.device == "DDE")
+        .map(|f| {
+            let target = if f.target.is_empty() { split_word(&f.options).0.to_string() } else { f.target };
+            let (application, rest) = target.split_once('|').unwrap_or(("", &target));
+            let (topic, item) = rest.split_once('!').unwrap_or((rest, ""));
+            DdeLink {
+                line: f.line,
+                fileref: f.fileref,
+                application: application.to_uppercase(),
+                topic: topic.to_string(),
+                item: item.to_string(),
+            }
+        })
+        .collect()
+}
+
+pub fn find_dde(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_dde_links(content)
+        .iter()
+        .map(|d| {
+            Finding::new(file_id, "find_dde", format!("({}, {}, {}, {}, {})", d.line, d.fileref, d.application, d.topic, d.item))
+                .at(d.line)
+                .value(&d.fileref)
+                .extra("application", &d.application)
+                .extra("topic", &d.topic)
+                .extra("item", &d.item)
+        })
+        .collect()
+}
+
+pub fn get_filename(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_filenames(content)
+        .iter()
+        .map(|f| {
+            Finding::new(file_id, "get_filename", format!("({}, {}, {}, {}, {})", f.line, f.fileref, f.device, f.target, f.options))
+                .at(f.line)
+                .value(&f.fileref)
+                .extra("device", &f.device)
+                .extra("target", &f.target)
+                .extra("options", &f.options)
+        })
+        .collect()
+}
+
+// filename_rows returns the FILENAME statements as rows for the filenames table
+pub fn filename_rows(content: &FileContent) -> Vec<Vec<String>> {
+    find_filenames(content)
+        .into_iter()
+        .map(|f| vec![f.line.to_string(), f.fileref, f.device, f.target, f.options])
+        .collect()
+}
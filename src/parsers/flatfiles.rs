@@ -0,0 +1,196 @@
+use super::datastep::find_datasteps;
+use super::datastep::statements::clause_keywords;
+use super::filename::strip_quotes;
+use super::patterns::INFILE_OPTION;
+use super::{quoted_end, split_statements, split_word, FileContent, Finding};
+
+/* -------------------------
+* Flat file parse functions:
+* - get_infiles: returns each INFILE statement of a DATA step with the file it reads (a quoted
+*   path, a fileref, or DATALINES/CARDS for inline data), its DLM= and FIRSTOBS= options and the
+*   rest of its options, paired with the INPUT statement that reads it (the first INPUT after it
+*   in the step). These are the points where flat files are loaded.
+* - get_outfiles: returns each FILE statement of a DATA step that writes an external file (a
+*   quoted path or a fileref; FILE LOG and FILE PRINT write to the SAS log and listing), with its
+*   options and the number of PUT statements that write to it (those after it in the step, up to
+*   the next FILE), so the consumers of the flat files a program writes can be traced.
+* --------------------------- */
+
+pub const INFILE_COLUMNS: [&str; 8] = ["line", "source", "kind", "dlm", "firstobs", "options", "input_line", "input"];
+pub const OUTFILE_COLUMNS: [&str; 5] = ["line", "target", "kind", "options", "puts"];
+
+// Infile is an INFILE statement and the INPUT statement that reads it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Infile {
+    pub line: usize,
+    pub source: String,
+    // PATH, FILEREF or INLINE
+    pub kind: &'static str,
+    pub dlm: String,
+    pub firstobs: String,
+    pub options: String,
+    // the line of the INPUT statement, 0 when the step has none after the INFILE
+    pub input_line: usize,
+    pub input: String,
+}
+
+impl Infile {
+    pub fn record(&self) -> Vec<String> {
+        vec![
+            self.line.to_string(),
+            self.source.clone(),
+            self.kind.to_string(),
+            self.dlm.clone(),
+            self.firstobs.clone(),
+            self.options.clone(),
+            self.input_line.to_string(),
+            self.input.clone(),
+        ]
+    }
+}
+
+pub fn find_infiles(content: &FileContent) -> Vec<Infile> {
+    let mut infiles: Vec<Infile> = Vec::new();
+    for step in find_datasteps(content) {
+        // the index in infiles of the INFILE still waiting for its INPUT
+        let mut waiting: Option<usize> = None;
+        for statement in split_statements(&step.text) {
+            let keyword = statement.keyword();
+            let line = step.start_line + step.text[..statement.start].matches('\n').count();
+            let body = &statement.text[keyword.len()..statement.text.len() - 1];
+            if body.trim_start().starts_with('=') {
+                continue;
+            }
+            if keyword == "INFILE" {
+                let (source, kind, options) = file_target(body);
+                let mut infile = Infile {
+                    line,
+                    source,
+                    kind,
+                    dlm: String::new(),
+                    firstobs: String::new(),
+                    options: compact(options),
+                    input_line: 0,
+                    input: String::new(),
+                };
+                for caps in INFILE_OPTION.captures_iter(options) {
+                    match caps[1].to_uppercase().as_str() {
+                        "FIRSTOBS" => infile.firstobs = caps[2].to_string(),
+                        _ => infile.dlm = caps[2].to_string(),
+                    }
+                }
+                waiting = Some(infiles.len());
+                infiles.push(infile);
+            } else if keyword == "INPUT" {
+                if let Some(i) = waiting.take() {
+                    infiles[i].input_line = line;
+                    infiles[i].input = compact(body);
+                }
+            }
+        }
+    }
+    infiles
+}
+
+// Outfile is a FILE statement and the number of PUT statements writing to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outfile {
+    pub line: usize,
+    pub target: String,
+    // PATH or FILEREF
+    pub kind: &'static str,
+    pub options: String,
+    pub puts: usize,
+}
+
+impl Outfile {
+    pub fn record(&self) -> Vec<String> {
+        vec![self.line.to_string(), self.target.clone(), self.kind.to_string(), self.options.clone(), self.puts.to_string()]
+    }
+}
+
+pub fn find_outfiles(content: &FileContent) -> Vec<Outfile> {
+    let mut outfiles: Vec<Outfile> = Vec::new();
+    for step in find_datasteps(content) {
+        // the index in outfiles of the FILE the PUT statements write to (None for the log)
+        let mut current: Option<usize> = None;
+        for statement in split_statements(&step.text) {
+            let keyword = statement.keyword();
+            let body = &statement.text[keyword.len()..statement.text.len() - 1];
+            if body.trim_start().starts_with('=') {
+                continue;
+            }
+            if keyword == "FILE" {
+                let (target, kind, options) = file_target(body);
+                current = None;
+                if kind == "FILEREF" && matches!(target.to_uppercase().as_str(), "LOG" | "PRINT") {
+                    continue;
+                }
+                current = Some(outfiles.len());
+                outfiles.push(Outfile {
+                    line: step.start_line + step.text[..statement.start].matches('\n').count(),
+                    target,
+                    kind,
+                    options: compact(options),
+                    puts: 0,
+                });
+            } else if let Some(i) = current {
+                if clause_keywords(statement.text).iter().any(|keyword| keyword == "PUT") {
+                    outfiles[i].puts += 1;
+                }
+            }
+        }
+    }
+    outfiles
+}
+
+// file_target splits the body of an INFILE or FILE statement into the file it names, the kind of
+// name (PATH for a quoted path, INLINE for DATALINES/CARDS, FILEREF otherwise) and the options
+pub(crate) fn file_target(body: &str) -> (String, &'static str, &str) {
+    let body = body.trim_start();
+    if body.starts_with(['\'', '"']) {
+        let end = quoted_end(body);
+        return (strip_quotes(&body[..end]).to_string(), "PATH", &body[end..]);
+    }
+    let (name, options) = split_word(body);
+    let kind = match name.to_uppercase().as_str() {
+        "DATALINES" | "CARDS" | "DATALINES4" | "CARDS4" => "INLINE",
+        _ => "FILEREF",
+    };
+    (name.to_string(), kind, options)
+}
+
+// compact joins the words of statement text with single spaces
+fn compact(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+pub fn get_infiles(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_infiles(content)
+        .iter()
+        .map(|f| {
+            let record = f.record();
+            Finding::new(file_id, "get_infiles", format!("({})", record.join(", "))).at(f.line).value(&f.source).extras(&INFILE_COLUMNS[2..], &record[2..])
+        })
+        .collect()
+}
+
+pub fn get_outfiles(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_outfiles(content)
+        .iter()
+        .map(|f| {
+            let record = f.record();
+            Finding::new(file_id, "get_outfiles", format!("({})", record.join(", "))).at(f.line).value(&f.target).extras(&OUTFILE_COLUMNS[2..], &record[2..])
+        })
+        .collect()
+}
+
+// outfile_rows returns the FILE statements as rows for the outfiles table
+pub fn outfile_rows(content: &FileContent) -> Vec<Vec<String>> {
+    find_outfiles(content).iter().map(Outfile::record).collect()
+}
+
+// infile_rows returns the INFILE statements as rows for the infiles table
+pub fn infile_rows(content: &FileContent) -> Vec<Vec<String>> {
+    find_infiles(content).iter().map(Infile::record).collect()
+}
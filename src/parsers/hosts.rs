@@ -0,0 +1,63 @@
+use super::patterns::{HOST_OPTION, IP_ADDRESS, URL_HOST};
+use super::{FileContent, Finding};
+use regex::Regex;
+
+/* -------------------------
+* Host parse functions:
+* - find_hosts: flags the servers a program connects to, with their line number, for the
+*   migration inventory of infrastructure dependencies:
+*   - URL: the host (and port) of a URL, http://dwhost:8080/... or jdbc:postgresql://db01/...
+*   - HOST: the value of a SERVER=, HOST=, HOSTNAME= or REMOTE= option, unless it is a macro
+*     variable reference
+*   - IP: an IPv4 address anywhere else in the code
+* A host is reported once, as the first of these that finds it, so the IP address in
+* server='10.1.2.3' is a HOST and not also an IP.
+* --------------------------- */
+
+// HostReference is one hardcoded host and how it was found (URL, HOST or IP)
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostReference {
+    pub line: usize,
+    pub kind: &'static str,
+    pub host: String,
+}
+
+pub fn find_host_references(content: &FileContent) -> Vec<HostReference> {
+    let rules: [(&str, &Regex); 3] = [("URL", &URL_HOST), ("HOST", &HOST_OPTION), ("IP", &IP_ADDRESS)];
+    let mut hosts: Vec<HostReference> = Vec::new();
+    for (line_number, line) in content.text.lines().enumerate() {
+        // the byte ranges of the hosts already found on the line
+        let mut found: Vec<(usize, usize)> = Vec::new();
+        for (kind, re) in &rules {
+            for caps in re.captures_iter(line) {
+                let m = caps.get(1).unwrap_or_else(|| caps.get(0).unwrap());
+                if found.iter().any(|&(start, end)| m.start() < end && start < m.end()) {
+                    continue;
+                }
+                let host = m.as_str().trim_matches(['\'', '"']);
+                if host.is_empty() || host.contains('&') || (*kind == "IP" && !is_ipv4(host)) {
+                    continue;
+                }
+                found.push((m.start(), m.end()));
+                hosts.push(HostReference {
+                    line: line_number + 1,
+                    kind,
+                    host: host.to_string(),
+                });
+            }
+        }
+    }
+    hosts
+}
+
+// is_ipv4 checks that each of the four numbers of an address is at most 255
+fn is_ipv4(address: &str) -> bool {
+    address.split('.').all(|octet| octet.parse::<u8>().is_ok())
+}
+
+pub fn find_hosts(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_host_references(content)
+        .iter()
+        .map(|h| Finding::new(file_id, "find_hosts", format!("({}, {}, {})", h.line, h.kind, h.host)).at(h.line).value(&h.host).extra("kind", h.kind))
+        .collect()
+}
@@ -0,0 +1,150 @@
+use super::patterns::{INCLUDE, INCLUDE_TARGET};
+use super::{FileContent, Finding};
+use crate::scanner::FileInfo;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/* -------------------------
+* %INCLUDE parse functions:
+* - get_includes: return each %INCLUDE (or %INC) target, and the line it is on
+* resolve_includes then turns the includes of every scanned file into a cross-file
+* dependency edge list, resolving paths against the scanned tree.
+* --------------------------- */
+
+pub const INCLUDE_HEADERS: [&str; 7] = ["source_uuid", "source_file", "line", "include", "target_uuid", "target_file", "status"];
+
+// Include is a single target of a %INCLUDE statement: a quoted path, or a fileref (with optional member)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Include {
+    pub line: usize,
+    pub target: String,
+    pub is_fileref: bool,
+}
+
+// IncludeEdge is one source file -> included file dependency
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncludeEdge {
+    pub source_uuid: String,
+    pub source_file: String,
+    pub line: usize,
+    pub include: String,
+    pub target_uuid: String,
+    pub target_file: String,
+    // resolved: the path exists in the scanned tree
+    // name_match: the path was not found, but exactly one scanned file has the same file name
+    // unresolved: the path is not in the scanned tree
+    // fileref: the include names a fileref, which is defined elsewhere by a FILENAME statement
+    pub status: String,
+}
+
+impl IncludeEdge {
+    pub fn record(&self) -> Vec<String> {
+        vec![
+            self.source_uuid.clone(),
+            self.source_file.clone(),
+            self.line.to_string(),
+            self.include.clone(),
+            self.target_uuid.clone(),
+            self.target_file.clone(),
+            self.status.clone(),
+        ]
+    }
+}
+
+pub fn find_includes(content: &FileContent) -> Vec<Include> {
+    let mut includes: Vec<Include> = Vec::new();
+    for caps in INCLUDE.captures_iter(&content.text) {
+        let line = content.line_at(caps.get(0).unwrap().start());
+        for target in INCLUDE_TARGET.captures_iter(&caps[1]) {
+            // everything after a slash is include options (SOURCE2, LRECL=...)
+            if &target[0] == "/" {
+                break;
+            }
+            if let Some(path) = target.get(1).or_else(|| target.get(2)) {
+                includes.push(Include { line, target: path.as_str().to_string(), is_fileref: false });
+            } else if let Some(fileref) = target.get(3) {
+                let fileref: String = fileref.as_str().split_whitespace().collect();
+                includes.push(Include { line, target: fileref, is_fileref: true });
+            }
+        }
+    }
+    includes
+}
+
+pub fn get_includes(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_includes(content)
+        .iter()
+        .map(|include| Finding::new(file_id, "get_includes", format!("({}, {})", include.line, include.target)).at(include.line).value(&include.target))
+        .collect()
+}
+
+// resolve_includes turns the includes found in each file into dependency edges between scanned files.
+// Relative paths are tried against the including file's directory and then the scan root.
+pub fn resolve_includes(root: &str, includes: &[(&FileInfo, Vec<Include>)], files: &[FileInfo]) -> Vec<IncludeEdge> {
+    let mut by_path: HashMap<PathBuf, &FileInfo> = HashMap::new();
+    let mut by_name: HashMap<String, Vec<&FileInfo>> = HashMap::new();
+    for file_info in files {
+        by_path.insert(normalize(Path::new(&file_info.path())), file_info);
+        by_name.entry(file_info.name.to_lowercase()).or_default().push(file_info);
+    }
+
+    let mut edges: Vec<IncludeEdge> = Vec::new();
+    for (source, source_includes) in includes {
+        for include in source_includes {
+            let mut edge = IncludeEdge {
+                source_uuid: source.uuid.clone(),
+                source_file: source.path(),
+                line: include.line,
+                include: include.target.clone(),
+                target_uuid: String::new(),
+                target_file: String::new(),
+                status: String::from("unresolved"),
+            };
+            if include.is_fileref {
+                edge.status = String::from("fileref");
+                edges.push(edge);
+                continue;
+            }
+
+            let target = include.target.replace('\\', "/");
+            let target_path = Path::new(&target);
+            let candidates: Vec<PathBuf> = if target_path.is_absolute() {
+                vec![normalize(target_path)]
+            } else {
+                vec![
+                    normalize(&Path::new(&source.directory).join(target_path)),
+                    normalize(&Path::new(root).join(target_path)),
+                ]
+            };
+
+            if let Some(found) = candidates.iter().find_map(|candidate| by_path.get(candidate)) {
+                edge.target_uuid = found.uuid.clone();
+                edge.target_file = found.path();
+                edge.status = String::from("resolved");
+            } else if let Some(file_name) = target_path.file_name().and_then(|n| n.to_str()) {
+                if let Some([found]) = by_name.get(&file_name.to_lowercase()).map(|v| v.as_slice()) {
+                    edge.target_uuid = found.uuid.clone();
+                    edge.target_file = found.path();
+                    edge.status = String::from("name_match");
+                }
+            }
+            edges.push(edge);
+        }
+    }
+    edges
+}
+
+// normalize removes . and .. components without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
@@ -0,0 +1,80 @@
+use super::datastep::statements::clause_keywords;
+use super::lexer::{self, Token, TokenKind};
+use super::{split_statements, FileContent, Finding};
+
+/* -------------------------
+* Jump parse functions:
+* - find_jumps: returns every unconditional jump and macro label as line, kind and target, since
+*   jump-heavy code is hard to follow and to convert:
+*   - %GOTO label and the %label: it jumps to, in macro code (%GOTO &target is reported as written)
+*   - GOTO (or GO TO) label and LINK label in DATA steps, including in IF ... THEN and ELSE
+* DATA step labels (name:) are not reported: they are also used by other statements.
+* --------------------------- */
+
+// Jump is one %GOTO, %label, GOTO or LINK
+#[derive(Debug, Clone, PartialEq)]
+pub struct Jump {
+    pub line: usize,
+    pub kind: String,
+    pub target: String,
+}
+
+// is_macro_label says whether the token at i is a %label: that %GOTO jumps to, a %name followed
+// by a colon
+pub fn is_macro_label(tokens: &[Token], i: usize) -> bool {
+    tokens[i].kind == TokenKind::MacroCall && tokens.get(i + 1).is_some_and(|next| next.text == ":")
+}
+
+pub fn find_jump_statements(content: &FileContent) -> Vec<Jump> {
+    let mut jumps: Vec<Jump> = Vec::new();
+    let tokens: Vec<Token> = lexer::tokens(&content.text).collect();
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::MacroCall {
+            continue;
+        }
+        let next = tokens.get(i + 1);
+        if token.text.eq_ignore_ascii_case("%GOTO") {
+            let target = next.filter(|t| matches!(t.kind, TokenKind::Word | TokenKind::MacroVariable)).map_or("", |t| t.text);
+            jumps.push(Jump { line: content.line_at(token.start), kind: String::from("%GOTO"), target: target.to_string() });
+        } else if is_macro_label(&tokens, i) {
+            jumps.push(Jump { line: content.line_at(token.start), kind: String::from("%LABEL"), target: token.text[1..].to_string() });
+        }
+    }
+    for statement in split_statements(&content.text) {
+        let keywords = clause_keywords(statement.text);
+        if !keywords.iter().any(|keyword| matches!(keyword.as_str(), "GOTO" | "GO" | "LINK")) {
+            continue;
+        }
+        let words: Vec<Token> = lexer::tokens(statement.text).filter(|token| token.kind == TokenKind::Word).collect();
+        for (i, word) in words.iter().enumerate() {
+            // a jump starts the statement or follows THEN, ELSE or OTHERWISE (link = 1 is a variable)
+            if i > 0 && !(words[i - 1].is_word("THEN") || words[i - 1].is_word("ELSE") || words[i - 1].is_word("OTHERWISE")) {
+                continue;
+            }
+            let (kind, target) = if word.is_word("GOTO") || word.is_word("LINK") {
+                (word.text.to_uppercase(), words.get(i + 1))
+            } else if word.is_word("GO") && words.get(i + 1).is_some_and(|next| next.is_word("TO")) {
+                (String::from("GOTO"), words.get(i + 2))
+            } else {
+                continue;
+            };
+            let Some(target) = target else {
+                continue;
+            };
+            jumps.push(Jump {
+                line: content.line_at(statement.start + word.start),
+                kind,
+                target: target.text.to_string(),
+            });
+        }
+    }
+    jumps.sort_by_key(|jump| jump.line);
+    jumps
+}
+
+pub fn find_jumps(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_jump_statements(content)
+        .iter()
+        .map(|j| Finding::new(file_id, "find_jumps", format!("({}, {}, {})", j.line, j.kind, j.target)).at(j.line).value(&j.target).extra("kind", &j.kind))
+        .collect()
+}
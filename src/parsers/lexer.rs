@@ -0,0 +1,273 @@
+/* -------------------------
+* SAS tokenizer: splits SAS source into tokens so parse functions can tell code from the
+* string literals and comments around it, instead of matching raw text.
+* - Word: a name or keyword (data, set, lib, _null_)
+* - Number: a numeric constant, or a word starting with a digit
+* - String: a quoted literal, '...' or "...", where a doubled quote is an escaped quote, with its
+*   date/time/name/hex suffix if it has one ('01jan2020'd, 'my var'n)
+* - Comment: /* ... */ anywhere, and * ...; or %* ...; at the start of a statement
+* - Semicolon
+* - MacroVariable: a macro variable reference, &name or &&name. (with its ampersands and dot)
+* - MacroCall: %name, a macro statement, function or invocation; the macro quoting functions
+*   (%str, %nrstr, %quote, ...) keep their parenthesized argument in the token, since the
+*   semicolons and quotes in it are text, not code
+* - Symbol: any other character (operators, parentheses, commas, dots)
+* - Data: the data lines after a DATALINES or CARDS (LINES) statement, up to the line with the
+*   semicolon that ends them (;;;; for DATALINES4, CARDS4 and LINES4); quotes and comment
+*   characters in the data are data. The rest of the DATALINES; line is skipped, as SAS does.
+* Whitespace is skipped. A string or comment that is never closed runs to the end of the text.
+* blank_strings uses the tokens to blank out string literals, for matching only in code.
+* --------------------------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Word,
+    Number,
+    String,
+    Comment,
+    Semicolon,
+    MacroVariable,
+    MacroCall,
+    Symbol,
+    Data,
+}
+
+// Token is one token of the text, with the byte offset where it starts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub start: usize,
+}
+
+impl Token<'_> {
+    // end is the offset just past the token
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    // is_word says whether the token is the word (keyword) given, in any case
+    pub fn is_word(&self, word: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(word)
+    }
+}
+
+// the macro quoting functions, whose arguments are text
+const MACRO_QUOTING: &[&str] = &["STR", "NRSTR", "QUOTE", "NRQUOTE", "BQUOTE", "NRBQUOTE", "SUPERQ"];
+
+// the suffixes of a quoted literal: date, time, datetime, name, hex and bit constants
+const LITERAL_SUFFIXES: &[&str] = &["D", "T", "DT", "N", "X", "B"];
+
+// the statements followed by inline data, and the text of the line that ends the data
+const INLINE_DATA: &[(&str, &str)] =
+    &[("DATALINES", ";"), ("CARDS", ";"), ("LINES", ";"), ("DATALINES4", ";;;;"), ("CARDS4", ";;;;"), ("LINES4", ";;;;")];
+
+// Lexer returns the tokens of a text one at a time
+pub struct Lexer<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    // whether the next token starts a statement (where * and %* start a comment)
+    statement_start: bool,
+    // the end of data, while the statement read so far is a lone DATALINES or CARDS
+    data_statement: Option<&'static str>,
+    // the end of data, when the data starts on the next line
+    data_end: Option<&'static str>,
+}
+
+pub fn tokens(text: &str) -> Lexer<'_> {
+    Lexer { text, bytes: text.as_bytes(), pos: 0, statement_start: true, data_statement: None, data_end: None }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        if let Some(terminator) = self.data_end.take() {
+            if let Some(token) = self.inline_data(terminator) {
+                self.statement_start = true;
+                return Some(token);
+            }
+        }
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        if self.pos >= self.bytes.len() {
+            return None;
+        }
+        let start = self.pos;
+        let b = self.bytes[start];
+        let next = self.bytes.get(start + 1).copied();
+        let (kind, end) = match b {
+            b'/' if next == Some(b'*') => {
+                let end = self.text[start + 2..].find("*/").map(|j| start + 2 + j + 2).unwrap_or(self.bytes.len());
+                (TokenKind::Comment, end)
+            }
+            b'*' if self.statement_start => (TokenKind::Comment, self.statement_end(start + 1)),
+            b'%' if self.statement_start && next == Some(b'*') => (TokenKind::Comment, self.statement_end(start + 2)),
+            b'\'' | b'"' => (TokenKind::String, self.string_end(start)),
+            b';' => (TokenKind::Semicolon, start + 1),
+            b'&' if next.is_some_and(|c| c == b'&' || is_name_start(c)) => (TokenKind::MacroVariable, self.macro_variable_end(start)),
+            b'%' if next.is_some_and(is_name_start) => (TokenKind::MacroCall, self.macro_call_end(start)),
+            c if is_name_start(c) => (TokenKind::Word, self.name_end(start)),
+            c if c.is_ascii_digit() => (TokenKind::Number, self.name_end(start)),
+            _ => (TokenKind::Symbol, start + self.text[start..].chars().next().map_or(1, char::len_utf8)),
+        };
+        self.data_statement = match kind {
+            TokenKind::Comment => self.data_statement,
+            TokenKind::Semicolon => {
+                self.data_end = self.data_statement;
+                None
+            }
+            TokenKind::Word if self.statement_start => INLINE_DATA
+                .iter()
+                .find(|(statement, _)| statement.eq_ignore_ascii_case(&self.text[start..end]))
+                .map(|(_, terminator)| *terminator),
+            _ => None,
+        };
+        self.statement_start = match kind {
+            TokenKind::Semicolon => true,
+            // a block comment does not change whether we are at the start of a statement, and a
+            // statement comment ends with its semicolon
+            TokenKind::Comment => b != b'/' || self.statement_start,
+            _ => false,
+        };
+        self.pos = end;
+        Some(Token { kind, text: &self.text[start..end], start })
+    }
+}
+
+impl<'a> Lexer<'a> {
+    // inline_data returns the lines from the next line up to the line holding the terminator (or
+    // the end of the text) as a Data token, or nothing when there are no data lines
+    fn inline_data(&mut self, terminator: &str) -> Option<Token<'a>> {
+        let start = self.text[self.pos..].find('\n').map(|j| self.pos + j + 1).unwrap_or(self.bytes.len());
+        let mut end = start;
+        while end < self.bytes.len() {
+            let line_end = self.text[end..].find('\n').map(|j| end + j + 1).unwrap_or(self.bytes.len());
+            if self.text[end..line_end].contains(terminator) {
+                break;
+            }
+            end = line_end;
+        }
+        self.pos = end;
+        (end > start).then(|| Token { kind: TokenKind::Data, text: &self.text[start..end], start })
+    }
+
+    // statement_end returns the offset just past the next semicolon (or the end of the text)
+    fn statement_end(&self, from: usize) -> usize {
+        self.text[from..].find(';').map(|j| from + j + 1).unwrap_or(self.bytes.len())
+    }
+
+    // string_end returns the offset just past a quoted string starting at start, and its suffix
+    fn string_end(&self, start: usize) -> usize {
+        let quote = self.bytes[start];
+        let mut i = start + 1;
+        let mut end = self.bytes.len();
+        while i < self.bytes.len() {
+            if self.bytes[i] == quote {
+                if self.bytes.get(i + 1) == Some(&quote) {
+                    i += 2;
+                    continue;
+                }
+                end = i + 1;
+                break;
+            }
+            i += 1;
+        }
+        let suffix_end = self.name_end(end);
+        let suffix = &self.text[end..suffix_end];
+        if LITERAL_SUFFIXES.iter().any(|s| s.eq_ignore_ascii_case(suffix)) {
+            suffix_end
+        } else {
+            end
+        }
+    }
+
+    // name_end returns the offset just past the letters, digits and underscores starting at from
+    fn name_end(&self, from: usize) -> usize {
+        let mut i = from;
+        while i < self.bytes.len() && is_name_byte(self.bytes[i]) {
+            i += 1;
+        }
+        i
+    }
+
+    // macro_variable_end returns the offset just past &name, &&name or &name. (the dot ends the reference)
+    fn macro_variable_end(&self, start: usize) -> usize {
+        let mut i = start;
+        while self.bytes.get(i) == Some(&b'&') {
+            i += 1;
+        }
+        let mut end = self.name_end(i);
+        if self.bytes.get(end) == Some(&b'.') {
+            end += 1;
+        }
+        end
+    }
+
+    // macro_call_end returns the offset just past %name, or for a quoting function past its
+    // parenthesized argument, where %x escapes the character x
+    fn macro_call_end(&self, start: usize) -> usize {
+        let name_end = self.name_end(start + 1);
+        let name = &self.text[start + 1..name_end];
+        if !MACRO_QUOTING.iter().any(|q| q.eq_ignore_ascii_case(name)) {
+            return name_end;
+        }
+        let mut i = name_end;
+        while i < self.bytes.len() && self.bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if self.bytes.get(i) != Some(&b'(') {
+            return name_end;
+        }
+        let mut depth = 0;
+        while i < self.bytes.len() {
+            match self.bytes[i] {
+                b'%' => i += 1,
+                b'(' => depth += 1,
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return i + 1;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        self.bytes.len()
+    }
+}
+
+// blank_strings returns the text with the inside of every string literal replaced by spaces
+// (keeping the quotes, the newlines and the byte length, so offsets still line up)
+pub fn blank_strings(text: &str) -> String {
+    let mut blanked = String::with_capacity(text.len());
+    let mut last = 0;
+    for token in tokens(text).filter(|token| token.kind == TokenKind::String) {
+        blanked.push_str(&text[last..token.start]);
+        let quote = &token.text[..1];
+        let close = token.text.rfind(quote).filter(|&i| i > 0).unwrap_or(token.text.len());
+        blanked.push_str(quote);
+        for c in token.text[1..close].chars() {
+            if c == '\n' || c == '\r' {
+                blanked.push(c);
+            } else {
+                blanked.extend(std::iter::repeat_n(' ', c.len_utf8()));
+            }
+        }
+        blanked.push_str(&token.text[close..]);
+        last = token.end();
+    }
+    blanked.push_str(&text[last..]);
+    blanked
+}
+
+fn is_name_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_'
+}
+
+fn is_name_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
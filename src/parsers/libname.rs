@@ -0,0 +1,93 @@
+use super::patterns::LIBNAME_OPTION;
+use super::{quoted_end, split_statements, split_word, FileContent, Finding};
+
+/* -------------------------
+* LIBNAME parse functions:
+* - get_libname: parses each LIBNAME statement into libref, engine, physical path (or the
+*   connection target for database engines) and the remaining options.
+*   A LIBNAME with a quoted path and no engine uses the default BASE engine.
+* --------------------------- */
+
+pub const LIBNAME_COLUMNS: [&str; 5] = ["line", "libref", "engine", "path", "options"];
+
+// options that name the connection target of a database engine, used as the path when there is no quoted path
+const PATH_OPTIONS: &[&str] = &["PATH", "SERVER", "DSN", "DATASRC", "DATABASE", "DB", "HOST"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Libname {
+    pub line: usize,
+    pub libref: String,
+    pub engine: String,
+    pub path: String,
+    pub options: String,
+}
+
+pub fn find_libnames(content: &FileContent) -> Vec<Libname> {
+    let mut libnames: Vec<Libname> = Vec::new();
+    for statement in split_statements(&content.text) {
+        if statement.keyword() != "LIBNAME" {
+            continue;
+        }
+        let body = statement.text["LIBNAME".len()..statement.text.len() - 1].trim();
+        let (libref, rest) = split_word(body);
+        let mut rest = rest.trim_start();
+
+        let mut engine = String::new();
+        if !rest.is_empty() && !rest.starts_with(['\'', '"', '(']) {
+            let (word, after) = split_word(rest);
+            if !word.contains('=') {
+                engine = word.to_uppercase();
+                rest = after.trim_start();
+            }
+        }
+
+        let mut path = String::new();
+        if rest.starts_with(['\'', '"', '(']) {
+            let end = quoted_end(rest);
+            path = rest[..end].trim_matches(['\'', '"']).to_string();
+            rest = rest[end..].trim_start();
+        }
+        if engine.is_empty() && !path.is_empty() {
+            engine = String::from("BASE");
+        }
+        if path.is_empty() {
+            if let Some(caps) = LIBNAME_OPTION
+                .captures_iter(rest)
+                .find(|caps| PATH_OPTIONS.contains(&caps[1].to_uppercase().as_str()))
+            {
+                path = caps[2].trim_matches(['\'', '"']).to_string();
+            }
+        }
+
+        libnames.push(Libname {
+            line: content.line_at(statement.start),
+            libref: libref.to_string(),
+            engine,
+            path,
+            options: rest.split_whitespace().collect::<Vec<&str>>().join(" "),
+        });
+    }
+    libnames
+}
+
+pub fn get_libname(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_libnames(content)
+        .iter()
+        .map(|l| {
+            Finding::new(file_id, "get_libname", format!("({}, {}, {}, {}, {})", l.line, l.libref, l.engine, l.path, l.options))
+                .at(l.line)
+                .value(&l.libref)
+                .extra("engine", &l.engine)
+                .extra("path", &l.path)
+                .extra("options", &l.options)
+        })
+        .collect()
+}
+
+// libname_rows returns the LIBNAME statements as rows for the libnames table
+pub fn libname_rows(content: &FileContent) -> Vec<Vec<String>> {
+    find_libnames(content)
+        .into_iter()
+        .map(|l| vec![l.line.to_string(), l.libref, l.engine, l.path, l.options])
+        .collect()
+}
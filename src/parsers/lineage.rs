@@ -0,0 +1,86 @@
+use super::datastep::{dataset_names, find_datasteps};
+use super::sql::find_sql_blocks;
+use super::sql::statements::block_statements;
+use super::{split_statements, FileContent, Finding};
+
+/* -------------------------
+* Lineage parse functions:
+* - get_lineage: for each DATA step and each statement in a PROC SQL block, records the datasets
+*   read (SET/MERGE/UPDATE/MODIFY, FROM/JOIN) and written (DATA statement, CREATE TABLE/VIEW,
+*   INSERT INTO, UPDATE, DELETE FROM), as one input -> output row per pair.
+*   A block that only reads, or only writes, gets rows with an empty output or input.
+*   The PROC SQL statements come from the statement parser (sql/statements.rs).
+* --------------------------- */
+
+// LineageEdge is one input dataset -> output dataset pair
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineageEdge {
+    pub line: usize,
+    pub kind: String,
+    pub input: String,
+    pub output: String,
+}
+
+pub fn find_lineage(content: &FileContent) -> Vec<LineageEdge> {
+    let mut edges: Vec<LineageEdge> = Vec::new();
+
+    for step in find_datasteps(content) {
+        let mut inputs: Vec<String> = Vec::new();
+        for statement in split_statements(&step.text) {
+            let keyword = statement.keyword();
+            if matches!(keyword.as_str(), "SET" | "MERGE" | "UPDATE" | "MODIFY") {
+                let list = &statement.text[keyword.len()..statement.text.len() - 1];
+                inputs.extend(dataset_names(list));
+            }
+        }
+        // DATA _NULL_ runs a step without writing a dataset
+        let outputs: Vec<String> = step.outputs.into_iter().filter(|output| !output.eq_ignore_ascii_case("_NULL_")).collect();
+        push_edges(&mut edges, step.start_line, "DATA", &inputs, &outputs);
+    }
+
+    for block in find_sql_blocks(content) {
+        for statement in block_statements(&block) {
+            if !statement.kind.queries_tables() {
+                continue;
+            }
+            let mut outputs: Vec<String> = Vec::new();
+            if statement.kind.writes() && !statement.target.is_empty() {
+                outputs.push(statement.target.clone());
+            }
+            push_edges(&mut edges, statement.line, "SQL", &statement.sources, &outputs);
+        }
+    }
+    edges
+}
+
+fn push_edges(edges: &mut Vec<LineageEdge>, line: usize, kind: &str, inputs: &[String], outputs: &[String]) {
+    let empty = vec![String::new()];
+    let inputs = if inputs.is_empty() { &empty } else { inputs };
+    let outputs = if outputs.is_empty() { &empty } else { outputs };
+    for input in inputs {
+        for output in outputs {
+            if input.is_empty() && output.is_empty() {
+                continue;
+            }
+            edges.push(LineageEdge {
+                line,
+                kind: kind.to_string(),
+                input: input.clone(),
+                output: output.clone(),
+            });
+        }
+    }
+}
+
+pub fn get_lineage(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_lineage(content)
+        .iter()
+        .map(|edge| {
+            Finding::new(file_id, "get_lineage", format!("({}, {}, {}, {})", edge.line, edge.kind, edge.input, edge.output))
+                .at(edge.line)
+                .value(&edge.output)
+                .extra("kind", &edge.kind)
+                .extra("input", &edge.input)
+        })
+        .collect()
+}
@@ -0,0 +1,202 @@
+use super::patterns::{LOG_MESSAGE, LOG_PROGRAM, LOG_STEP, LOG_TIME};
+use super::{FileContent, Finding, ParseFunction};
+
+/* -------------------------
+* SAS log parse functions (with --mode logs, which scans .log files instead of programs):
+* - log_counts: counts the ERROR, WARNING and NOTE messages of a log
+* - log_errors: returns each ERROR message with its line, joined with the indented lines it wraps
+*   onto (ERROR 22-322: messages keep their message number)
+* - log_times: returns the real and cpu time, in seconds, of each DATA step and procedure, from the
+*   "NOTE: ... used" message and the real time/cpu time lines below it
+* - log_program: returns the program the log was written by: the _SASPROGRAMFILE macro variable set
+*   by Enterprise Guide and SAS Studio, or the file of the -SYSIN option of a batch run
+* - log_summary: returns the log quality summary of the file: message counts, total step times and
+*   program, which is also the log_summary table
+* Lines echoing the submitted code start with their line number, so an ERROR: in a PUT statement
+* is only counted when the PUT statement runs.
+* --------------------------- */
+
+pub const LOG_SUMMARY_COLUMNS: [&str; 6] = ["errors", "warnings", "notes", "real_time", "cpu_time", "program"];
+
+// LOG_FUNCTIONS are the parse functions of --mode logs: (name, description, function)
+pub const LOG_FUNCTIONS: &[(&str, &str, ParseFunction)] = &[
+    ("log_counts", "count the ERROR, WARNING and NOTE messages of a log", log_counts),
+    ("log_errors", "return each ERROR message and its line", log_errors),
+    ("log_times", "return the real and cpu time, in seconds, of each DATA step and procedure", log_times),
+    ("log_program", "return the program the log was written by", log_program),
+    ("log_summary", "return message counts, total real and cpu time and program of a log", log_summary),
+];
+
+// LogMessage is one ERROR, WARNING or NOTE of a log
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogMessage {
+    pub line: usize,
+    pub level: String,
+    pub message: String,
+}
+
+// StepTime is the time used by one DATA step or procedure
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepTime {
+    pub line: usize,
+    pub step: String,
+    pub real_time: f64,
+    pub cpu_time: f64,
+}
+
+// LogSummary is the log quality summary of one log
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogSummary {
+    pub errors: usize,
+    pub warnings: usize,
+    pub notes: usize,
+    pub real_time: f64,
+    pub cpu_time: f64,
+    pub program: String,
+}
+
+// log_lines returns the lines of a log without the form feeds that start its pages
+fn log_lines(content: &FileContent) -> Vec<&str> {
+    content.text.lines().map(|line| line.trim_start_matches('\x0c').trim_end()).collect()
+}
+
+pub fn find_log_messages(content: &FileContent) -> Vec<LogMessage> {
+    let lines = log_lines(content);
+    let mut messages: Vec<LogMessage> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(caps) = LOG_MESSAGE.captures(line) else {
+            continue;
+        };
+        let mut message = match caps.get(2) {
+            Some(number) => format!("{}: {}", number.as_str(), caps[3].trim()),
+            None => caps[3].trim().to_string(),
+        };
+        // long messages wrap onto indented lines
+        for next in lines[i + 1..].iter().take_while(|next| next.starts_with(' ') && !next.trim().is_empty()) {
+            if LOG_TIME.is_match(next) {
+                break;
+            }
+            message.push(' ');
+            message.push_str(next.trim());
+        }
+        messages.push(LogMessage { line: i + 1, level: caps[1].to_uppercase(), message });
+    }
+    messages
+}
+
+// seconds reads a SAS log time: 0.01 seconds, 1:02.35 (minutes) or 1:02:03.45 (hours)
+fn seconds(time: &str) -> f64 {
+    time.split(':').fold(0.0, |total, part| total * 60.0 + part.parse::<f64>().unwrap_or(0.0))
+}
+
+pub fn find_step_times(content: &FileContent) -> Vec<StepTime> {
+    let mut steps: Vec<StepTime> = Vec::new();
+    for (i, line) in log_lines(content).iter().enumerate() {
+        if let Some(caps) = LOG_STEP.captures(line) {
+            let step = match caps.get(1) {
+                Some(procedure) => format!("PROC {}", procedure.as_str().to_uppercase()),
+                None => String::from("DATA"),
+            };
+            steps.push(StepTime { line: i + 1, step, real_time: 0.0, cpu_time: 0.0 });
+        } else if let (Some(caps), Some(step)) = (LOG_TIME.captures(line), steps.last_mut()) {
+            match caps[1].to_lowercase().as_str() {
+                "real" => step.real_time = seconds(&caps[2]),
+                _ => step.cpu_time = seconds(&caps[2]),
+            }
+        }
+    }
+    steps
+}
+
+pub fn find_program(content: &FileContent) -> String {
+    LOG_PROGRAM
+        .captures(&content.text)
+        .map(|caps| caps.iter().skip(1).flatten().next().map_or("", |m| m.as_str()).trim().to_string())
+        .unwrap_or_default()
+}
+
+pub fn summarize(content: &FileContent) -> LogSummary {
+    let mut summary = LogSummary { program: find_program(content), ..LogSummary::default() };
+    for message in find_log_messages(content) {
+        match message.level.as_str() {
+            "ERROR" => summary.errors += 1,
+            "WARNING" => summary.warnings += 1,
+            _ => summary.notes += 1,
+        }
+    }
+    for step in find_step_times(content) {
+        summary.real_time += step.real_time;
+        summary.cpu_time += step.cpu_time;
+    }
+    summary
+}
+
+// round keeps the step time totals to the hundredths of a second SAS logs them in
+fn round(seconds: f64) -> f64 {
+    (seconds * 100.0).round() / 100.0
+}
+
+pub fn log_counts(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    let summary = summarize(content);
+    vec![Finding::new(file_id, "log_counts", format!("({}, {}, {})", summary.errors, summary.warnings, summary.notes))
+        .value(summary.errors)
+        .extra("warnings", summary.warnings)
+        .extra("notes", summary.notes)]
+}
+
+pub fn log_errors(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_log_messages(content)
+        .iter()
+        .filter(|m| m.level == "ERROR")
+        .map(|m| Finding::new(file_id, "log_errors", format!("({}, {})", m.line, m.message)).at(m.line).value(&m.message))
+        .collect()
+}
+
+pub fn log_times(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_step_times(content)
+        .iter()
+        .map(|s| {
+            Finding::new(file_id, "log_times", format!("({}, {}, {}, {})", s.line, s.step, s.real_time, s.cpu_time))
+                .at(s.line)
+                .value(&s.step)
+                .extra("real_time", s.real_time)
+                .extra("cpu_time", s.cpu_time)
+        })
+        .collect()
+}
+
+pub fn log_program(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    let program = find_program(content);
+    match program.is_empty() {
+        true => Vec::new(),
+        false => vec![Finding::new(file_id, "log_program", program)],
+    }
+}
+
+pub fn log_summary(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    let s = summarize(content);
+    vec![Finding::new(
+        file_id,
+        "log_summary",
+        format!("({}, {}, {}, {}, {}, {})", s.errors, s.warnings, s.notes, round(s.real_time), round(s.cpu_time), s.program),
+    )
+    .value(&s.program)
+    .extra("errors", s.errors)
+    .extra("warnings", s.warnings)
+    .extra("notes", s.notes)
+    .extra("real_time", round(s.real_time))
+    .extra("cpu_time", round(s.cpu_time))]
+}
+
+// log_summary_rows returns the log_summary table row of a log
+pub fn log_summary_rows(content: &FileContent) -> Vec<Vec<String>> {
+    let s = summarize(content);
+    vec![vec![
+        s.errors.to_string(),
+        s.warnings.to_string(),
+        s.notes.to_string(),
+        round(s.real_time).to_string(),
+        round(s.cpu_time).to_string(),
+        s.program,
+    ]]
+}
@@ -0,0 +1,280 @@
+use super::jumps::is_macro_label;
+use super::lexer::{self, Token, TokenKind};
+use super::patterns::{MACRO_CALL, MACRO_END, MACRO_START};
+use super::{FileContent, Finding};
+use crate::scanner::FileInfo;
+use std::collections::{HashMap, HashSet};
+
+/* -------------------------
+* Macro parse functions:
+* - get_macros: finds each %MACRO name(params); ... %MEND; block, analogous to how get_sql
+*   captures PROC SQL blocks, and records the start line, macro name, parameter list, end
+*   line and the body length in lines.
+* - get_macro_calls: return each %name macro invocation as line, caller (the enclosing macro
+*   definition, empty in open code) and callee. Macro language statements and functions
+*   (%LET, %IF, %SYSFUNC, ...) are not invocations, and neither is a %label: (a %GOTO target) or
+*   a %name in single quotes or in DATALINES, where macro triggers are not resolved
+*   ('%Y-%0m-%0d' is a date format).
+* resolve_macro_calls then links the invocations of every scanned file to the file that defines
+* the macro, giving a cross-file caller -> callee edge list for the macro call graph, and
+* find_orphans reports the macros that are defined but never invoked anywhere in the scanned tree.
+* --------------------------- */
+
+// MacroDef is a single %MACRO ... %MEND definition found in a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroDef {
+    pub name: String,
+    pub params: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub body: String,
+}
+
+impl MacroDef {
+    // body_lines is the number of lines between the %MACRO statement and %MEND
+    pub fn body_lines(&self) -> usize {
+        self.body.trim_matches('\n').lines().count()
+    }
+}
+
+// find_macros returns every macro definition in the file, in the order the %MEND statements appear.
+// Nested definitions are matched to their own %MEND using a stack.
+pub fn find_macros(content: &FileContent) -> Vec<MacroDef> {
+    match_definitions(content).0
+}
+
+// find_unclosed_macros returns the line and name of each %MACRO statement that no %MEND closes
+pub fn find_unclosed_macros(content: &FileContent) -> Vec<(usize, String)> {
+    match_definitions(content).1.into_iter().map(|(offset, name)| (content.line_at(offset), name)).collect()
+}
+
+// match_definitions matches the %MACRO and %MEND statements of the file, returning the
+// definitions and the offset and name of every %MACRO left without a %MEND
+fn match_definitions(content: &FileContent) -> (Vec<MacroDef>, Vec<(usize, String)>) {
+    // (offset, is_start, statement end, name, params)
+    let mut events: Vec<(usize, bool, usize, String, String)> = Vec::new();
+    for caps in MACRO_START.captures_iter(&content.text) {
+        let whole = caps.get(0).unwrap();
+        let params = caps.get(2).map(|m| m.as_str().split_whitespace().collect::<Vec<&str>>().join(" ")).unwrap_or_default();
+        events.push((whole.start(), true, whole.end(), caps[1].to_string(), params));
+    }
+    for m in MACRO_END.find_iter(&content.text) {
+        events.push((m.start(), false, m.end(), String::new(), String::new()));
+    }
+    events.sort_by_key(|event| event.0);
+
+    let mut macros: Vec<MacroDef> = Vec::new();
+    let mut open: Vec<(usize, usize, String, String)> = Vec::new();
+    for (offset, is_start, statement_end, name, params) in events {
+        if is_start {
+            open.push((offset, statement_end, name, params));
+        } else if let Some((start, body_start, name, params)) = open.pop() {
+            macros.push(MacroDef {
+                name,
+                params,
+                start_line: content.line_at(start),
+                end_line: content.line_at(offset),
+                body: content.text[body_start..offset].to_string(),
+            });
+        }
+    }
+    (macros, open.into_iter().map(|(offset, _, name, _)| (offset, name)).collect())
+}
+
+pub fn get_macros(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_macros(content)
+        .iter()
+        .map(|m| {
+            Finding::new(
+                file_id,
+                "get_macros",
+                format!("({}, {}, ({}), {}, {})", m.start_line, m.name, m.params, m.end_line, m.body_lines()),
+            )
+            .lines(m.start_line, m.end_line)
+            .value(&m.name)
+            .extra("params", &m.params)
+            .extra("body_lines", m.body_lines())
+        })
+        .collect()
+}
+
+// macro language statements and functions, which look like %name but are not macro invocations
+const MACRO_KEYWORDS: &[&str] = &[
+    "ABORT", "BQUOTE", "BY", "CMPRES", "COPY", "DATATYP", "DISPLAY", "DO", "ELSE", "END", "EVAL", "GLOBAL",
+    "GOTO", "IF", "INC", "INCLUDE", "INDEX", "INPUT", "KEYDEF", "LEFT", "LENGTH", "LET", "LIST", "LOCAL",
+    "LOWCASE", "MACRO", "MEND", "NRBQUOTE", "NRQUOTE", "NRSTR", "PUT", "QCMPRES", "QLEFT", "QLOWCASE", "QSCAN",
+    "QSUBSTR", "QSYSFUNC", "QTRIM", "QUOTE", "QUPCASE", "RETURN", "RUN", "SCAN", "STR", "SUBSTR", "SUPERQ",
+    "SYMDEL", "SYMEXIST", "SYMGLOBL", "SYMLOCAL", "SYSCALL", "SYSEVALF", "SYSEXEC", "SYSFUNC", "SYSGET",
+    "SYSLPUT", "SYSMACDELETE", "SYSMACEXEC", "SYSMEXECDEPTH", "SYSMEXECNAME", "SYSRPUT", "THEN", "TO", "TRIM",
+    "UNQUOTE", "UNTIL", "UPCASE", "VERIFY", "WHILE", "WINDOW",
+];
+
+// MacroCall is a single %name invocation; caller is the macro whose definition contains it,
+// or empty for open code
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroCall {
+    pub line: usize,
+    pub caller: String,
+    pub callee: String,
+}
+
+// find_macro_calls returns every %name invocation of a (user written or autocall) macro
+pub fn find_macro_calls(content: &FileContent) -> Vec<MacroCall> {
+    let definitions = find_macros(content);
+    let mut calls: Vec<MacroCall> = Vec::new();
+    let tokens: Vec<Token> = lexer::tokens(&content.text).collect();
+    for (i, token) in tokens.iter().enumerate() {
+        // macro triggers resolve in code and double quotes, not in single quotes or inline data
+        let resolved = match token.kind {
+            TokenKind::Data => false,
+            TokenKind::String => !token.text.starts_with('\''),
+            _ => !is_macro_label(&tokens, i),
+        };
+        if !resolved {
+            continue;
+        }
+        for caps in MACRO_CALL.captures_iter(token.text) {
+            let callee = &caps[1];
+            if MACRO_KEYWORDS.contains(&callee.to_uppercase().as_str()) {
+                continue;
+            }
+            let line = content.line_at(token.start + caps.get(0).unwrap().start());
+            // the innermost definition around the call is the caller
+            let caller = definitions
+                .iter()
+                .filter(|m| m.start_line <= line && line <= m.end_line)
+                .min_by_key(|m| m.end_line - m.start_line)
+                .map(|m| m.name.clone())
+                .unwrap_or_default();
+            calls.push(MacroCall { line, caller, callee: callee.to_string() });
+        }
+    }
+    calls
+}
+
+pub fn get_macro_calls(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_macro_calls(content)
+        .iter()
+        .map(|c| Finding::new(file_id, "get_macro_calls", format!("({}, {}, {})", c.line, c.caller, c.callee)).at(c.line).value(&c.callee).extra("caller", &c.caller))
+        .collect()
+}
+
+pub const MACRO_CALL_HEADERS: [&str; 8] =
+    ["caller_uuid", "caller_file", "line", "caller", "callee", "callee_uuid", "callee_file", "status"];
+
+// MacroCallEdge is one caller -> callee edge of the cross-file macro call graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroCallEdge {
+    pub caller_uuid: String,
+    pub caller_file: String,
+    pub line: usize,
+    pub caller: String,
+    pub callee: String,
+    pub callee_uuid: String,
+    pub callee_file: String,
+    // local: the macro is defined in the calling file
+    // resolved: the macro is defined in exactly one other scanned file
+    // ambiguous: the macro is defined in several other scanned files (the first one is used)
+    // undefined: no scanned file defines the macro (an autocall or stored compiled macro)
+    pub status: String,
+}
+
+impl MacroCallEdge {
+    pub fn record(&self) -> Vec<String> {
+        vec![
+            self.caller_uuid.clone(),
+            self.caller_file.clone(),
+            self.line.to_string(),
+            self.caller.clone(),
+            self.callee.clone(),
+            self.callee_uuid.clone(),
+            self.callee_file.clone(),
+            self.status.clone(),
+        ]
+    }
+}
+
+// FileMacros holds the macro definitions and invocations found in one scanned file
+pub struct FileMacros<'a> {
+    pub file: &'a FileInfo,
+    pub definitions: Vec<MacroDef>,
+    pub calls: Vec<MacroCall>,
+}
+
+impl<'a> FileMacros<'a> {
+    pub fn new(file: &'a FileInfo, content: &FileContent) -> FileMacros<'a> {
+        FileMacros { file, definitions: find_macros(content), calls: find_macro_calls(content) }
+    }
+}
+
+// resolve_macro_calls links every invocation to the file defining the macro, producing the
+// caller -> callee edge list of the macro call graph across all scanned files.
+// Macro names are not case sensitive.
+pub fn resolve_macro_calls(files: &[FileMacros]) -> Vec<MacroCallEdge> {
+    let mut defined_in: HashMap<String, Vec<&FileInfo>> = HashMap::new();
+    for file_macros in files {
+        for definition in &file_macros.definitions {
+            let files = defined_in.entry(definition.name.to_lowercase()).or_default();
+            if !files.iter().any(|f| f.uuid == file_macros.file.uuid) {
+                files.push(file_macros.file);
+            }
+        }
+    }
+
+    let mut edges: Vec<MacroCallEdge> = Vec::new();
+    for file_macros in files {
+        let source = file_macros.file;
+        for call in &file_macros.calls {
+            let definitions = defined_in.get(&call.callee.to_lowercase()).map(|v| v.as_slice()).unwrap_or(&[]);
+            let (target, status) = if let Some(local) = definitions.iter().find(|f| f.uuid == source.uuid) {
+                (Some(local), "local")
+            } else {
+                match definitions {
+                    [] => (None, "undefined"),
+                    [only] => (Some(only), "resolved"),
+                    [first, ..] => (Some(first), "ambiguous"),
+                }
+            };
+            edges.push(MacroCallEdge {
+                caller_uuid: source.uuid.clone(),
+                caller_file: source.path(),
+                line: call.line,
+                caller: call.caller.clone(),
+                callee: call.callee.clone(),
+                callee_uuid: target.map(|f| f.uuid.clone()).unwrap_or_default(),
+                callee_file: target.map(|f| f.path()).unwrap_or_default(),
+                status: String::from(status),
+            });
+        }
+    }
+    edges
+}
+
+pub const ORPHAN_HEADERS: [&str; 5] = ["uuid", "file", "macro", "start_line", "end_line"];
+
+// find_orphans returns the macros defined in a scanned file but never invoked in any scanned file,
+// as rows for the orphans table. A macro that only calls itself is still an orphan.
+pub fn find_orphans(files: &[FileMacros]) -> Vec<Vec<String>> {
+    let called: HashSet<String> = files
+        .iter()
+        .flat_map(|file_macros| file_macros.calls.iter())
+        .filter(|call| !call.caller.eq_ignore_ascii_case(&call.callee))
+        .map(|call| call.callee.to_lowercase())
+        .collect();
+
+    let mut orphans: Vec<Vec<String>> = Vec::new();
+    for file_macros in files {
+        for definition in &file_macros.definitions {
+            if !called.contains(&definition.name.to_lowercase()) {
+                orphans.push(vec![
+                    file_macros.file.uuid.clone(),
+                    file_macros.file.path(),
+                    definition.name.clone(),
+                    definition.start_line.to_string(),
+                    definition.end_line.to_string(),
+                ]);
+            }
+        }
+    }
+    orphans
+}
@@ -0,0 +1,240 @@
+use super::lexer::{self, TokenKind};
+use super::patterns::{CALL_SYMPUT, LET, MACRO_VARIABLE_REF, SYSFUNC};
+use super::{FileContent, Finding};
+
+/* -------------------------
+* Macro variable parse functions:
+* - get_let: returns every %LET name = value; assignment with its line number, building a catalog
+*   of the macro variables (and the configuration values they hold) set in each file.
+*   A semicolon inside parentheses or quotes, as in %STR(a;b), does not end the value.
+* - get_symput: returns every CALL SYMPUT / SYMPUTX as line, routine, macro variable name expression
+*   and value expression. These create macro variables at run time, often with computed names,
+*   so they are missed by the %LET catalog.
+* - get_sysfunc: returns every %SYSFUNC (and %QSYSFUNC) call as line, macro function, the DATA step
+*   function it runs, its arguments and the optional format of the result. Heavy %SYSFUNC use is
+*   macro logic that resists automated conversion. Nested calls are each returned.
+* - get_auto_macro_vars: returns every reference to an automatic (system) macro variable, such as
+*   &SYSDATE9, &SYSUSERID or &SYSHOSTNAME, as line and name. Programs depending on these need
+*   rework for a new environment. References in code and in double quoted strings are found; a
+*   single quoted string does not resolve macro variables, so its text is skipped.
+* --------------------------- */
+
+pub const MACRO_VARIABLE_COLUMNS: [&str; 3] = ["line", "name", "value"];
+
+// LetAssignment is a single %LET statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct LetAssignment {
+    pub line: usize,
+    pub name: String,
+    pub value: String,
+}
+
+pub fn find_lets(content: &FileContent) -> Vec<LetAssignment> {
+    let mut lets: Vec<LetAssignment> = Vec::new();
+    let mut from = 0;
+    while let Some(caps) = LET.captures_at(&content.text, from) {
+        let whole = caps.get(0).unwrap();
+        let end = value_end(&content.text, whole.end());
+        from = end;
+        lets.push(LetAssignment {
+            line: content.line_at(whole.start()),
+            name: caps[1].to_string(),
+            value: content.text[whole.end()..end].split_whitespace().collect::<Vec<&str>>().join(" "),
+        });
+    }
+    lets
+}
+
+// value_end returns the offset of the semicolon ending a %LET value, ignoring semicolons
+// inside parentheses and quoted strings
+fn value_end(text: &str, from: usize) -> usize {
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    for (i, c) in text[from..].char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') if depth > 0 => depth -= 1,
+            (None, ';') if depth == 0 => return from + i,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+pub fn get_let(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_lets(content)
+        .iter()
+        .map(|l| Finding::new(file_id, "get_let", format!("({}, {}, {})", l.line, l.name, l.value)).at(l.line).value(&l.name).extra("value", &l.value))
+        .collect()
+}
+
+// macro_variable_rows returns the %LET assignments as rows for the macro_variables table
+pub fn macro_variable_rows(content: &FileContent) -> Vec<Vec<String>> {
+    find_lets(content)
+        .into_iter()
+        .map(|l| vec![l.line.to_string(), l.name, l.value])
+        .collect()
+}
+
+// SymputCall is a single CALL SYMPUT or CALL SYMPUTX statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymputCall {
+    pub line: usize,
+    pub routine: String,
+    pub name: String,
+    pub value: String,
+}
+
+pub fn find_symputs(content: &FileContent) -> Vec<SymputCall> {
+    let mut calls: Vec<SymputCall> = Vec::new();
+    for caps in CALL_SYMPUT.captures_iter(&content.text) {
+        let whole = caps.get(0).unwrap();
+        let args = call_arguments(&content.text[whole.end()..]);
+        calls.push(SymputCall {
+            line: content.line_at(whole.start()),
+            routine: caps[1].to_uppercase(),
+            name: args.first().cloned().unwrap_or_default(),
+            value: args.get(1).cloned().unwrap_or_default(),
+        });
+    }
+    calls
+}
+
+// call_arguments splits the argument list that follows an opening parenthesis at its top level
+// commas, stopping at the closing parenthesis (or the end of the statement)
+pub(crate) fn call_arguments(text: &str) -> Vec<String> {
+    let mut args: Vec<String> = Vec::new();
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ',') if depth == 0 => {
+                args.push(text[start..i].split_whitespace().collect::<Vec<&str>>().join(" "));
+                start = i + 1;
+            }
+            (None, ')' | ';') if depth == 0 => {
+                args.push(text[start..i].split_whitespace().collect::<Vec<&str>>().join(" "));
+                return args;
+            }
+            (None, ')') => depth -= 1,
+            _ => {}
+        }
+    }
+    args.push(text[start..].split_whitespace().collect::<Vec<&str>>().join(" "));
+    args
+}
+
+pub fn get_symput(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_symputs(content)
+        .iter()
+        .map(|c| {
+            Finding::new(file_id, "get_symput", format!("({}, {}, {}, {})", c.line, c.routine, c.name, c.value))
+                .at(c.line)
+                .value(&c.name)
+                .extra("routine", &c.routine)
+                .extra("value", &c.value)
+        })
+        .collect()
+}
+
+// SysfuncCall is a single %SYSFUNC or %QSYSFUNC call
+#[derive(Debug, Clone, PartialEq)]
+pub struct SysfuncCall {
+    pub line: usize,
+    pub macro_function: String,
+    pub function: String,
+    pub arguments: String,
+    pub format: String,
+}
+
+pub fn find_sysfunc_calls(content: &FileContent) -> Vec<SysfuncCall> {
+    let mut calls: Vec<SysfuncCall> = Vec::new();
+    for caps in SYSFUNC.captures_iter(&content.text) {
+        let whole = caps.get(0).unwrap();
+        // the outer arguments are the function call and the format
+        let outer = call_arguments(&content.text[caps.get(2).unwrap().end()..]);
+        calls.push(SysfuncCall {
+            line: content.line_at(whole.start()),
+            macro_function: format!("%{}", caps[1].to_uppercase()),
+            function: caps[3].to_uppercase(),
+            arguments: call_arguments(&content.text[whole.end()..]).join(", "),
+            format: outer.get(1).cloned().unwrap_or_default(),
+        });
+    }
+    calls
+}
+
+pub fn get_sysfunc(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_sysfunc_calls(content)
+        .iter()
+        .map(|c| {
+            Finding::new(
+                file_id,
+                "get_sysfunc",
+                format!("({}, {}, {}, ({}), {})", c.line, c.macro_function, c.function, c.arguments, c.format),
+            )
+            .at(c.line)
+            .value(&c.function)
+            .extra("macro_function", &c.macro_function)
+            .extra("arguments", &c.arguments)
+            .extra("format", &c.format)
+        })
+        .collect()
+}
+
+// the automatic macro variables SAS sets, which describe the session, host and last step
+pub const AUTOMATIC_MACRO_VARIABLES: &[&str] = &[
+    "SYSADDRBITS", "SYSBUFFR", "SYSCC", "SYSCHARWIDTH", "SYSCMD", "SYSDATASTEPPHASE", "SYSDATE", "SYSDATE9",
+    "SYSDAY", "SYSDEVIC", "SYSDMG", "SYSDSN", "SYSENCODING", "SYSENDIAN", "SYSENV", "SYSERR", "SYSERRORTEXT",
+    "SYSFILRC", "SYSHOSTINFOLONG", "SYSHOSTNAME", "SYSINDEX", "SYSINFO", "SYSJOBID", "SYSLAST", "SYSLCKRC",
+    "SYSLIBRC", "SYSLOGAPPLNAME", "SYSMACRONAME", "SYSMENV", "SYSMSG", "SYSNCPU", "SYSNOBS", "SYSODSESCAPECHAR",
+    "SYSODSPATH", "SYSPARM", "SYSPBUFF", "SYSPROCESSID", "SYSPROCESSMODE", "SYSPROCESSNAME", "SYSPROCNAME",
+    "SYSRC", "SYSSCP", "SYSSCPL", "SYSSITE", "SYSSIZEOFLONG", "SYSSIZEOFPTR", "SYSSIZEOFUNICODE", "SYSSTARTID",
+    "SYSSTARTNAME", "SYSTCPIPHOSTNAME", "SYSTIME", "SYSTIMEZONE", "SYSTIMEZONEIDENT", "SYSTIMEZONEOFFSET",
+    "SYSUSERID", "SYSVER", "SYSVLONG", "SYSVLONG4", "SYSWARNINGTEXT",
+];
+
+// AutoMacroVariable is a single reference to an automatic macro variable
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoMacroVariable {
+    pub line: usize,
+    pub name: String,
+}
+
+pub fn find_auto_macro_vars(content: &FileContent) -> Vec<AutoMacroVariable> {
+    let mut references: Vec<AutoMacroVariable> = Vec::new();
+    for token in lexer::tokens(&content.text) {
+        // the argument of %str() and the other quoting functions is part of their token
+        let resolved = match token.kind {
+            TokenKind::MacroVariable | TokenKind::MacroCall => true,
+            TokenKind::String => token.text.starts_with('"'),
+            _ => false,
+        };
+        if !resolved {
+            continue;
+        }
+        for caps in MACRO_VARIABLE_REF.captures_iter(token.text) {
+            let name = caps[1].to_uppercase();
+            if AUTOMATIC_MACRO_VARIABLES.contains(&name.as_str()) {
+                let line = content.line_at(token.start + caps.get(0).unwrap().start());
+                references.push(AutoMacroVariable { line, name });
+            }
+        }
+    }
+    references
+}
+
+pub fn get_auto_macro_vars(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_auto_macro_vars(content)
+        .iter()
+        .map(|r| Finding::new(file_id, "get_auto_macro_vars", format!("({}, &{})", r.line, r.name)).at(r.line).value(&r.name))
+        .collect()
+}
@@ -0,0 +1,84 @@
+use super::datastep::retain::find_row_order;
+use super::includes::find_includes;
+use super::macros::find_macros;
+use super::passthrough::find_passthrough;
+use super::shell::find_commands;
+use super::sql::find_sql_blocks;
+use super::FileContent;
+use crate::scanner::FileInfo;
+
+/* -------------------------
+* Migration difficulty score (with --migration-score):
+* - MigrationMetrics::count: counts the constructs of a file that make it harder to move off SAS:
+*   PROC SQL blocks, macro definitions, RETAIN/sum/LAG/DIF row order dependencies, operating system
+*   commands, explicit pass-through SQL sections and %INCLUDEs.
+* - migration_scores: weighs the counts of every scanned file into one score per file (the sum of
+*   each count times its weight) for the migration_scores table, hardest file first.
+* The weights default to DEFAULT_WEIGHTS and can be changed with --migration-score=name=weight,...
+* (e.g. --migration-score=shell_calls=10,includes=0); metrics not given keep their default. The
+* counts are kept per file (and cached), and the weights applied when the table is written, so
+* changing the weights does not need a new parse.
+* --------------------------- */
+
+// the metrics, in column order
+pub const METRICS: [&str; 6] = ["sql_blocks", "macros", "retain_lag", "shell_calls", "passthrough", "includes"];
+
+pub const DEFAULT_WEIGHTS: [f64; 6] = [2.0, 3.0, 2.0, 5.0, 4.0, 1.0];
+
+pub const MIGRATION_HEADERS: [&str; 9] =
+    ["uuid", "file", "sql_blocks", "macros", "retain_lag", "shell_calls", "passthrough", "includes", "score"];
+
+// MigrationMetrics are the counts of each of METRICS in one file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MigrationMetrics {
+    pub counts: [usize; 6],
+}
+
+impl MigrationMetrics {
+    pub fn count(content: &FileContent) -> MigrationMetrics {
+        MigrationMetrics {
+            counts: [
+                find_sql_blocks(content).len(),
+                find_macros(content).len(),
+                find_row_order(content).len(),
+                find_commands(content).len(),
+                find_passthrough(content).len(),
+                find_includes(content).len(),
+            ],
+        }
+    }
+
+    pub fn score(&self, weights: &[f64; 6]) -> f64 {
+        self.counts.iter().zip(weights).map(|(count, weight)| *count as f64 * weight).sum()
+    }
+}
+
+// parse_weights reads name=weight pairs, separated by commas, over the default weights
+pub fn parse_weights(text: &str) -> Result<[f64; 6], String> {
+    let mut weights = DEFAULT_WEIGHTS;
+    for pair in text.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=').ok_or_else(|| format!("expected name=weight, got {}", pair))?;
+        let i = METRICS
+            .iter()
+            .position(|metric| metric.eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| format!("unknown metric {} (expected one of {})", name.trim(), METRICS.join(", ")))?;
+        weights[i] = value.trim().parse::<f64>().map_err(|_| format!("weight of {} must be a number, got {}", name.trim(), value.trim()))?;
+    }
+    Ok(weights)
+}
+
+// migration_scores returns the migration_scores table rows, the highest score first
+pub fn migration_scores(files: &[(&FileInfo, MigrationMetrics)], weights: &[f64; 6]) -> Vec<Vec<String>> {
+    let mut scored: Vec<(f64, &FileInfo, &MigrationMetrics)> =
+        files.iter().map(|(file, metrics)| (metrics.score(weights), *file, metrics)).collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored
+        .into_iter()
+        .map(|(score, file, metrics)| {
+            let mut row = vec![file.uuid.clone(), file.path()];
+            row.extend(metrics.counts.iter().map(|count| count.to_string()));
+            row.push(score.to_string());
+            row
+        })
+        .collect()
+}
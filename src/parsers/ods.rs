@@ -0,0 +1,55 @@
+use super::patterns::ODS_FILE_OPTION;
+use super::{split_statements, split_word, FileContent, Finding};
+
+/* -------------------------
+* ODS parse functions:
+* - get_ods: returns each ODS statement that opens a destination with an output file, as line,
+*   destination (PDF, EXCEL, HTML, RTF, TAGSETS.EXCELXP, ...) and path, so every report a program
+*   produces is inventoried. The path is the FILE= (or BODY=) value, after the PATH= directory
+*   when there is one. ODS statements that write no file (ODS PDF CLOSE, ODS SELECT, ODS
+*   GRAPHICS) are left out.
+* --------------------------- */
+
+// OdsOutput is one ODS destination opened with an output file
+#[derive(Debug, Clone, PartialEq)]
+pub struct OdsOutput {
+    pub line: usize,
+    pub destination: String,
+    pub path: String,
+}
+
+pub fn find_ods_outputs(content: &FileContent) -> Vec<OdsOutput> {
+    let mut outputs: Vec<OdsOutput> = Vec::new();
+    for statement in split_statements(&content.text) {
+        if statement.keyword() != "ODS" {
+            continue;
+        }
+        let body = &statement.text["ODS".len()..statement.text.len() - 1];
+        let (destination, options) = split_word(body.trim_start());
+        let destination = destination.split('(').next().unwrap_or("").to_uppercase();
+        let mut directory = String::new();
+        let mut file = String::new();
+        for caps in ODS_FILE_OPTION.captures_iter(options) {
+            let value = caps[2].trim_matches(['\'', '"']).to_string();
+            match caps[1].to_uppercase().as_str() {
+                "PATH" => directory = value,
+                _ if file.is_empty() => file = value,
+                _ => {}
+            }
+        }
+        let path = match (directory.is_empty(), file.is_empty()) {
+            (true, true) => continue,
+            (false, false) => format!("{}/{}", directory.trim_end_matches(['/', '\\']), file),
+            _ => directory + &file,
+        };
+        outputs.push(OdsOutput { line: content.line_at(statement.start), destination, path });
+    }
+    outputs
+}
+
+pub fn get_ods(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_ods_outputs(content)
+        .iter()
+        .map(|o| Finding::new(file_id, "get_ods", format!("({}, {}, {})", o.line, o.destination, o.path)).at(o.line).value(&o.path).extra("destination", &o.destination))
+        .collect()
+}
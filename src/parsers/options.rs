@@ -0,0 +1,76 @@
+use super::{quoted_end, split_statements, FileContent, Finding};
+
+/* -------------------------
+* OPTIONS parse functions:
+* - get_options: splits each OPTIONS statement into its system options, one row per option with
+*   the line, the option name and its value: OBS=10, COMPRESS=YES, SASAUTOS=('/sas/macros'
+*   sasautos). A flag such as NOCENTER or MPRINT has no value. System options change how the rest
+*   of the program runs without showing up anywhere else in the code.
+* --------------------------- */
+
+pub const OPTION_COLUMNS: [&str; 3] = ["line", "option", "value"];
+
+// SystemOption is one option set by an OPTIONS statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemOption {
+    pub line: usize,
+    pub name: String,
+    pub value: String,
+}
+
+pub fn find_options(content: &FileContent) -> Vec<SystemOption> {
+    let mut options: Vec<SystemOption> = Vec::new();
+    for statement in split_statements(&content.text) {
+        let keyword = statement.keyword();
+        if keyword != "OPTIONS" && keyword != "OPTION" {
+            continue;
+        }
+        let body_start = statement.start + keyword.len();
+        let body = &statement.text[keyword.len()..statement.text.len() - 1];
+        options.extend(split_options(body).into_iter().map(|(offset, name, value)| SystemOption {
+            line: content.line_at(body_start + offset),
+            name,
+            value,
+        }));
+    }
+    options
+}
+
+// split_options splits the body of an OPTIONS statement into (offset, uppercased name, value); a
+// value is a quoted string, a parenthesized list or a word
+fn split_options(body: &str) -> Vec<(usize, String, String)> {
+    let mut options: Vec<(usize, String, String)> = Vec::new();
+    let mut rest = body.trim_start();
+    while !rest.is_empty() {
+        let offset = body.len() - rest.len();
+        let end = rest.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(rest.len());
+        let name = rest[..end].to_uppercase();
+        rest = rest[end..].trim_start();
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let value_end = match after.starts_with(['\'', '"', '(']) {
+                true => quoted_end(after),
+                false => after.find(char::is_whitespace).unwrap_or(after.len()),
+            };
+            value = after[..value_end].split_whitespace().collect::<Vec<&str>>().join(" ");
+            rest = after[value_end..].trim_start();
+        }
+        if !name.is_empty() {
+            options.push((offset, name, value));
+        }
+    }
+    options
+}
+
+pub fn get_options(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_options(content)
+        .iter()
+        .map(|o| Finding::new(file_id, "get_options", format!("({}, {}, {})", o.line, o.name, o.value)).at(o.line).value(&o.name).extra("value", &o.value))
+        .collect()
+}
+
+// option_rows returns the options as rows for the options table
+pub fn option_rows(content: &FileContent) -> Vec<Vec<String>> {
+    find_options(content).into_iter().map(|o| vec![o.line.to_string(), o.name, o.value]).collect()
+}
@@ -0,0 +1,196 @@
+use super::patterns::{CONNECT, CONNECT_OPTION, PASSTHROUGH_BY, PASSTHROUGH_START};
+use super::sql::{find_sql_blocks, SqlBlock};
+use super::{quoted_end, split_statements, split_word, FileContent, Finding};
+
+/* -------------------------
+* SQL pass-through parse functions:
+* - get_connections: parses each CONNECT TO statement in PROC SQL into the DBMS, the connection
+*   alias, the server, path and schema options and the remaining options, so every external
+*   database touch point is inventoried. CONNECT USING libref is reported with the libref as the DBMS.
+* - get_passthrough: return the native SQL of each EXECUTE (...) BY and CONNECTION TO (...) section as
+*   line, kind (EXECUTE or QUERY), target DBMS and SQL text. The DBMS is looked up from the connection
+*   alias of the CONNECT TO statements in the same PROC SQL block.
+* --------------------------- */
+
+pub const CONNECTION_COLUMNS: [&str; 7] = ["line", "dbms", "alias", "server", "path", "schema", "options"];
+
+// connection options that name the server, and the database/path, of a CONNECT TO statement
+const SERVER_OPTIONS: &[&str] = &["SERVER", "HOST", "DSN", "DATASRC", "TDPID"];
+const PATH_OPTIONS: &[&str] = &["PATH", "DATABASE", "DB", "CATALOG"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Connection {
+    pub line: usize,
+    pub dbms: String,
+    pub alias: String,
+    pub server: String,
+    pub path: String,
+    pub schema: String,
+    pub options: String,
+    // CONNECT USING libref, where dbms is the libref
+    pub using: bool,
+}
+
+pub fn find_connections(content: &FileContent) -> Vec<Connection> {
+    find_sql_blocks(content).iter().flat_map(block_connections).collect()
+}
+
+// block_connections returns the CONNECT TO statements of one PROC SQL block
+fn block_connections(block: &SqlBlock) -> Vec<Connection> {
+    let mut connections: Vec<Connection> = Vec::new();
+    for statement in split_statements(&block.text) {
+        let caps = match CONNECT.captures(statement.text) {
+            Some(caps) => caps,
+            None => continue,
+        };
+        let (dbms, rest) = split_word(caps[2].trim());
+        let mut rest = rest.trim_start();
+        let mut alias = String::new();
+        if rest.len() > 2 && rest[..2].eq_ignore_ascii_case("AS") && rest[2..].starts_with(char::is_whitespace) {
+            let (word, after) = split_word(rest[2..].trim_start());
+            alias = word.to_string();
+            rest = after.trim_start();
+        }
+        let options = if rest.starts_with('(') {
+            rest[1..quoted_end(rest)].trim_end_matches(')')
+        } else {
+            rest
+        };
+
+        let mut connection = Connection {
+            line: block.line_at(statement.start),
+            dbms: dbms.to_uppercase(),
+            alias,
+            server: String::new(),
+            path: String::new(),
+            schema: String::new(),
+            options: options.split_whitespace().collect::<Vec<&str>>().join(" "),
+            using: caps[1].eq_ignore_ascii_case("USING"),
+        };
+        for caps in CONNECT_OPTION.captures_iter(options) {
+            let name = caps[1].to_uppercase();
+            let value = caps[2].trim_matches(['\'', '"']).to_string();
+            if connection.server.is_empty() && SERVER_OPTIONS.contains(&name.as_str()) {
+                connection.server = value;
+            } else if connection.path.is_empty() && PATH_OPTIONS.contains(&name.as_str()) {
+                connection.path = value;
+            } else if connection.schema.is_empty() && name == "SCHEMA" {
+                connection.schema = value;
+            }
+        }
+        connections.push(connection);
+    }
+    connections
+}
+
+pub fn get_connections(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_connections(content)
+        .iter()
+        .map(|c| {
+            Finding::new(
+                file_id,
+                "get_connections",
+                format!("({}, {}, {}, {}, {}, {}, {})", c.line, c.dbms, c.alias, c.server, c.path, c.schema, c.options),
+            )
+            .at(c.line)
+            .value(&c.dbms)
+            .extra("alias", &c.alias)
+            .extra("server", &c.server)
+            .extra("path", &c.path)
+            .extra("schema", &c.schema)
+            .extra("options", &c.options)
+        })
+        .collect()
+}
+
+// connection_rows returns the CONNECT TO statements as rows for the connections table
+pub fn connection_rows(content: &FileContent) -> Vec<Vec<String>> {
+    find_connections(content)
+        .into_iter()
+        .map(|c| vec![c.line.to_string(), c.dbms, c.alias, c.server, c.path, c.schema, c.options])
+        .collect()
+}
+
+// Passthrough is a section of native SQL sent to a database by explicit pass-through
+#[derive(Debug, Clone, PartialEq)]
+pub struct Passthrough {
+    pub line: usize,
+    pub kind: String,
+    pub dbms: String,
+    pub sql: String,
+}
+
+pub fn find_passthrough(content: &FileContent) -> Vec<Passthrough> {
+    let mut sections: Vec<Passthrough> = Vec::new();
+    for block in find_sql_blocks(content) {
+        let connections = block_connections(&block);
+        let dbms_of = |alias: &str| {
+            connections
+                .iter()
+                .find(|c| alias.eq_ignore_ascii_case(if c.alias.is_empty() { &c.dbms } else { &c.alias }))
+                .map(|c| c.dbms.clone())
+                .unwrap_or_else(|| alias.to_uppercase())
+        };
+        let mut from = 0;
+        while let Some(caps) = PASSTHROUGH_START.captures_at(&block.text, from) {
+            let whole = caps.get(0).unwrap();
+            let open = whole.end() - 1;
+            let close = paren_end(&block.text, open);
+            from = close;
+            // an unclosed section runs to the end of the block
+            let inner_end = if block.text[..close].ends_with(')') { close - 1 } else { close };
+            let sql = block.text[open + 1..inner_end].trim();
+            let (kind, alias) = match (caps.get(1), caps.get(2)) {
+                (Some(alias), _) => ("EXECUTE", alias.as_str().to_string()),
+                (_, Some(alias)) => ("QUERY", alias.as_str().to_string()),
+                _ => {
+                    let alias = PASSTHROUGH_BY.captures(&block.text[close..]).map(|by| by[1].to_string()).unwrap_or_default();
+                    ("EXECUTE", alias)
+                }
+            };
+            sections.push(Passthrough {
+                line: block.line_at(whole.start()),
+                kind: kind.to_string(),
+                dbms: dbms_of(&alias),
+                sql: sql.to_string(),
+            });
+        }
+    }
+    sections
+}
+
+// paren_end returns the offset just past the parenthesis that closes the one at `open`,
+// skipping parentheses inside quoted strings
+fn paren_end(text: &str, open: usize) -> usize {
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    for (i, c) in text[open..].char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return open + i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+pub fn get_passthrough(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_passthrough(content)
+        .iter()
+        .map(|p| {
+            Finding::new(file_id, "get_passthrough", format!("({}, {}, {}, {})", p.line, p.kind, p.dbms, p.sql))
+                .at(p.line)
+                .value(&p.sql)
+                .extra("kind", &p.kind)
+                .extra("dbms", &p.dbms)
+        })
+        .collect()
+}
@@ -0,0 +1,42 @@
+use super::patterns::{UNC_PATH, UNIX_PATH, WINDOWS_PATH};
+use super::{FileContent, Finding};
+
+/* -------------------------
+* Path parse functions:
+* - find_paths: flags hardcoded filesystem paths anywhere in the code, with their line number:
+*   Windows drive paths (C:\...), UNC paths (\\server\share\...) and Unix paths with at least
+*   two segments (/sasdata/...). These block relocating programs to a new server.
+* --------------------------- */
+
+// HardcodedPath is one hardcoded path and the kind of path it is (WINDOWS, UNC or UNIX)
+#[derive(Debug, Clone, PartialEq)]
+pub struct HardcodedPath {
+    pub line: usize,
+    pub kind: &'static str,
+    pub path: String,
+}
+
+pub fn find_hardcoded_paths(content: &FileContent) -> Vec<HardcodedPath> {
+    let rules = [("UNC", &*UNC_PATH), ("WINDOWS", &*WINDOWS_PATH), ("UNIX", &*UNIX_PATH)];
+    let mut paths: Vec<HardcodedPath> = Vec::new();
+    for (line_number, line) in content.text.lines().enumerate() {
+        for (kind, re) in &rules {
+            for caps in re.captures_iter(line) {
+                let path = caps.get(1).unwrap_or_else(|| caps.get(0).unwrap());
+                paths.push(HardcodedPath {
+                    line: line_number + 1,
+                    kind,
+                    path: path.as_str().to_string(),
+                });
+            }
+        }
+    }
+    paths
+}
+
+pub fn find_paths(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_hardcoded_paths(content)
+        .iter()
+        .map(|p| Finding::new(file_id, "find_paths", format!("({}, {}, {})", p.line, p.kind, p.path)).at(p.line).value(&p.path).extra("kind", p.kind))
+        .collect()
+}
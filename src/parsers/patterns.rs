@@ -0,0 +1,127 @@
+use super::credentials::{credential_rules, CredentialRule};
+use super::deprecated::{deprecated_rules, DeprecatedRule};
+use super::pii::{pii_rules, PiiRule};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/* -------------------------
+* Regex registry: every fixed pattern the parse functions use, compiled once on first use and
+* shared by every file (and thread) of a run, instead of being compiled again for each file,
+* block or statement. Patterns are grouped by the parse module that uses them.
+* --------------------------- */
+
+fn compile(pattern: &str) -> Regex {
+    Regex::new(pattern).unwrap()
+}
+
+// parsers.rs
+pub static PASSWORD_OPTION: LazyLock<Regex> = LazyLock::new(|| compile(r"(?i)PASSWORD\s*="));
+pub static PASSWORD_MACRO_VARIABLE: LazyLock<Regex> = LazyLock::new(|| compile(r"(?i)&PASSWORD"));
+
+// dates.rs
+const MONTH: &str = "(?:JAN|FEB|MAR|APR|MAY|JUN|JUL|AUG|SEP|OCT|NOV|DEC)";
+const MONTH_NUMBER: &str = r"(?:0?[1-9]|1[0-2])";
+const DAY: &str = r"(?:0?[1-9]|[12]\d|3[01])";
+pub static DATETIME_LITERAL: LazyLock<Regex> = LazyLock::new(|| {
+    compile(&format!(r#"(?i)['"]{}{}\d{{2}}(?:\d{{2}})?:\d{{1,2}}:\d{{2}}(?::\d{{2}}(?:\.\d+)?)?['"]DT\b"#, DAY, MONTH))
+});
+pub static DATE_LITERAL: LazyLock<Regex> =
+    LazyLock::new(|| compile(&format!(r#"(?i)['"]{}{}\d{{2}}(?:\d{{2}})?['"]D\b"#, DAY, MONTH)));
+pub static ISO_DATE: LazyLock<Regex> = LazyLock::new(|| compile(r"\b\d{4}-\d{2}-\d{2}\b"));
+pub static US_DATE: LazyLock<Regex> = LazyLock::new(|| compile(&format!(r"\b{}/{}/\d{{4}}\b", MONTH_NUMBER, DAY)));
+pub static US_DATE_SHORT: LazyLock<Regex> = LazyLock::new(|| compile(&format!(r"\b{}/{}/\d{{2}}\b", MONTH_NUMBER, DAY)));
+pub static DDMONYYYY: LazyLock<Regex> = LazyLock::new(|| compile(&format!(r"(?i)\b{}{}\d{{4}}\b", DAY, MONTH)));
+pub static DDMONYY: LazyLock<Regex> = LazyLock::new(|| compile(&format!(r"(?i)\b{}{}\d{{2}}\b", DAY, MONTH)));
+
+// sql.rs
+pub static SQL_TOKEN: LazyLock<Regex> = LazyLock::new(|| compile(r#"'[^']*'|"[^"]*"|[A-Za-z_&%][A-Za-z0-9_&.%]*|[(),;]"#));
+
+// paths.rs
+pub static UNC_PATH: LazyLock<Regex> = LazyLock::new(|| compile(r#"\\\\[A-Za-z0-9._$-]+\\[^\s'";)]*"#));
+pub static WINDOWS_PATH: LazyLock<Regex> = LazyLock::new(|| compile(r#"\b[A-Za-z]:[\\/][^\s'";)]*"#));
+pub static UNIX_PATH: LazyLock<Regex> = LazyLock::new(|| compile(r#"(?:^|[\s'"=(])(/[A-Za-z0-9._$&-]+(?:/[A-Za-z0-9._$&-]*)+)"#));
+
+// flatfiles.rs
+pub static INFILE_OPTION: LazyLock<Regex> =
+    LazyLock::new(|| compile(r#"(?i)\b(DLM|DELIMITER|FIRSTOBS)\s*=\s*('[^']*'[xX]?|"[^"]*"[xX]?|[^\s;]+)"#));
+
+// datastep/hash.rs: DECLARE HASH h(args) or h = _NEW_ HASH(args)
+pub static HASH_DECLARE: LazyLock<Regex> = LazyLock::new(|| {
+    compile(r"(?i)(?:\b(?:DECLARE|DCL)\s+(HASH|HITER)\s+([A-Za-z_]\w*)|\b([A-Za-z_]\w*)\s*=\s*_NEW_\s+(HASH|HITER))\s*(\()?")
+});
+
+// hosts.rs
+pub static URL_HOST: LazyLock<Regex> =
+    LazyLock::new(|| compile(r#"(?i)\b[a-z][a-z0-9+.-]*://(?:[^\s/@'"]+@)?([a-z0-9](?:[a-z0-9.-]*[a-z0-9])?(?::\d+)?)"#));
+pub static HOST_OPTION: LazyLock<Regex> =
+    LazyLock::new(|| compile(r#"(?i)\b(?:SERVER|HOST|HOSTNAME|REMOTE)\s*=\s*('[^']*'|"[^"]*"|[^\s;)]+)"#));
+pub static IP_ADDRESS: LazyLock<Regex> = LazyLock::new(|| compile(r"\b(?:\d{1,3}\.){3}\d{1,3}\b"));
+
+// macros.rs
+pub static MACRO_START: LazyLock<Regex> = LazyLock::new(|| compile(r"(?i)%MACRO\s+([A-Z_][A-Z0-9_]*)\s*(?:\(([^)]*)\))?[^;]*;"));
+pub static MACRO_END: LazyLock<Regex> = LazyLock::new(|| compile(r"(?i)%MEND\b[^;]*;"));
+pub static MACRO_CALL: LazyLock<Regex> = LazyLock::new(|| compile(r"%([A-Za-z_][A-Za-z0-9_]*)"));
+
+// macrovars.rs
+pub static LET: LazyLock<Regex> = LazyLock::new(|| compile(r"(?i)%LET\s+([^=;\s]+)\s*="));
+pub static CALL_SYMPUT: LazyLock<Regex> = LazyLock::new(|| compile(r"(?i)\bCALL\s+(SYMPUTX?)\s*\("));
+// a macro variable reference in a double quoted string (which resolves it)
+pub static MACRO_VARIABLE_REF: LazyLock<Regex> = LazyLock::new(|| compile(r"&+([A-Za-z_]\w*)"));
+// %SYSFUNC( or %QSYSFUNC( and the name of the function it calls
+pub static SYSFUNC: LazyLock<Regex> = LazyLock::new(|| compile(r"(?i)%(Q?SYSFUNC)\s*(\()\s*([A-Za-z_]\w*)\s*\("));
+
+// ods.rs
+pub static ODS_FILE_OPTION: LazyLock<Regex> =
+    LazyLock::new(|| compile(r#"(?i)\b(FILE|BODY|PATH)\s*=\s*('[^']*'|"[^"]*"|[^\s;()]+)"#));
+
+// shell.rs
+pub static SHELL_CALL: LazyLock<Regex> = LazyLock::new(|| compile(r"(?i)%SYSEXEC\b|\bCALL\s+SYSTEM\s*\("));
+
+// libname.rs
+pub static LIBNAME_OPTION: LazyLock<Regex> = LazyLock::new(|| compile(r#"(?i)\b([A-Z_]+)\s*=\s*('[^']*'|"[^"]*"|\([^)]*\)|[^\s;]+)"#));
+
+// includes.rs
+pub static INCLUDE: LazyLock<Regex> = LazyLock::new(|| compile(r"(?i)%INC(?:LUDE)?\b([^;]*);"));
+pub static INCLUDE_TARGET: LazyLock<Regex> = LazyLock::new(|| compile(r#"'([^']*)'|"([^"]*)"|([A-Za-z_][A-Za-z0-9_]*(?:\s*\([^)]*\))?)|/"#));
+
+// passthrough.rs
+pub static CONNECT: LazyLock<Regex> = LazyLock::new(|| compile(r"(?is)^CONNECT\s+(TO|USING)\s+(.*);$"));
+pub static CONNECT_OPTION: LazyLock<Regex> = LazyLock::new(|| compile(r#"(?i)\b([A-Z_]+)\s*=\s*('[^']*'|"[^"]*"|[^\s)]+)"#));
+// EXECUTE (sql) BY alias, EXECUTE BY alias (sql), and FROM CONNECTION TO alias (sql)
+pub static PASSTHROUGH_START: LazyLock<Regex> =
+    LazyLock::new(|| compile(r"(?i)\bEXECUTE\s*\(|\bEXECUTE\s+BY\s+([A-Za-z_]\w*)\s*\(|\bCONNECTION\s+TO\s+([A-Za-z_]\w*)\s*\("));
+pub static PASSTHROUGH_BY: LazyLock<Regex> = LazyLock::new(|| compile(r"(?i)^\s*BY\s+([A-Za-z_]\w*)"));
+
+// procs.rs
+pub static IMPORT_EXPORT_OPTION: LazyLock<Regex> =
+    LazyLock::new(|| compile(r#"(?i)\b(DATAFILE|OUTFILE|FILE|TABLE|DBMS|OUT|DATA|SHEET|RANGE)\s*=\s*('[^']*'|"[^"]*"|[^\s;()]+(?:\([^)]*\))?)"#));
+
+// filename.rs: the recipient and subject options of FILENAME ... EMAIL
+pub static EMAIL_OPTION: LazyLock<Regex> =
+    LazyLock::new(|| compile(r#"(?i)\b(TO|CC|BCC|SUBJECT)\s*=\s*(\([^)]*\)|'(?:[^']|'')*'|"(?:[^"]|"")*"|[^\s;]+)"#));
+// the OUTLIB= package of a PROC FCMP step
+pub static FCMP_OUTLIB: LazyLock<Regex> = LazyLock::new(|| compile(r#"(?i)\bOUTLIB\s*=\s*([^\s;]+)"#));
+// the library of a PROC DATASETS step
+pub static DATASETS_LIBRARY: LazyLock<Regex> = LazyLock::new(|| compile(r#"(?i)\b(?:LIBRARY|LIB|DDNAME|DD)\s*=\s*('[^']*'|"[^"]*"|[^\s;()]+)"#));
+pub static FORMAT_OPTION: LazyLock<Regex> = LazyLock::new(|| compile(r#"(?i)\b(LIBRARY|LIB|CNTLIN)\s*=\s*('[^']*'|"[^"]*"|[^\s;()]+)"#));
+
+// logs.rs: ERROR:, WARNING 123-456: and NOTE: messages, the step a NOTE: ... used reports the time of,
+// the real time and cpu time lines below it and the program a log was written by
+pub static LOG_MESSAGE: LazyLock<Regex> = LazyLock::new(|| compile(r"^(ERROR|WARNING|NOTE)(?:\s+(\d+-\d+))?:\s*(.*)$"));
+pub static LOG_STEP: LazyLock<Regex> = LazyLock::new(|| compile(r"^NOTE: (?:PROCEDURE\s+(\w+)|DATA statement) used"));
+pub static LOG_TIME: LazyLock<Regex> = LazyLock::new(|| compile(r"^\s+(real|cpu) time\s+([\d:.]+)"));
+pub static LOG_PROGRAM: LazyLock<Regex> = LazyLock::new(|| {
+    compile(r#"(?i)\b_SASPROGRAMFILE\s*=\s*(?:'([^']*)'|"([^"]*)"|([^\s;]+))|-SYSIN\s+(?:'([^']*)'|"([^"]*)"|(\S+))"#)
+});
+
+// credentials.rs: each credential rule with its compiled pattern
+pub static CREDENTIAL_RULES: LazyLock<Vec<(CredentialRule, Regex)>> =
+    LazyLock::new(|| credential_rules().into_iter().map(|rule| { let re = compile(&rule.pattern); (rule, re) }).collect());
+
+// pii.rs: each PII rule with its compiled pattern
+pub static PII_RULES: LazyLock<Vec<(PiiRule, Regex)>> =
+    LazyLock::new(|| pii_rules().into_iter().map(|rule| { let re = compile(rule.pattern); (rule, re) }).collect());
+
+// deprecated.rs: each deprecated syntax rule with its compiled pattern (matched against uppercased code)
+pub static DEPRECATED_RULES: LazyLock<Vec<(DeprecatedRule, Regex)>> =
+    LazyLock::new(|| deprecated_rules().into_iter().map(|rule| { let re = compile(&format!("(?i){}", rule.pattern)); (rule, re) }).collect());
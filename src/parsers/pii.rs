@@ -0,0 +1,105 @@
+use super::datastep::find_inline_data;
+use super::patterns::PII_RULES;
+use super::{FileContent, Finding};
+
+/* -------------------------
+* PII parse functions:
+* - find_pii: runs every PII rule against each line and returns (line, rule id, where, sample)
+*   for each hit, where is CODE or DATALINES. Personal data hardcoded in a program (a test SSN in
+*   a WHERE clause, a contact list in a CARDS block) has to be found before code is shared or moved.
+* Comments are skipped unless they are included; the lines of DATALINES/CARDS blocks are data, so
+* quotes and comment characters in them are read as written.
+* The sample is redacted: only the last four digits of an SSN or phone number, and the first
+* character and the domain of an email address, are kept.
+* --------------------------- */
+
+// PiiRule is one kind of personal data the PII scanner looks for
+pub struct PiiRule {
+    pub id: &'static str,
+    pub description: &'static str,
+    pub pattern: &'static str,
+}
+
+pub fn pii_rules() -> Vec<PiiRule> {
+    vec![
+        PiiRule {
+            id: "PII001",
+            description: "US social security number (123-45-6789)",
+            pattern: r"\b\d{3}-\d{2}-\d{4}\b",
+        },
+        PiiRule {
+            id: "PII002",
+            description: "phone number ((555) 123-4567, 555-123-4567, +1 555.123.4567)",
+            pattern: r"(?:\+1[\s.-]?)?(?:\(\d{3}\)\s?|\b\d{3}[\s.-])\d{3}[.-]\d{4}\b",
+        },
+        PiiRule {
+            id: "PII003",
+            description: "email address",
+            pattern: r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b",
+        },
+    ]
+}
+
+// PiiHit is one match of a PII rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct PiiHit {
+    pub line: usize,
+    pub rule_id: &'static str,
+    pub location: &'static str,
+    pub sample: String,
+}
+
+pub fn find_pii_hits(content: &FileContent) -> Vec<PiiHit> {
+    let data = find_inline_data(content);
+    let mut hits: Vec<PiiHit> = Vec::new();
+    for (line_number, line) in content.text.lines().enumerate() {
+        let in_data = data.iter().any(|block| block.contains(line_number + 1));
+        let location = if in_data { "DATALINES" } else { "CODE" };
+        for (rule, re) in PII_RULES.iter() {
+            for m in re.find_iter(line) {
+                hits.push(PiiHit {
+                    line: line_number + 1,
+                    rule_id: rule.id,
+                    location,
+                    sample: redact(rule.id, m.as_str()),
+                });
+            }
+        }
+    }
+    hits
+}
+
+// redact masks a match: the first character and domain of an email address are kept, and the
+// last four digits of a number
+fn redact(rule_id: &str, matched: &str) -> String {
+    if rule_id == "PII003" {
+        let (local, domain) = matched.split_once('@').unwrap_or((matched, ""));
+        let first: String = local.chars().take(1).collect();
+        return format!("{}***@{}", first, domain);
+    }
+    let digits = matched.chars().filter(char::is_ascii_digit).count();
+    let mut seen = 0;
+    matched
+        .chars()
+        .map(|c| {
+            if !c.is_ascii_digit() {
+                return c;
+            }
+            seen += 1;
+            if seen > digits.saturating_sub(4) { c } else { '*' }
+        })
+        .collect()
+}
+
+pub fn find_pii(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_pii_hits(content)
+        .iter()
+        .map(|hit| {
+            Finding::new(file_id, "find_pii", format!("({}, {}, {}, {})", hit.line, hit.rule_id, hit.location, hit.sample))
+                .at(hit.line)
+                .value(&hit.sample)
+                .extra("rule_id", hit.rule_id)
+                .extra("location", hit.location)
+        })
+        .collect()
+}
@@ -0,0 +1,340 @@
+use super::{lexer, split_statements, split_word, FileContent, Finding, Statement};
+use super::variables::compact;
+use std::collections::BTreeMap;
+use super::patterns::{DATASETS_LIBRARY, FCMP_OUTLIB, FORMAT_OPTION, IMPORT_EXPORT_OPTION};
+
+/* -------------------------
+* PROC parse functions:
+* - proc_inventory: counts every PROC <name> statement in the file and returns one row per
+*   procedure with its count, so we can see which procedures a codebase relies on.
+* - get_import_export: parses PROC IMPORT and PROC EXPORT steps into the dataset, the external
+*   file (DATAFILE=/OUTFILE=), DBMS=, and the SHEET= and RANGE= options.
+* - get_formats: returns each format defined in a PROC FORMAT step (VALUE, INVALUE and PICTURE
+*   statements) as line, kind, format name and the library it is stored in (LIBRARY=, WORK by
+*   default), and each CNTLIN= dataset formats are built from. Custom formats live in catalogs
+*   outside the code but have to be migrated with it.
+* - get_datasets_operations: returns each destructive operation of a PROC DATASETS step as line,
+*   operation, library and the members affected: DELETE, CHANGE (as old=new), AGE, SAVE (every
+*   other member is deleted) and the KILL option (every member is deleted, shown as _ALL_). The
+*   library is the LIBRARY= option of the step, WORK by default. What a job destroys matters as
+*   much as what it creates.
+* - get_fcmp_functions: returns each FUNCTION and SUBROUTINE defined in a PROC FCMP step as line,
+*   kind, name, arguments (a $ marks a character argument), what a function returns (CHARACTER or
+*   NUMERIC, empty for a subroutine) and the OUTLIB= package it is stored in. These functions have
+*   to be reimplemented on any target platform.
+* --------------------------- */
+
+pub const IMPORT_EXPORT_COLUMNS: [&str; 7] = ["line", "proc", "dataset", "file", "dbms", "sheet", "range"];
+
+// ProcBlock is a PROC step: the PROC statement and the statements up to its RUN; or QUIT;
+// (or the next DATA/PROC statement, or the end of the file, when the step is not closed).
+// PROC DATASETS runs in RUN groups, so only QUIT; ends it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcBlock<'a> {
+    pub name: String,
+    pub start_line: usize,
+    pub statements: Vec<Statement<'a>>,
+}
+
+pub fn find_proc_blocks(content: &FileContent) -> Vec<ProcBlock<'_>> {
+    let mut blocks: Vec<ProcBlock> = Vec::new();
+    let mut open: Option<ProcBlock> = None;
+    for statement in split_statements(&content.text) {
+        let keyword = statement.keyword();
+        if keyword == "PROC" || keyword == "DATA" {
+            blocks.extend(open.take());
+            if let Some(name) = proc_name(statement.text) {
+                open = Some(ProcBlock {
+                    name,
+                    start_line: content.line_at(statement.start),
+                    statements: vec![statement],
+                });
+            }
+            continue;
+        }
+        if let Some(block) = open.as_mut() {
+            block.statements.push(statement);
+            if keyword == "QUIT" || (keyword == "RUN" && block.name != "DATASETS") {
+                blocks.extend(open.take());
+            }
+        }
+    }
+    blocks.extend(open);
+    blocks
+}
+
+// ImportExport is a PROC IMPORT or PROC EXPORT step
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportExport {
+    pub line: usize,
+    pub proc: String,
+    pub dataset: String,
+    pub file: String,
+    pub dbms: String,
+    pub sheet: String,
+    pub range: String,
+}
+
+pub fn find_import_export(content: &FileContent) -> Vec<ImportExport> {
+    let mut steps: Vec<ImportExport> = Vec::new();
+    for block in find_proc_blocks(content) {
+        if block.name != "IMPORT" && block.name != "EXPORT" {
+            continue;
+        }
+        let mut step = ImportExport {
+            line: block.start_line,
+            proc: block.name.clone(),
+            dataset: String::new(),
+            file: String::new(),
+            dbms: String::new(),
+            sheet: String::new(),
+            range: String::new(),
+        };
+        for statement in &block.statements {
+            for caps in IMPORT_EXPORT_OPTION.captures_iter(statement.text) {
+                let value = caps[2].trim_matches(['\'', '"']).to_string();
+                match caps[1].to_uppercase().as_str() {
+                    "DATAFILE" | "OUTFILE" | "FILE" | "TABLE" => step.file = value,
+                    "DBMS" => step.dbms = value.to_uppercase(),
+                    "OUT" if block.name == "IMPORT" => step.dataset = value,
+                    "DATA" if block.name == "EXPORT" => step.dataset = value,
+                    "SHEET" => step.sheet = value,
+                    "RANGE" => step.range = value,
+                    _ => {}
+                }
+            }
+        }
+        steps.push(step);
+    }
+    steps
+}
+
+pub fn get_import_export(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_import_export(content)
+        .iter()
+        .map(|s| {
+            Finding::new(
+                file_id,
+                "get_import_export",
+                format!("({}, {}, {}, {}, {}, {}, {})", s.line, s.proc, s.dataset, s.file, s.dbms, s.sheet, s.range),
+            )
+            .at(s.line)
+            .value(&s.dataset)
+            .extra("proc", &s.proc)
+            .extra("file", &s.file)
+            .extra("dbms", &s.dbms)
+            .extra("sheet", &s.sheet)
+            .extra("range", &s.range)
+        })
+        .collect()
+}
+
+// import_export_rows returns the PROC IMPORT/EXPORT steps as rows for the import_export table
+pub fn import_export_rows(content: &FileContent) -> Vec<Vec<String>> {
+    find_import_export(content)
+        .into_iter()
+        .map(|s| vec![s.line.to_string(), s.proc, s.dataset, s.file, s.dbms, s.sheet, s.range])
+        .collect()
+}
+
+// FormatDefinition is a format defined in PROC FORMAT, or a CNTLIN= dataset (kind CNTLIN) formats
+// are loaded from
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatDefinition {
+    pub line: usize,
+    pub kind: String,
+    pub name: String,
+    pub library: String,
+}
+
+pub fn find_formats(content: &FileContent) -> Vec<FormatDefinition> {
+    let mut formats: Vec<FormatDefinition> = Vec::new();
+    for block in find_proc_blocks(content).iter().filter(|block| block.name == "FORMAT") {
+        let mut library = String::from("WORK");
+        let mut cntlin = String::new();
+        for caps in FORMAT_OPTION.captures_iter(block.statements[0].text) {
+            let value = caps[2].trim_matches(['\'', '"']).to_string();
+            match caps[1].to_uppercase().as_str() {
+                "CNTLIN" => cntlin = value,
+                _ => library = value,
+            }
+        }
+        if !cntlin.is_empty() {
+            formats.push(FormatDefinition { line: block.start_line, kind: String::from("CNTLIN"), name: cntlin, library: library.clone() });
+        }
+        for statement in &block.statements[1..] {
+            let kind = statement.keyword();
+            if !matches!(kind.as_str(), "VALUE" | "INVALUE" | "PICTURE") {
+                continue;
+            }
+            let (name, _) = split_word(statement.text[kind.len()..].trim_start());
+            let name = name.split(['(', ';', '=']).next().unwrap_or("");
+            if name.is_empty() {
+                continue;
+            }
+            formats.push(FormatDefinition {
+                line: content.line_at(statement.start),
+                kind,
+                name: name.to_string(),
+                library: library.clone(),
+            });
+        }
+    }
+    formats
+}
+
+pub fn get_formats(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_formats(content)
+        .iter()
+        .map(|f| {
+            Finding::new(file_id, "get_formats", format!("({}, {}, {}, {})", f.line, f.kind, f.name, f.library))
+                .at(f.line)
+                .value(&f.name)
+                .extra("kind", &f.kind)
+                .extra("library", &f.library)
+        })
+        .collect()
+}
+
+// DatasetsOperation is a destructive statement (or the KILL option) of a PROC DATASETS step
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetsOperation {
+    pub line: usize,
+    pub operation: String,
+    pub library: String,
+    pub members: String,
+}
+
+pub fn find_datasets_operations(content: &FileContent) -> Vec<DatasetsOperation> {
+    let mut operations: Vec<DatasetsOperation> = Vec::new();
+    for block in find_proc_blocks(content).iter().filter(|block| block.name == "DATASETS") {
+        let proc_statement = block.statements[0].text;
+        let library = match DATASETS_LIBRARY.captures(proc_statement) {
+            Some(caps) => caps[1].trim_matches(['\'', '"']).to_string(),
+            None => String::from("WORK"),
+        };
+        if lexer::tokens(proc_statement).any(|token| token.is_word("KILL")) {
+            operations.push(DatasetsOperation {
+                line: block.start_line,
+                operation: String::from("KILL"),
+                library: library.clone(),
+                members: String::from("_ALL_"),
+            });
+        }
+        for statement in &block.statements[1..] {
+            let operation = statement.keyword();
+            if !matches!(operation.as_str(), "DELETE" | "CHANGE" | "AGE" | "SAVE") {
+                continue;
+            }
+            // the options after the slash (MEMTYPE=, GENNUM=) are not members
+            let body = statement.text[operation.len()..].trim_end_matches(';');
+            let members = body.split('/').next().unwrap_or("");
+            operations.push(DatasetsOperation {
+                line: content.line_at(statement.start),
+                operation,
+                library: library.clone(),
+                members: compact(members),
+            });
+        }
+    }
+    operations
+}
+
+pub fn get_datasets_operations(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_datasets_operations(content)
+        .iter()
+        .map(|o| {
+            Finding::new(file_id, "get_datasets_operations", format!("({}, {}, {}, {})", o.line, o.operation, o.library, o.members))
+                .at(o.line)
+                .value(&o.members)
+                .extra("operation", &o.operation)
+                .extra("library", &o.library)
+        })
+        .collect()
+}
+
+// FcmpFunction is a FUNCTION or SUBROUTINE defined in PROC FCMP
+#[derive(Debug, Clone, PartialEq)]
+pub struct FcmpFunction {
+    pub line: usize,
+    pub kind: String,
+    pub name: String,
+    pub arguments: String,
+    pub returns: String,
+    pub outlib: String,
+}
+
+pub fn find_fcmp_functions(content: &FileContent) -> Vec<FcmpFunction> {
+    let mut functions: Vec<FcmpFunction> = Vec::new();
+    for block in find_proc_blocks(content).iter().filter(|block| block.name == "FCMP") {
+        let outlib = FCMP_OUTLIB.captures(block.statements[0].text).map(|caps| caps[1].to_string()).unwrap_or_default();
+        for statement in &block.statements[1..] {
+            let kind = statement.keyword();
+            if kind != "FUNCTION" && kind != "SUBROUTINE" {
+                continue;
+            }
+            // FUNCTION name(arguments) $ VARARGS KIND='...';
+            let body = statement.text[kind.len()..].trim_end_matches(';').trim();
+            let (name, arguments, after) = match (body.find('('), body.rfind(')')) {
+                (Some(open), Some(close)) if open < close => (&body[..open], &body[open + 1..close], &body[close + 1..]),
+                _ => (body, "", ""),
+            };
+            let returns = match kind.as_str() {
+                "FUNCTION" if after.trim_start().starts_with('$') => "CHARACTER",
+                "FUNCTION" => "NUMERIC",
+                _ => "",
+            };
+            functions.push(FcmpFunction {
+                line: content.line_at(statement.start),
+                kind,
+                name: name.trim().to_string(),
+                arguments: arguments.split(',').map(compact).collect::<Vec<String>>().join(", "),
+                returns: returns.to_string(),
+                outlib: outlib.clone(),
+            });
+        }
+    }
+    functions
+}
+
+pub fn get_fcmp_functions(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_fcmp_functions(content)
+        .iter()
+        .map(|f| {
+            Finding::new(
+                file_id,
+                "get_fcmp_functions",
+                format!("({}, {}, {}, ({}), {}, {})", f.line, f.kind, f.name, f.arguments, f.returns, f.outlib),
+            )
+            .at(f.line)
+            .value(&f.name)
+            .extra("kind", &f.kind)
+            .extra("arguments", &f.arguments)
+            .extra("returns", &f.returns)
+            .extra("outlib", &f.outlib)
+        })
+        .collect()
+}
+
+// proc_name returns the uppercased procedure name of a PROC statement
+pub fn proc_name(statement_text: &str) -> Option<String> {
+    let mut words = statement_text.split(|c: char| c.is_whitespace() || c == ';');
+    let keyword = words.next()?;
+    if !keyword.eq_ignore_ascii_case("PROC") {
+        return None;
+    }
+    words.find(|word| !word.is_empty()).map(|name| name.to_uppercase())
+}
+
+pub fn proc_inventory(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for statement in split_statements(&content.text) {
+        if let Some(name) = proc_name(statement.text) {
+            *counts.entry(name).or_insert(0) += 1;
+        }
+    }
+    counts
+        .iter()
+        .map(|(name, count)| Finding::new(file_id, "proc_inventory", format!("({}, {})", name, count)).value(name).extra("count", count))
+        .collect()
+}
@@ -0,0 +1,79 @@
+use super::patterns::LIBNAME_OPTION;
+use super::{split_statements, split_word, FileContent, Finding};
+
+/* -------------------------
+* SAS/CONNECT parse functions:
+* - find_sas_connect: flags every statement that runs code or moves data between SAS sessions, with
+*   its line, the statement and the remote session (or macro variable) it names, since distributed
+*   processing does not survive a move to a single server or the cloud unchanged:
+*   - SIGNON, SIGNOFF, RSUBMIT and WAITFOR, with the remote session: the first word after the
+*     statement, or the CONNECTREMOTE= (REMOTE=, CREMOTE=, PROCESS=) option
+*   - ENDRSUBMIT, which ends the code run by the last RSUBMIT
+*   - %SYSLPUT and %SYSRPUT, which copy a macro variable to and from the remote session, with the
+*     macro variable name
+*   - PROC UPLOAD and PROC DOWNLOAD, which copy datasets and files between sessions
+* Options such as USER= and PASSWORD= on SIGNON are not reported (see get_credentials).
+* --------------------------- */
+
+// the options naming the remote session of SIGNON, SIGNOFF and RSUBMIT
+const SESSION_OPTIONS: &[&str] = &["CONNECTREMOTE", "REMOTE", "CREMOTE", "PROCESS"];
+
+// RemoteStatement is one SAS/CONNECT statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteStatement {
+    pub line: usize,
+    pub statement: String,
+    pub name: String,
+}
+
+pub fn find_remote_statements(content: &FileContent) -> Vec<RemoteStatement> {
+    let mut statements: Vec<RemoteStatement> = Vec::new();
+    for statement in split_statements(&content.text) {
+        let body = statement.text.strip_suffix(';').unwrap_or(statement.text);
+        let (keyword, rest) = split_word(body);
+        let keyword = keyword.to_uppercase();
+        let rest = rest.trim();
+        let (statement_name, name) = match keyword.as_str() {
+            "SIGNON" | "SIGNOFF" | "RSUBMIT" | "WAITFOR" => (keyword.clone(), session(rest)),
+            "ENDRSUBMIT" => (keyword.clone(), String::new()),
+            "%SYSLPUT" | "%SYSRPUT" => {
+                let name = rest.split(['=', '/']).next().unwrap_or("").trim();
+                (keyword.clone(), name.to_string())
+            }
+            "PROC" => {
+                let (procedure, _) = split_word(rest);
+                let procedure = procedure.to_uppercase();
+                if procedure != "UPLOAD" && procedure != "DOWNLOAD" {
+                    continue;
+                }
+                (format!("PROC {}", procedure), String::new())
+            }
+            _ => continue,
+        };
+        statements.push(RemoteStatement { line: content.line_at(statement.start), statement: statement_name, name });
+    }
+    statements
+}
+
+// session returns the remote session named by the options of a SIGNON, SIGNOFF, RSUBMIT or WAITFOR
+// statement, or an empty name for the current remote session
+fn session(options: &str) -> String {
+    if let Some(caps) = LIBNAME_OPTION
+        .captures_iter(options)
+        .find(|caps| SESSION_OPTIONS.contains(&caps[1].to_uppercase().as_str()))
+    {
+        return caps[2].trim_matches(['\'', '"']).to_string();
+    }
+    let (first, _) = split_word(options);
+    match first.is_empty() || first.contains('=') || first.starts_with(['_', '/']) {
+        true => String::new(),
+        false => first.to_string(),
+    }
+}
+
+pub fn find_sas_connect(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_remote_statements(content)
+        .iter()
+        .map(|s| Finding::new(file_id, "find_sas_connect", format!("({}, {}, {})", s.line, s.statement, s.name)).at(s.line).value(&s.name).extra("statement", &s.statement))
+        .collect()
+}
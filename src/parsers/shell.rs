@@ -0,0 +1,60 @@
+use super::macrovars::call_arguments;
+use super::patterns::SHELL_CALL;
+use super::{quoted_end, split_statements, split_word, FileContent, Finding};
+
+/* -------------------------
+* Shell command parse functions:
+* - find_shell_commands: flags every place a program runs an operating system command, with the
+*   line, the kind of call and the command text, since these need a security review and rarely
+*   work unchanged on another platform:
+*   - X: the X statement (x 'rm /tmp/old.dat';), at the start of a statement. X = 1; and the sum
+*     statement x + 1; are assignments, not commands.
+*   - SYSTASK: SYSTASK COMMAND "..." (SYSTASK LIST and KILL run nothing)
+*   - %SYSEXEC: the %SYSEXEC macro statement, anywhere in the code
+*   - CALL SYSTEM: the CALL SYSTEM routine, with its argument
+* --------------------------- */
+
+// ShellCommand is one operating system command run by the code
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShellCommand {
+    pub line: usize,
+    pub kind: &'static str,
+    pub command: String,
+}
+
+pub fn find_commands(content: &FileContent) -> Vec<ShellCommand> {
+    let mut commands: Vec<ShellCommand> = Vec::new();
+    for statement in split_statements(&content.text) {
+        let body = statement.text.strip_suffix(';').unwrap_or(statement.text);
+        let (keyword, rest) = split_word(body);
+        let rest = rest.trim();
+        let line = content.line_at(statement.start);
+        if keyword.eq_ignore_ascii_case("X") && !rest.starts_with(['=', '(', '+', '[', '{']) {
+            commands.push(ShellCommand { line, kind: "X", command: rest.to_string() });
+        } else if keyword.eq_ignore_ascii_case("SYSTASK") {
+            let (action, command) = split_word(rest);
+            if action.eq_ignore_ascii_case("COMMAND") {
+                let command = command.trim();
+                let end = if command.starts_with(['\'', '"']) { quoted_end(command) } else { command.len() };
+                commands.push(ShellCommand { line, kind: "SYSTASK", command: command[..end].to_string() });
+            }
+        }
+        for m in SHELL_CALL.find_iter(body) {
+            let after = &body[m.end()..];
+            let (kind, command) = if m.as_str().starts_with('%') {
+                ("%SYSEXEC", after.trim().to_string())
+            } else {
+                ("CALL SYSTEM", call_arguments(after).first().cloned().unwrap_or_default())
+            };
+            commands.push(ShellCommand { line: content.line_at(statement.start + m.start()), kind, command });
+        }
+    }
+    commands
+}
+
+pub fn find_shell_commands(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_commands(content)
+        .iter()
+        .map(|c| Finding::new(file_id, "find_shell_commands", format!("({}, {}, {})", c.line, c.kind, c.command)).at(c.line).value(&c.command).extra("kind", c.kind))
+        .collect()
+}
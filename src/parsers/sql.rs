@@ -0,0 +1,201 @@
+use super::patterns::SQL_TOKEN;
+use super::procs::proc_name;
+use super::{split_statements, statements, FileContent, Finding, Statement};
+use std::io;
+
+pub mod statements;
+
+/* -------------------------
+* PROC SQL parse functions:
+* - sql_count: Counts the number of SQL blocks in a file, not counting PROC SQL ... QUIT; in string literals.
+* - get_sql: Extracts SQL blocks from a file, as written (comments included). A block whose QUIT; is missing is closed at the next
+*   PROC or DATA statement, or the end of the file, and flagged as unterminated. stream_sql is the same extraction for --stream runs,
+*   writing each block out as soon as it is found.
+* - get_sql_tables: return each table referenced in a FROM or JOIN clause, as libref and table name,
+*   with its line number. One-level names are reported in the WORK library.
+* - get_sql_statements: splits each block into classified statements (statements.rs).
+* - get_sql_dml and sql_dml_count: the INSERT, UPDATE, DELETE and ALTER statements, and counts of
+*   them beside the SELECTs (statements.rs).
+* - get_sql_creates: the libref and name of each table and view created (statements.rs).
+* - get_sql_into: the macro variables filled by SELECT ... INTO (statements.rs).
+* find_sql_blocks, dataset_statements and table_references are shared with the other SQL-aware parse functions.
+* --------------------------- */
+
+// SqlBlock is a PROC SQL ... QUIT; block, from the PROC SQL statement to the end of QUIT;
+// (terminated), or for a block without QUIT; to the end of its last statement. `text` is the
+// code the block is parsed from (comments blanked out, see FileContent) and `source` is the same
+// range of the file as read, comments included, which get_sql reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqlBlock {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+    pub source: String,
+    pub terminated: bool,
+}
+
+impl SqlBlock {
+    // new makes the block of the file text from offset start to end
+    fn new(content: &FileContent, start: usize, end: usize, terminated: bool) -> SqlBlock {
+        SqlBlock {
+            start_line: content.line_at(start),
+            end_line: content.line_at(end.saturating_sub(1)),
+            text: content.text[start..end].to_string(),
+            // the code keeps the offsets of the file, so the range is the same in both
+            source: content.raw.get(start..end).unwrap_or(&content.text[start..end]).to_string(),
+            terminated,
+        }
+    }
+
+    // line_at returns the file line number of a byte offset in the block text
+    pub fn line_at(&self, offset: usize) -> usize {
+        self.start_line + self.text[..offset].matches('\n').count()
+    }
+
+    // dataset_statements returns the statements of the block that can reference SAS datasets,
+    // leaving out PROC SQL/QUIT and the connection management and pass-through statements
+    pub fn dataset_statements(&self) -> Vec<Statement<'_>> {
+        split_statements(&self.text)
+            .into_iter()
+            .filter(|statement| !matches!(statement.keyword().as_str(), "PROC" | "QUIT" | "CONNECT" | "DISCONNECT" | "EXECUTE"))
+            .collect()
+    }
+}
+
+// SQL words that end a FROM/JOIN table list, so they are not mistaken for a table alias
+const SQL_KEYWORDS: &[&str] = &[
+    "WHERE", "GROUP", "ORDER", "HAVING", "ON", "USING", "LEFT", "RIGHT", "INNER", "OUTER", "FULL", "CROSS",
+    "NATURAL", "JOIN", "UNION", "EXCEPT", "INTERSECT", "AS", "SELECT", "INTO", "SET", "VALUES", "WHEN", "THEN",
+    "ELSE", "END", "AND", "OR", "CORRESPONDING", "CORR", "ALL",
+];
+
+pub fn sql_count(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    let sql_count = sql_blocks(content).count();
+    vec![Finding::new(file_id, "sql_count", sql_count.to_string())]
+}
+
+pub fn find_sql_blocks(content: &FileContent) -> Vec<SqlBlock> {
+    sql_blocks(content).collect()
+}
+
+// sql_blocks returns the PROC SQL blocks of a file one at a time, as they are found, so a caller
+// that handles each block in turn never holds all of them. Blocks are found on statement
+// boundaries: a block runs from a PROC SQL statement to the end of the next QUIT statement,
+// even when they share a line with each other or with other code, and PROC SQL or QUIT in a
+// string literal is not a statement. A block without QUIT; (RUN; used instead, or none at all)
+// ends before the next PROC or DATA statement, or at the end of the file, unterminated.
+pub fn sql_blocks(content: &FileContent) -> impl Iterator<Item = SqlBlock> + '_ {
+    let mut statements = statements(&content.text);
+    // the (start, end) of a PROC SQL statement that closed the block before it
+    let mut reopened: Option<(usize, usize)> = None;
+    std::iter::from_fn(move || {
+        let mut start = reopened.map(|(start, _)| start);
+        let mut last_end = reopened.take().map_or(0, |(_, end)| end);
+        for statement in statements.by_ref() {
+            let is_sql = proc_name(statement.text).as_deref() == Some("SQL");
+            if let Some(block_start) = start {
+                let keyword = statement.keyword();
+                if keyword == "QUIT" {
+                    return Some(SqlBlock::new(content, block_start, statement.end, true));
+                }
+                if keyword == "PROC" || keyword == "DATA" {
+                    if is_sql {
+                        reopened = Some((statement.start, statement.end));
+                    }
+                    return Some(SqlBlock::new(content, block_start, last_end, false));
+                }
+            } else if is_sql {
+                start = Some(statement.start);
+            }
+            last_end = statement.end;
+        }
+        // the end of the file closes a block that is still open
+        let end = content.text.trim_end().len().max(last_end);
+        start.map(|block_start| SqlBlock::new(content, block_start, end, false))
+    })
+}
+
+pub fn get_sql(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    sql_blocks(content).map(|block| sql_finding(file_id, &block)).collect()
+}
+
+// stream_sql is the streaming form of get_sql: each block is handed to emit as soon as it is found
+pub fn stream_sql(file_id: &str, content: &FileContent, emit: &mut dyn FnMut(Finding) -> io::Result<()>) -> io::Result<()> {
+    for block in sql_blocks(content) {
+        emit(sql_finding(file_id, &block))?;
+    }
+    Ok(())
+}
+
+fn sql_finding(file_id: &str, block: &SqlBlock) -> Finding {
+    match block.terminated {
+        true => Finding::new(file_id, "get_sql", format!("({}, {})", block.start_line, block.source)),
+        false => Finding::new(file_id, "get_sql", format!("({}, unterminated, {})", block.start_line, block.source)).extra("unterminated", true),
+    }
+    .lines(block.start_line, block.end_line)
+    .value(&block.source)
+}
+
+pub fn get_sql_tables(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    let mut results: Vec<Finding> = Vec::new();
+    for block in find_sql_blocks(content) {
+        for statement in block.dataset_statements() {
+            for table in table_references(statement.text) {
+                let (libref, name) = split_table_name(&table);
+                let line = block.line_at(statement.start);
+                results.push(Finding::new(file_id, "get_sql_tables", format!("({}, {}, {})", line, libref, name)).at(line).value(name).extra("libref", libref));
+            }
+        }
+    }
+    results
+}
+
+// split_table_name splits libref.table; a one-level name is in the WORK library
+pub fn split_table_name(table: &str) -> (String, String) {
+    match table.split_once('.') {
+        Some((libref, name)) if !libref.is_empty() => (libref.to_string(), name.to_string()),
+        _ => (String::from("WORK"), table.trim_start_matches('.').to_string()),
+    }
+}
+
+// table_references returns the tables named in the FROM and JOIN clauses of a SQL statement,
+// including comma separated FROM lists. Subqueries and CONNECTION TO are skipped.
+pub fn table_references(sql: &str) -> Vec<String> {
+    let tokens: Vec<&str> = SQL_TOKEN.find_iter(sql).map(|m| m.as_str()).collect();
+    let mut tables: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let upper = tokens[i].to_uppercase();
+        if upper != "FROM" && upper != "JOIN" {
+            i += 1;
+            continue;
+        }
+        let is_from = upper == "FROM";
+        i += 1;
+        // read one table, then (for FROM) any further ", table" entries
+        while let Some(table) = tokens.get(i) {
+            if !is_table_name(table) || table.eq_ignore_ascii_case("CONNECTION") {
+                break;
+            }
+            tables.push(table.to_string());
+            i += 1;
+            // skip an optional alias, with or without AS
+            if tokens.get(i).is_some_and(|t| t.eq_ignore_ascii_case("AS")) {
+                i += 1;
+            }
+            if tokens.get(i).is_some_and(|t| is_table_name(t) && !SQL_KEYWORDS.contains(&t.to_uppercase().as_str())) {
+                i += 1;
+            }
+            if is_from && tokens.get(i) == Some(&",") {
+                i += 1;
+                continue;
+            }
+            break;
+        }
+    }
+    tables
+}
+
+fn is_table_name(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '&' || c == '%')
+}
@@ -0,0 +1,468 @@
+use super::{find_sql_blocks, is_table_name, split_table_name, SqlBlock, SQL_KEYWORDS};
+use crate::parsers::lexer::{self, Token, TokenKind};
+use crate::parsers::patterns::SQL_TOKEN;
+use crate::parsers::{split_statements, FileContent, Finding};
+
+/* -------------------------
+* PROC SQL statement parser:
+* - get_sql_statements: splits each PROC SQL block into its statements and classifies them
+*   (CREATE TABLE, CREATE TABLE AS, CREATE VIEW, SELECT, INSERT, UPDATE, DELETE, ...), returning one
+*   row per statement with its line, the table it targets, the tables it reads, and the number of
+*   joins, subqueries and set operators (UNION, EXCEPT, INTERSECT) in it.
+* Each statement is tokenized, its leading clause (CREATE TABLE name, INSERT INTO name, ...) read
+* for the kind and target, and the rest parsed as a query: FROM and JOIN clauses give the tables
+* read, and every parenthesized group is parsed recursively, so the tables of subqueries at any
+* depth are found too. The statements are also the PROC SQL half of get_lineage.
+* - get_sql_dml: return one row per data manipulation statement (INSERT, UPDATE, DELETE and ALTER)
+*   with its line, kind and target table.
+* - sql_dml_count: counts the SELECT, INSERT, UPDATE, DELETE and ALTER statements of a file, as
+*   (select, insert, update, delete, alter), to tell programs that change tables in place from
+*   those that only query them.
+* - get_sql_creates: return the target of each CREATE TABLE, CREATE TABLE AS and CREATE VIEW statement
+*   as line, kind, libref and name (WORK for a one-level name), so the tables a program produces can
+*   be matched against the programs that read them.
+* - get_sql_into: return each macro variable a SELECT ... INTO fills as line, target and separator:
+*   :name, a range :first - :last (or THROUGH/THRU) given as first-last, and the SEPARATED BY
+*   string of a list, if any. This data to macro bridge couples programs in ways that are easy to miss.
+* --------------------------- */
+
+pub const SQL_STATEMENT_COLUMNS: [&str; 7] = ["line", "statement", "target", "sources", "joins", "subqueries", "set_operators"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementKind {
+    // CREATE TABLE name (column definitions) or LIKE table
+    CreateTable,
+    // CREATE TABLE name AS SELECT ...
+    CreateTableAs,
+    CreateView,
+    CreateIndex,
+    Select,
+    Insert,
+    Update,
+    Delete,
+    Drop,
+    Alter,
+    Connect,
+    Disconnect,
+    Execute,
+    // any other statement (RESET, VALIDATE, DESCRIBE, ...), by its first word
+    Other(String),
+}
+
+impl StatementKind {
+    pub fn name(&self) -> &str {
+        match self {
+            StatementKind::CreateTable => "CREATE TABLE",
+            StatementKind::CreateTableAs => "CREATE TABLE AS",
+            StatementKind::CreateView => "CREATE VIEW",
+            StatementKind::CreateIndex => "CREATE INDEX",
+            StatementKind::Select => "SELECT",
+            StatementKind::Insert => "INSERT",
+            StatementKind::Update => "UPDATE",
+            StatementKind::Delete => "DELETE",
+            StatementKind::Drop => "DROP",
+            StatementKind::Alter => "ALTER",
+            StatementKind::Connect => "CONNECT",
+            StatementKind::Disconnect => "DISCONNECT",
+            StatementKind::Execute => "EXECUTE",
+            StatementKind::Other(keyword) => keyword,
+        }
+    }
+
+    // writes says whether the statement writes rows to its target table (for lineage)
+    pub fn writes(&self) -> bool {
+        matches!(
+            self,
+            StatementKind::CreateTable
+                | StatementKind::CreateTableAs
+                | StatementKind::CreateView
+                | StatementKind::Insert
+                | StatementKind::Update
+                | StatementKind::Delete
+        )
+    }
+
+    // is_dml says whether the statement changes the rows or columns of an existing table
+    pub fn is_dml(&self) -> bool {
+        matches!(self, StatementKind::Insert | StatementKind::Update | StatementKind::Delete | StatementKind::Alter)
+    }
+
+    // queries_tables says whether the statement is SQL run by SAS, whose FROM and JOIN clauses
+    // name SAS tables; the connection management and EXECUTE pass-through statements are not
+    pub fn queries_tables(&self) -> bool {
+        !matches!(self, StatementKind::Connect | StatementKind::Disconnect | StatementKind::Execute)
+    }
+}
+
+// SqlStatement is one parsed statement of a PROC SQL block
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqlStatement {
+    pub line: usize,
+    pub kind: StatementKind,
+    // the table created, written, dropped or altered (empty for a query)
+    pub target: String,
+    // the tables read in FROM and JOIN clauses (and CREATE TABLE LIKE), in order, at any depth
+    pub sources: Vec<String>,
+    pub joins: usize,
+    pub subqueries: usize,
+    pub set_operators: usize,
+}
+
+impl SqlStatement {
+    // parse parses the text of one statement, starting on line
+    pub fn parse(line: usize, text: &str) -> SqlStatement {
+        let mut parser = Parser { tokens: SQL_TOKEN.find_iter(text).map(|m| m.as_str()).collect(), pos: 0 };
+        let mut statement = SqlStatement {
+            line,
+            kind: StatementKind::Select,
+            target: String::new(),
+            sources: Vec::new(),
+            joins: 0,
+            subqueries: 0,
+            set_operators: 0,
+        };
+        let keyword = parser.next().unwrap_or("").to_uppercase();
+        statement.kind = match keyword.as_str() {
+            "CREATE" => {
+                parser.eat("UNIQUE");
+                if parser.eat("TABLE") {
+                    statement.target = parser.name();
+                    if parser.eat("LIKE") {
+                        statement.sources.push(parser.name());
+                    }
+                    match parser.contains("SELECT") {
+                        true => StatementKind::CreateTableAs,
+                        false => StatementKind::CreateTable,
+                    }
+                } else if parser.eat("VIEW") {
+                    statement.target = parser.name();
+                    StatementKind::CreateView
+                } else if parser.eat("INDEX") {
+                    parser.name();
+                    parser.eat("ON");
+                    statement.target = parser.name();
+                    StatementKind::CreateIndex
+                } else {
+                    StatementKind::Other(keyword)
+                }
+            }
+            "SELECT" => StatementKind::Select,
+            "INSERT" => {
+                parser.eat("INTO");
+                statement.target = parser.name();
+                StatementKind::Insert
+            }
+            "UPDATE" => {
+                statement.target = parser.name();
+                StatementKind::Update
+            }
+            "DELETE" => {
+                parser.eat("FROM");
+                statement.target = parser.name();
+                StatementKind::Delete
+            }
+            "DROP" | "ALTER" => {
+                if parser.eat("TABLE") || parser.eat("VIEW") || parser.eat("INDEX") {
+                    statement.target = parser.name();
+                }
+                if keyword == "DROP" { StatementKind::Drop } else { StatementKind::Alter }
+            }
+            "CONNECT" => StatementKind::Connect,
+            "DISCONNECT" => StatementKind::Disconnect,
+            "EXECUTE" => StatementKind::Execute,
+            _ => StatementKind::Other(keyword),
+        };
+        if statement.kind.queries_tables() {
+            parser.query(&mut statement);
+        }
+        statement
+    }
+
+    // sources_list is the distinct source tables, space separated
+    pub fn sources_list(&self) -> String {
+        let mut distinct: Vec<&str> = Vec::new();
+        for source in &self.sources {
+            if !distinct.iter().any(|d| d.eq_ignore_ascii_case(source)) {
+                distinct.push(source);
+            }
+        }
+        distinct.join(" ")
+    }
+
+    pub fn record(&self) -> Vec<String> {
+        vec![
+            self.line.to_string(),
+            self.kind.name().to_string(),
+            self.target.clone(),
+            self.sources_list(),
+            self.joins.to_string(),
+            self.subqueries.to_string(),
+            self.set_operators.to_string(),
+        ]
+    }
+}
+
+// Parser walks the tokens of one statement
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.tokens.get(self.pos).copied();
+        self.pos += 1;
+        token
+    }
+
+    fn peek_is(&self, word: &str) -> bool {
+        self.tokens.get(self.pos).is_some_and(|token| token.eq_ignore_ascii_case(word))
+    }
+
+    // eat skips the next token if it is word
+    fn eat(&mut self, word: &str) -> bool {
+        let found = self.peek_is(word);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    // name reads a table (or index) name, or returns an empty name
+    fn name(&mut self) -> String {
+        match self.tokens.get(self.pos) {
+            Some(token) if is_table_name(token) => {
+                self.pos += 1;
+                token.to_string()
+            }
+            _ => String::new(),
+        }
+    }
+
+    // contains says whether word is one of the remaining tokens
+    fn contains(&self, word: &str) -> bool {
+        self.tokens[self.pos.min(self.tokens.len())..].iter().any(|token| token.eq_ignore_ascii_case(word))
+    }
+
+    // query reads tokens up to the closing parenthesis of the current group (or the end of the
+    // statement), collecting the tables, joins and set operators into the statement and parsing
+    // every parenthesized group recursively
+    fn query(&mut self, statement: &mut SqlStatement) {
+        while let Some(token) = self.next() {
+            match token.to_uppercase().as_str() {
+                ")" => return,
+                "(" => {
+                    if self.peek_is("SELECT") {
+                        statement.subqueries += 1;
+                    }
+                    self.query(statement);
+                }
+                "FROM" => self.table_list(statement, true),
+                "JOIN" => {
+                    statement.joins += 1;
+                    self.table_list(statement, false);
+                }
+                "UNION" | "EXCEPT" | "INTERSECT" => statement.set_operators += 1,
+                _ => {}
+            }
+        }
+    }
+
+    // table_list reads the table after FROM or JOIN, with an optional alias, and for FROM any
+    // further comma separated tables (each an implicit join). A subquery is left to query.
+    fn table_list(&mut self, statement: &mut SqlStatement, is_from: bool) {
+        while let Some(table) = self.tokens.get(self.pos) {
+            if !is_table_name(table) || table.eq_ignore_ascii_case("CONNECTION") {
+                return;
+            }
+            statement.sources.push(table.to_string());
+            self.pos += 1;
+            self.eat("AS");
+            if self.tokens.get(self.pos).is_some_and(|t| is_table_name(t) && !SQL_KEYWORDS.contains(&t.to_uppercase().as_str())) {
+                self.pos += 1;
+            }
+            if !(is_from && self.eat(",")) {
+                return;
+            }
+            statement.joins += 1;
+        }
+    }
+}
+
+// block_statements parses the statements of a PROC SQL block, leaving out PROC SQL and QUIT (and
+// RUN, which PROC SQL ignores)
+pub fn block_statements(block: &SqlBlock) -> Vec<SqlStatement> {
+    split_statements(&block.text)
+        .into_iter()
+        .filter(|statement| !matches!(statement.keyword().as_str(), "PROC" | "QUIT" | "RUN"))
+        .map(|statement| SqlStatement::parse(block.line_at(statement.start), statement.text))
+        .collect()
+}
+
+pub fn find_sql_statements(content: &FileContent) -> Vec<SqlStatement> {
+    find_sql_blocks(content).iter().flat_map(block_statements).collect()
+}
+
+pub fn get_sql_statements(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_sql_statements(content)
+        .iter()
+        .map(|s| {
+            Finding::new(
+                file_id,
+                "get_sql_statements",
+                format!(
+                    "({}, {}, {}, ({}), {}, {}, {})",
+                    s.line,
+                    s.kind.name(),
+                    s.target,
+                    s.sources_list(),
+                    s.joins,
+                    s.subqueries,
+                    s.set_operators
+                ),
+            )
+            .at(s.line)
+            .value(&s.target)
+            .extra("statement", s.kind.name())
+            .extra("sources", s.sources_list())
+            .extra("joins", s.joins)
+            .extra("subqueries", s.subqueries)
+            .extra("set_operators", s.set_operators)
+        })
+        .collect()
+}
+
+pub fn get_sql_dml(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_sql_statements(content)
+        .iter()
+        .filter(|s| s.kind.is_dml())
+        .map(|s| Finding::new(file_id, "get_sql_dml", format!("({}, {}, {})", s.line, s.kind.name(), s.target)).at(s.line).value(&s.target).extra("kind", s.kind.name()))
+        .collect()
+}
+
+pub fn get_sql_creates(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_sql_statements(content)
+        .iter()
+        .filter(|s| matches!(s.kind, StatementKind::CreateTable | StatementKind::CreateTableAs | StatementKind::CreateView))
+        .filter(|s| !s.target.is_empty())
+        .map(|s| {
+            let (libref, name) = split_table_name(&s.target);
+            Finding::new(file_id, "get_sql_creates", format!("({}, {}, {}, {})", s.line, s.kind.name(), libref, name))
+                .at(s.line)
+                .value(name)
+                .extra("kind", s.kind.name())
+                .extra("libref", libref)
+        })
+        .collect()
+}
+
+// IntoTarget is one macro variable (or range of them) filled by SELECT ... INTO
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntoTarget {
+    pub line: usize,
+    pub target: String,
+    pub separator: String,
+}
+
+pub fn find_into_targets(content: &FileContent) -> Vec<IntoTarget> {
+    let mut targets: Vec<IntoTarget> = Vec::new();
+    for block in find_sql_blocks(content) {
+        for statement in split_statements(&block.text) {
+            if statement.keyword() != "SELECT" {
+                continue;
+            }
+            let line = block.line_at(statement.start);
+            targets.extend(into_targets(statement.text).into_iter().map(|(target, separator)| IntoTarget { line, target, separator }));
+        }
+    }
+    targets
+}
+
+// into_targets reads the INTO clause of a SELECT statement (at the top level, up to FROM) into
+// (target, separator) pairs
+fn into_targets(text: &str) -> Vec<(String, String)> {
+    let tokens: Vec<Token> = lexer::tokens(text).collect();
+    let mut depth = 0;
+    let Some(into) = tokens.iter().position(|token| {
+        match token.text {
+            "(" => depth += 1,
+            ")" => depth -= 1,
+            _ => {}
+        }
+        depth == 0 && token.is_word("INTO")
+    }) else {
+        return Vec::new();
+    };
+    let mut targets: Vec<(String, String)> = Vec::new();
+    let mut i = into + 1;
+    while i < tokens.len() && !tokens[i].is_word("FROM") && tokens[i].kind != TokenKind::Semicolon {
+        let token = tokens[i];
+        if token.text == ":" {
+            let (name, next) = macro_name(&tokens, i + 1);
+            i = next;
+            targets.push((name, String::new()));
+            continue;
+        }
+        let is_range = token.text == "-" || token.is_word("THROUGH") || token.is_word("THRU");
+        if is_range && tokens.get(i + 1).is_some_and(|t| t.text == ":") {
+            let (name, next) = macro_name(&tokens, i + 2);
+            i = next;
+            if let Some((target, _)) = targets.last_mut() {
+                target.push('-');
+                target.push_str(&name);
+            }
+            continue;
+        }
+        if token.is_word("SEPARATED") && tokens.get(i + 1).is_some_and(|t| t.is_word("BY")) {
+            if let (Some(separator), Some((_, last))) = (tokens.get(i + 2), targets.last_mut()) {
+                *last = separator.text.to_string();
+            }
+            i += 3;
+            continue;
+        }
+        i += 1;
+    }
+    targets
+}
+
+// macro_name joins the name tokens starting at tokens[from] that touch each other (prefix&n.),
+// returning the name and the index of the token after it
+fn macro_name(tokens: &[Token], from: usize) -> (String, usize) {
+    let mut name = String::new();
+    let mut i = from;
+    while let Some(token) = tokens.get(i) {
+        let touches = i == from || tokens[i - 1].end() == token.start;
+        if !touches || !matches!(token.kind, TokenKind::Word | TokenKind::Number | TokenKind::MacroVariable) {
+            break;
+        }
+        name.push_str(token.text);
+        i += 1;
+    }
+    (name, i)
+}
+
+pub fn get_sql_into(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_into_targets(content)
+        .iter()
+        .map(|t| Finding::new(file_id, "get_sql_into", format!("({}, {}, {})", t.line, t.target, t.separator)).at(t.line).value(&t.target).extra("separator", &t.separator))
+        .collect()
+}
+
+pub fn sql_dml_count(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    let kinds = [StatementKind::Select, StatementKind::Insert, StatementKind::Update, StatementKind::Delete, StatementKind::Alter];
+    let mut counts = [0; 5];
+    for statement in find_sql_statements(content) {
+        if let Some(i) = kinds.iter().position(|kind| *kind == statement.kind) {
+            counts[i] += 1;
+        }
+    }
+    let total: usize = counts.iter().sum();
+    let counts: Vec<String> = counts.iter().map(|count| count.to_string()).collect();
+    vec![Finding::new(file_id, "sql_dml_count", format!("({})", counts.join(", ")))
+        .value(total)
+        .extras(&["select", "insert", "update", "delete", "alter"], &counts)]
+}
+
+pub fn sql_statement_rows(content: &FileContent) -> Vec<Vec<String>> {
+    find_sql_statements(content).iter().map(SqlStatement::record).collect()
+}
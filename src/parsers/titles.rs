@@ -0,0 +1,72 @@
+use super::lexer::{self, TokenKind};
+use super::{split_statements, FileContent, Finding};
+
+/* -------------------------
+* TITLE and FOOTNOTE parse functions:
+* - get_titles: returns each TITLE and FOOTNOTE statement (TITLE, TITLE1 to TITLE10, FOOTNOTE, ...)
+*   as line, statement and text. Titles often name the report, its owner or its audience, which
+*   helps when cataloging programs nobody knows anymore. The text is the quoted strings of the
+*   statement joined by spaces, leaving out options such as J=, H= and COLOR=; a title written
+*   without quotes is taken as written. A bare TITLE; clears the titles and is left out.
+* --------------------------- */
+
+// Title is one TITLE or FOOTNOTE statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct Title {
+    pub line: usize,
+    pub statement: String,
+    pub text: String,
+}
+
+pub fn find_titles(content: &FileContent) -> Vec<Title> {
+    let mut titles: Vec<Title> = Vec::new();
+    for statement in split_statements(&content.text) {
+        let keyword = statement.keyword();
+        if !is_title_keyword(&keyword) {
+            continue;
+        }
+        let body = statement.text[keyword.len()..statement.text.len() - 1].trim();
+        // title = 'x'; assigns a DATA step variable named title
+        if body.starts_with('=') {
+            continue;
+        }
+        let strings: Vec<String> = lexer::tokens(body)
+            .filter(|token| token.kind == TokenKind::String)
+            .map(|token| unquote(token.text))
+            .collect();
+        let text = match strings.is_empty() {
+            true => body.split_whitespace().collect::<Vec<&str>>().join(" "),
+            false => strings.join(" "),
+        };
+        if text.is_empty() {
+            continue;
+        }
+        titles.push(Title { line: content.line_at(statement.start), statement: keyword, text });
+    }
+    titles
+}
+
+// is_title_keyword says whether an uppercased keyword is TITLE, FOOTNOTE or one of them numbered 1 to 10
+fn is_title_keyword(keyword: &str) -> bool {
+    let number = keyword.strip_prefix("TITLE").or_else(|| keyword.strip_prefix("FOOTNOTE"));
+    match number {
+        Some("") => true,
+        Some(n) => n.parse::<u8>().is_ok_and(|n| (1..=10).contains(&n)),
+        None => false,
+    }
+}
+
+// unquote returns the text of a string literal, without its quotes (and suffix) and with doubled
+// quotes made single
+fn unquote(literal: &str) -> String {
+    let quote = &literal[..1];
+    let close = literal.rfind(quote).filter(|&i| i > 0).unwrap_or(literal.len());
+    literal[1..close].replace(&quote.repeat(2), quote)
+}
+
+pub fn get_titles(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_titles(content)
+        .iter()
+        .map(|t| Finding::new(file_id, "get_titles", format!("({}, {}, {})", t.line, t.statement, t.text)).at(t.line).value(&t.text).extra("statement", &t.statement))
+        .collect()
+}
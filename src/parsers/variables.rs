@@ -0,0 +1,159 @@
+use super::datastep::find_datasteps;
+use super::lexer::{self, Token, TokenKind};
+use super::{split_statements, FileContent, Finding};
+
+/* -------------------------
+* Variable list parse functions:
+* - get_variable_lists: returns each KEEP=, DROP= and RENAME= dataset option and each KEEP, DROP
+*   and RENAME statement as line, kind (KEEP= for the option, KEEP for the statement), dataset and
+*   variables, so we can see which variables each step carries forward; this is the first step
+*   towards column-level lineage.
+*   A dataset option belongs to the dataset it follows, in any statement (DATA, SET, MERGE, PROC
+*   ... DATA=, PROC SQL FROM); a statement belongs to the output datasets of its DATA step.
+*   The variables are written as in the code, with RENAME pairs as old=new.
+* --------------------------- */
+
+pub const VARIABLE_LIST_COLUMNS: [&str; 4] = ["line", "kind", "dataset", "variables"];
+
+const LIST_KEYWORDS: &[&str] = &["KEEP", "DROP", "RENAME"];
+
+// VariableList is one KEEP, DROP or RENAME list
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableList {
+    pub line: usize,
+    pub kind: String,
+    pub dataset: String,
+    pub variables: String,
+}
+
+pub fn find_variable_lists(content: &FileContent) -> Vec<VariableList> {
+    let mut lists: Vec<VariableList> = Vec::new();
+    for statement in split_statements(&content.text) {
+        for (offset, kind, dataset, variables) in dataset_options(statement.text) {
+            lists.push(VariableList { line: content.line_at(statement.start + offset), kind, dataset, variables });
+        }
+    }
+    for step in find_datasteps(content) {
+        for statement in split_statements(&step.text) {
+            let kind = statement.keyword();
+            if !LIST_KEYWORDS.contains(&kind.as_str()) {
+                continue;
+            }
+            let body = &statement.text[kind.len()..statement.text.len() - 1];
+            // keep = 1; assigns a variable named keep
+            if body.trim_start().starts_with('=') {
+                continue;
+            }
+            lists.push(VariableList {
+                line: step.start_line + step.text[..statement.start].matches('\n').count(),
+                kind,
+                dataset: step.outputs.join(" "),
+                variables: compact(body),
+            });
+        }
+    }
+    lists.sort_by_key(|list| list.line);
+    lists
+}
+
+// dataset_options returns the KEEP=, DROP= and RENAME= options in the parentheses after a dataset
+// name in a statement, as (offset, kind, dataset, variables)
+fn dataset_options(text: &str) -> Vec<(usize, String, String, String)> {
+    let tokens: Vec<Token> = lexer::tokens(text).filter(|token| token.kind != TokenKind::Comment).collect();
+    let mut options = Vec::new();
+    // the dataset name before each open parenthesis (empty when it is not a dataset's)
+    let mut groups: Vec<&str> = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        match token.text {
+            "(" => groups.push(name_before(text, token.start)),
+            ")" => {
+                groups.pop();
+            }
+            _ => {}
+        }
+        let Some(&dataset) = groups.last() else { continue };
+        let keyword = token.text.to_uppercase();
+        if dataset.is_empty() || token.kind != TokenKind::Word || !LIST_KEYWORDS.contains(&keyword.as_str()) {
+            continue;
+        }
+        if tokens.get(i + 1).is_none_or(|next| next.text != "=") {
+            continue;
+        }
+        let (start, end) = option_value(&tokens[i + 2..]);
+        if start < end {
+            options.push((token.start, format!("{}=", keyword), dataset.to_string(), compact(&text[start..end])));
+        }
+    }
+    options
+}
+
+// name_before returns the dataset name (lib.name, &lib..name) just before an open parenthesis
+fn name_before(text: &str, paren: usize) -> &str {
+    let before = text[..paren].trim_end();
+    let start = before
+        .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '&'))
+        .map_or(0, |i| i + 1);
+    let name = &before[start..];
+    // a name must start like one: not a number, and not an option value such as WHERE=(...)
+    match name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '&') {
+        true => name,
+        false => "",
+    }
+}
+
+// option_value returns the byte range of an option value starting at tokens: a parenthesized list,
+// or the words up to the next NAME= option or the closing parenthesis
+fn option_value(tokens: &[Token]) -> (usize, usize) {
+    let Some(first) = tokens.first() else { return (0, 0) };
+    if first.text == "(" {
+        let mut depth = 0;
+        for token in tokens {
+            match token.text {
+                "(" => depth += 1,
+                ")" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return (first.end(), token.start);
+                    }
+                }
+                _ => {}
+            }
+        }
+        return (first.end(), tokens.last().map_or(first.end(), Token::end));
+    }
+    let mut end = first.start;
+    for (i, token) in tokens.iter().enumerate() {
+        let option_starts = tokens.get(i + 1).is_some_and(|next| next.text == "=") && token.kind == TokenKind::Word;
+        if token.text == ")" || token.kind == TokenKind::Semicolon || option_starts {
+            break;
+        }
+        end = token.end();
+    }
+    (first.start, end)
+}
+
+// compact joins the words of a variable list with single spaces, writing RENAME pairs as old=new
+pub(crate) fn compact(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ").replace(" =", "=").replace("= ", "=")
+}
+
+pub fn get_variable_lists(file_id: &str, content: &FileContent) -> Vec<Finding> {
+    find_variable_lists(content)
+        .iter()
+        .map(|v| {
+            Finding::new(file_id, "get_variable_lists", format!("({}, {}, {}, {})", v.line, v.kind, v.dataset, v.variables))
+                .at(v.line)
+                .value(&v.variables)
+                .extra("kind", &v.kind)
+                .extra("dataset", &v.dataset)
+        })
+        .collect()
+}
+
+// variable_list_rows returns the lists as rows for the variable_lists table
+pub fn variable_list_rows(content: &FileContent) -> Vec<Vec<String>> {
+    find_variable_lists(content)
+        .into_iter()
+        .map(|v| vec![v.line.to_string(), v.kind, v.dataset, v.variables])
+        .collect()
+}
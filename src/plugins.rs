@@ -0,0 +1,218 @@
+use crate::parsers::{FileContext, Finding, Parser};
+use std::ffi::{c_char, c_void, CStr};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+/* -------------------------
+* Plugins (--plugins DIR): parse functions shipped as shared libraries, so a team can add its own
+* analyzers without forking the crate. Every shared library in the directory (.so, or .dylib on
+* macOS) is loaded, and each function it exports is registered next to the built-in ones: it is
+* listed by --list-functions, can be named in --functions and --fail-on, and its results go to
+* detail under its name. A plugin function is given the same text as the built-in functions
+* (comments removed unless --include-comments) and hands back one result string per finding.
+*
+* The ABI is plain C, so a plugin can be written in C, Rust (a cdylib with extern "C"
+* functions) or anything else that can export C symbols:
+*     typedef void (*sas_parser_emit)(void *ctx, const char *result, size_t len);
+*     typedef struct {
+*         const char *name;          // the function name, unique across built-ins and plugins
+*         const char *description;   // shown by --list-functions
+*         void (*parse)(const char *text, size_t len, void *ctx, sas_parser_emit emit);
+*     } sas_parser_function;
+*     uint32_t sas_parser_plugin_abi(void);   // returns PLUGIN_ABI (1)
+*     const sas_parser_function *sas_parser_plugin_functions(size_t *count);
+* The text is UTF-8 and not NUL-terminated. parse calls emit(ctx, result, len) once for each
+* finding, with a UTF-8 result (conventionally in the (line, ...) tuple style); the result is
+* copied, so it only has to live for the call. A plugin runs in the process: a crash in a plugin
+* is a crash of the run.
+* --------------------------- */
+
+pub const PLUGIN_ABI: u32 = 1;
+
+type Emit = extern "C" fn(*mut c_void, *const c_char, usize);
+type Parse = extern "C" fn(*const c_char, usize, *mut c_void, Emit);
+
+// RawFunction is the sas_parser_function struct of the ABI
+#[repr(C)]
+struct RawFunction {
+    name: *const c_char,
+    description: *const c_char,
+    parse: Option<Parse>,
+}
+
+// PluginFunction is one parse function exported by a plugin
+pub struct PluginFunction {
+    pub name: String,
+    pub description: String,
+    // the file of the library it came from
+    pub library: String,
+    parse: Parse,
+    // keeps the library loaded for as long as the function can be called
+    _loaded: Rc<Library>,
+}
+
+impl Parser for PluginFunction {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn describe(&self) -> &str {
+        &self.description
+    }
+
+    fn parse(&self, context: &FileContext) -> Vec<Finding> {
+        let text = &context.content.text;
+        let mut results: Vec<String> = Vec::new();
+        (self.parse)(text.as_ptr() as *const c_char, text.len(), &mut results as *mut Vec<String> as *mut c_void, emit);
+        results.into_iter().map(|result| Finding::new(context.file_id, &self.name, result)).collect()
+    }
+}
+
+// emit is the callback plugins hand their results to; ctx is the Vec<String> of PluginFunction::parse
+extern "C" fn emit(ctx: *mut c_void, result: *const c_char, len: usize) {
+    if ctx.is_null() || (result.is_null() && len > 0) {
+        return;
+    }
+    let results = unsafe { &mut *(ctx as *mut Vec<String>) };
+    let bytes = if len == 0 { &[][..] } else { unsafe { std::slice::from_raw_parts(result as *const u8, len) } };
+    results.push(String::from_utf8_lossy(bytes).to_string());
+}
+
+// Library is a loaded shared library, unloaded when dropped
+struct Library {
+    handle: *mut c_void,
+}
+
+#[cfg(unix)]
+impl Library {
+    fn open(path: &str) -> io::Result<Library> {
+        let c_path = std::ffi::CString::new(path).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path holds a NUL byte"))?;
+        let handle = unsafe { libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if handle.is_null() {
+            return Err(io::Error::other(dl_error()));
+        }
+        Ok(Library { handle })
+    }
+
+    fn symbol(&self, name: &CStr) -> io::Result<*mut c_void> {
+        let symbol = unsafe { libc::dlsym(self.handle, name.as_ptr()) };
+        if symbol.is_null() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("no {} symbol", name.to_string_lossy())));
+        }
+        Ok(symbol)
+    }
+}
+
+#[cfg(unix)]
+fn dl_error() -> String {
+    let error = unsafe { libc::dlerror() };
+    if error.is_null() {
+        return String::from("could not load the library");
+    }
+    unsafe { CStr::from_ptr(error) }.to_string_lossy().to_string()
+}
+
+#[cfg(not(unix))]
+impl Library {
+    fn open(_path: &str) -> io::Result<Library> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "plugins are only supported on unix systems"))
+    }
+
+    fn symbol(&self, _name: &CStr) -> io::Result<*mut c_void> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "plugins are only supported on unix systems"))
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        unsafe {
+            libc::dlclose(self.handle);
+        }
+    }
+}
+
+// c_string reads a NUL-terminated string of a plugin, empty when null
+fn c_string(text: *const c_char) -> String {
+    if text.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(text) }.to_string_lossy().to_string()
+}
+
+// PluginSet is the functions of every plugin loaded
+#[derive(Default)]
+pub struct PluginSet {
+    pub functions: Vec<PluginFunction>,
+    // the file, size and modified time of each library, so a cache from other plugins is not reused
+    pub fingerprint: String,
+}
+
+impl PluginSet {
+    // load_dir loads every shared library in a directory, in file name order
+    pub fn load_dir(dir: &str) -> io::Result<PluginSet> {
+        let mut paths: Vec<String> = fs::read_dir(dir)?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && matches!(path.extension().and_then(|e| e.to_str()), Some("so" | "dylib")))
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        paths.sort();
+        let mut set = PluginSet::default();
+        for path in paths {
+            set.load(&path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+        }
+        Ok(set)
+    }
+
+    // load loads one plugin library and registers its functions
+    pub fn load(&mut self, path: &str) -> io::Result<()> {
+        let metadata = fs::metadata(path)?;
+        let library = Rc::new(Library::open(path)?);
+        let abi = library.symbol(c"sas_parser_plugin_abi")?;
+        let abi: extern "C" fn() -> u32 = unsafe { std::mem::transmute::<*mut c_void, extern "C" fn() -> u32>(abi) };
+        if abi() != PLUGIN_ABI {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("plugin ABI {} is not the supported ABI {}", abi(), PLUGIN_ABI)));
+        }
+        let functions = library.symbol(c"sas_parser_plugin_functions")?;
+        let functions: extern "C" fn(*mut usize) -> *const RawFunction =
+            unsafe { std::mem::transmute::<*mut c_void, extern "C" fn(*mut usize) -> *const RawFunction>(functions) };
+        let mut count: usize = 0;
+        let raw = functions(&mut count);
+        let raw: &[RawFunction] = if raw.is_null() || count == 0 { &[] } else { unsafe { std::slice::from_raw_parts(raw, count) } };
+        let file = Path::new(path).file_name().map_or(path.to_string(), |name| name.to_string_lossy().to_string());
+        for function in raw {
+            let name = c_string(function.name);
+            let parse = function.parse.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("{} has no parse function", name)))?;
+            if name.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "a function has no name"));
+            }
+            self.functions.push(PluginFunction {
+                name,
+                description: c_string(function.description),
+                library: file.clone(),
+                parse,
+                _loaded: Rc::clone(&library),
+            });
+        }
+        let modified = metadata.modified().ok().and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok()).map_or(0, |time| time.as_secs());
+        self.fingerprint.push_str(&format!("{}:{}:{};", file, metadata.len(), modified));
+        Ok(())
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.functions.iter().map(|function| function.name.clone()).collect()
+    }
+
+    // retain keeps only the named functions, for --functions
+    pub fn retain(&mut self, names: &[String]) {
+        self.functions.retain(|function| names.contains(&function.name));
+    }
+
+    // into_parsers returns the functions as parsers, to run next to the built-in ones
+    pub fn into_parsers(self) -> Vec<Box<dyn Parser>> {
+        self.functions.into_iter().map(|function| Box::new(function) as Box<dyn Parser>).collect()
+    }
+}
@@ -0,0 +1,79 @@
+use crate::parsers::Finding;
+use std::collections::HashMap;
+
+/* -------------------------
+* CI gate policies (--fail-on get_password>0,sql_count>100). Each rule compares the run total of a
+* parse function with a threshold, and the run exits with status 1 when any rule holds, so the tool
+* can gate a pipeline. The total of a function is the number of its findings, or for a function
+* that returns one number per file (sql_count, line_count, export_count, ...) the sum of those
+* numbers. The operators are >, >=, <, <=, = and !=.
+* --------------------------- */
+
+// Rule is one function, operator and threshold of a policy
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub function: String,
+    pub operator: &'static str,
+    pub threshold: f64,
+}
+
+impl Rule {
+    fn holds(&self, value: f64) -> bool {
+        match self.operator {
+            ">" => value > self.threshold,
+            ">=" => value >= self.threshold,
+            "<" => value < self.threshold,
+            "<=" => value <= self.threshold,
+            "=" => value == self.threshold,
+            _ => value != self.threshold,
+        }
+    }
+}
+
+// the operators, two character ones first so >= is not read as >
+const OPERATORS: [&str; 6] = [">=", "<=", "!=", ">", "<", "="];
+
+#[derive(Debug, Default)]
+pub struct Policy {
+    pub rules: Vec<Rule>,
+    // the run total of each function a rule names
+    totals: HashMap<String, f64>,
+}
+
+impl Policy {
+    // parse reads comma separated rules such as get_password>0,sql_count>=100
+    pub fn parse(text: &str) -> Result<Policy, String> {
+        let mut policy = Policy::default();
+        for rule in text.split(',').map(str::trim).filter(|rule| !rule.is_empty()) {
+            let (at, operator) = OPERATORS
+                .iter()
+                .filter_map(|operator| rule.find(operator).map(|at| (at, *operator)))
+                .min_by_key(|(at, operator)| (*at, usize::MAX - operator.len()))
+                .ok_or_else(|| format!("expected function>number (or >=, <, <=, =, !=), got {}", rule))?;
+            let function = rule[..at].trim().to_string();
+            let threshold = rule[at + operator.len()..].trim();
+            let threshold = threshold.parse::<f64>().map_err(|_| format!("threshold of {} must be a number, got {}", function, threshold))?;
+            policy.totals.insert(function.clone(), 0.0);
+            policy.rules.push(Rule { function, operator, threshold });
+        }
+        Ok(policy)
+    }
+
+    // add counts a finding towards the total of its function
+    pub fn add(&mut self, finding: &Finding) {
+        if let Some(total) = self.totals.get_mut(&finding.func_nm) {
+            *total += finding.result.trim().parse::<f64>().unwrap_or(1.0);
+        }
+    }
+
+    // failures describes each rule that holds for the run totals
+    pub fn failures(&self) -> Vec<String> {
+        self.rules
+            .iter()
+            .filter_map(|rule| {
+                let value = self.totals.get(&rule.function).copied().unwrap_or(0.0);
+                rule.holds(value).then(|| format!("{} = {} ({} {})", rule.function, value, rule.operator, rule.threshold))
+            })
+            .collect()
+    }
+}
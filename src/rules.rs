@@ -0,0 +1,237 @@
+use crate::config::Config;
+use crate::parsers::{split_statements, FileContent, FileContext, Finding, Parser};
+use regex::{Regex, RegexBuilder};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/* -------------------------
+* User rules (--rules FILE): site-specific patterns that can't be hardcoded as parse functions
+* (naming conventions, banned librefs, old server names, ...). Each rule is a named regex that is
+* run alongside the parse functions; its hits go to the detail output under the rule name, as
+* (line, severity, match). Patterns are case-insensitive, like SAS, unless they turn that off
+* with (?-i), and are matched against the code with comments removed (unless --include-comments).
+* The scope of a rule says what its pattern is matched against:
+* - line (the default): each line of the file
+* - block: each DATA or PROC step as a whole, from its DATA/PROC statement to its RUN; or QUIT;,
+*   so a pattern can span the statements of a step; the line is where the match starts
+* The rules file is TOML, one [[rule]] table per rule, or YAML, a list of rules:
+*     [[rule]]                                 - name: old_server
+*     name = "old_server"                        pattern: '\bsasprd0\d\b'
+*     pattern = '\bsasprd0\d\b'                  description: retired server name
+*     description = "retired server name"        severity: high
+*     severity = "high"                          scope: line
+* The severity is low, medium (the default) or high. Names must be unique and must not be the
+* name of a parse function.
+* --------------------------- */
+
+const SEVERITIES: [&str; 3] = ["LOW", "MEDIUM", "HIGH"];
+const KEYS: [&str; 5] = ["name", "pattern", "description", "severity", "scope"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+    Line,
+    Block,
+}
+
+// UserRule is one rule of a rules file
+#[derive(Debug, Clone)]
+pub struct UserRule {
+    pub name: String,
+    pub pattern: Regex,
+    pub description: String,
+    pub severity: &'static str,
+    pub scope: Scope,
+}
+
+impl UserRule {
+    // from_config builds a rule from the keys of one [[rule]] table or YAML list item
+    fn from_config(config: &Config) -> Result<UserRule, String> {
+        let name = config.get_str("name").filter(|name| !name.is_empty()).ok_or("a rule has no name")?;
+        let pattern = config.get_str("pattern").ok_or_else(|| format!("{} has no pattern", name))?;
+        let pattern = RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("{}: bad pattern: {}", name, e))?;
+        let severity = config.get_str("severity").unwrap_or_else(|| String::from("medium")).to_uppercase();
+        let severity = SEVERITIES
+            .iter()
+            .find(|known| **known == severity)
+            .ok_or_else(|| format!("{}: severity must be low, medium or high, got {}", name, severity.to_lowercase()))?;
+        let scope = match config.get_str("scope").unwrap_or_else(|| String::from("line")).to_lowercase().as_str() {
+            "line" => Scope::Line,
+            "block" => Scope::Block,
+            other => return Err(format!("{}: scope must be line or block, got {}", name, other)),
+        };
+        let description = config.get_str("description").unwrap_or_default();
+        if let Some(key) = config.unused_keys().into_iter().find(|key| !KEYS.contains(&key.as_str())) {
+            return Err(format!("{}: unknown key {} (expected {})", name, key, KEYS.join(", ")));
+        }
+        Ok(UserRule { name, pattern, description, severity, scope })
+    }
+
+    // matches returns (line, matched text) for each hit of the rule in a file
+    fn matches(&self, content: &FileContent) -> Vec<(usize, String)> {
+        let mut hits: Vec<(usize, String)> = Vec::new();
+        let ranges = match self.scope {
+            Scope::Line => line_ranges(&content.text),
+            Scope::Block => step_ranges(&content.text),
+        };
+        for (start, end) in ranges {
+            for m in self.pattern.find_iter(&content.text[start..end]) {
+                // a match over several lines is reported on one
+                let matched = m.as_str().split_whitespace().collect::<Vec<&str>>().join(" ");
+                hits.push((content.line_at(start + m.start()), matched));
+            }
+        }
+        hits
+    }
+}
+
+// line_ranges returns the byte range of each line of text, without its line end
+fn line_ranges(text: &str) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let mut start = 0;
+    for line in text.split_inclusive('\n') {
+        ranges.push((start, start + line.trim_end_matches(['\r', '\n']).len()));
+        start += line.len();
+    }
+    ranges
+}
+
+// step_ranges returns the byte range of each DATA and PROC step of text. A step that has no RUN;
+// or QUIT; is closed by the next DATA or PROC statement, or the end of the file.
+fn step_ranges(text: &str) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let mut open: Option<(usize, usize)> = None;
+    for statement in split_statements(text) {
+        let keyword = statement.keyword();
+        if keyword == "DATA" || keyword == "PROC" {
+            ranges.extend(open.take());
+            open = Some((statement.start, statement.end));
+            continue;
+        }
+        if let Some((_, end)) = open.as_mut() {
+            *end = statement.end;
+            if keyword == "RUN" || keyword == "QUIT" {
+                ranges.extend(open.take());
+            }
+        }
+    }
+    ranges.extend(open);
+    ranges
+}
+
+// RuleSet is the rules of a rules file
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet {
+    pub rules: Vec<UserRule>,
+    // the text of the file, so a cache from a run with other rules is not reused
+    pub source: String,
+}
+
+impl RuleSet {
+    // load reads a rules file, choosing the syntax from its extension (.yaml/.yml, otherwise TOML)
+    pub fn load(path: &str) -> io::Result<RuleSet> {
+        let text = fs::read_to_string(path)?;
+        let extension = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+        let parsed = match extension.as_str() {
+            "yaml" | "yml" => RuleSet::parse_yaml(&text),
+            _ => RuleSet::parse_toml(&text),
+        };
+        parsed.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, e)))
+    }
+
+    // parse_toml reads [[rule]] tables; each table body is flat TOML, read by Config
+    pub fn parse_toml(text: &str) -> Result<RuleSet, String> {
+        let mut sections: Vec<(usize, String)> = Vec::new();
+        for (line_number, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                if trimmed.split('#').next().unwrap_or("").trim() != "[[rule]]" {
+                    return Err(format!("line {}: expected [[rule]], got {}", line_number + 1, trimmed));
+                }
+                sections.push((line_number + 1, String::new()));
+                continue;
+            }
+            match sections.last_mut() {
+                Some((_, body)) => {
+                    body.push_str(line);
+                    body.push('\n');
+                }
+                None if trimmed.is_empty() || trimmed.starts_with('#') => {}
+                None => return Err(format!("line {}: expected [[rule]] before the rule keys", line_number + 1)),
+            }
+        }
+        RuleSet::build(text, sections, Config::parse_toml)
+    }
+
+    // parse_yaml reads a list of mappings (at the top level, or under a rules: key); each item,
+    // with its "- " and indentation removed, is flat YAML, read by Config
+    pub fn parse_yaml(text: &str) -> Result<RuleSet, String> {
+        let mut sections: Vec<(usize, String)> = Vec::new();
+        let mut indent: Option<usize> = None;
+        for (line_number, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---" || (sections.is_empty() && trimmed == "rules:") {
+                continue;
+            }
+            let depth = line.len() - line.trim_start().len();
+            if trimmed.starts_with("- ") && indent.is_none_or(|indent| indent == depth) {
+                indent = Some(depth);
+                sections.push((line_number + 1, format!("{}\n", &trimmed[2..])));
+                continue;
+            }
+            match (sections.last_mut(), indent) {
+                (Some((_, body)), Some(indent)) if depth >= indent + 2 => {
+                    body.push_str(&line[indent + 2..]);
+                    body.push('\n');
+                }
+                _ => return Err(format!("line {}: expected a list of rules (- name: ...)", line_number + 1)),
+            }
+        }
+        RuleSet::build(text, sections, Config::parse_yaml)
+    }
+
+    fn build(text: &str, sections: Vec<(usize, String)>, parse: fn(&str) -> Result<Config, String>) -> Result<RuleSet, String> {
+        let mut set = RuleSet { rules: Vec::new(), source: text.to_string() };
+        for (line_number, body) in sections {
+            let rule = parse(&body)
+                .and_then(|config| UserRule::from_config(&config))
+                .map_err(|e| format!("rule at line {}: {}", line_number, e))?;
+            set.rules.push(rule);
+        }
+        Ok(set)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.rules.iter().map(|rule| rule.name.clone()).collect()
+    }
+
+    // into_parsers returns the rules as parsers, to run next to the parse functions
+    pub fn into_parsers(self) -> Vec<Box<dyn Parser>> {
+        self.rules.into_iter().map(|rule| Box::new(rule) as Box<dyn Parser>).collect()
+    }
+}
+
+impl Parser for UserRule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn describe(&self) -> &str {
+        &self.description
+    }
+
+    fn parse(&self, context: &FileContext) -> Vec<Finding> {
+        self.matches(context.content)
+            .into_iter()
+            .map(|(line, matched)| {
+                Finding::new(context.file_id, &self.name, format!("({}, {}, {})", line, self.severity, matched))
+                    .at(line)
+                    .extra("severity", self.severity)
+                    .value(matched)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,556 @@
+use crate::errors::FileError;
+use crate::parsers::duplicates::{self, Shingle};
+use crate::parsers::engines::{self, EngineUse};
+use crate::parsers::includes::{self, Include};
+use crate::parsers::lineage::{self, LineageEdge};
+use crate::parsers::macros::{self, MacroCall, MacroDef};
+use crate::parsers::migration::MigrationMetrics;
+#[cfg(unix)]
+use crate::mmap::Mmap;
+use crate::parsers::{self, FileContent, FileContext, Finding, Parser, TableFunction};
+#[cfg(unix)]
+use crate::parsers::Source;
+use chrono::{DateTime, TimeZone, Utc};
+use indicatif::ProgressBar;
+use std::fs::{self, DirEntry, Metadata};
+use std::io;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+pub mod egp;
+pub mod git;
+pub mod glob;
+pub mod ids;
+pub mod s3;
+pub mod sftp;
+
+use glob::Glob;
+
+// utc_time converts a file time to UTC, times before 1970 included; a time chrono can't
+// represent falls back to the epoch, so one odd file time doesn't stop the run
+fn utc_time(time: SystemTime) -> DateTime<Utc> {
+    let seconds = match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => i64::try_from(after.as_secs()).unwrap_or(i64::MAX),
+        Err(before) => i64::try_from(before.duration().as_secs()).map_or(i64::MIN, |seconds| -seconds),
+    };
+    Utc.timestamp_opt(seconds, 0).single().unwrap_or_default()
+}
+
+// Define structure to hold information about each file
+#[derive(Debug, Clone)]
+pub struct FileInfo {
+    pub uuid: String,
+    pub name: String,
+    pub directory: String,
+    pub create_date: DateTime<Utc>,
+    pub modify_date: DateTime<Utc>,
+    pub size: u64,
+}
+
+impl FileInfo {
+    // path returns the full path of the file, as directory/name
+    pub fn path(&self) -> String {
+        format!("{}/{}", self.directory, self.name)
+    }
+
+    // from_metadata creates the FileInfo of a file with a new uuid
+    pub fn from_metadata(directory: &str, name: &str, metadata: &Metadata) -> io::Result<FileInfo> {
+        let create_date = utc_time(metadata.created()?);
+        let modify_date = utc_time(metadata.modified()?);
+
+        Ok(FileInfo {
+            uuid: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            directory: directory.to_string(),
+            create_date,
+            modify_date,
+            size: metadata.len(),
+        })
+    }
+
+    // from_path creates the FileInfo of a single file given by its path
+    pub fn from_path(file_path: &str) -> io::Result<FileInfo> {
+        let path = Path::new(file_path);
+        let metadata = fs::metadata(path)?;
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let directory = match path.parent().map(|p| p.to_string_lossy().to_string()) {
+            Some(parent) if !parent.is_empty() => parent,
+            _ => String::from("."),
+        };
+        FileInfo::from_metadata(&directory, &name, &metadata)
+    }
+}
+
+// file extensions scanned by default: SAS programs, %INCLUDE members, SQL scripts and Enterprise
+// Guide projects (their embedded programs)
+pub const DEFAULT_EXTENSIONS: &[&str] = &["sas", "inc", "sql", "egp"];
+
+// files of 64 MB or more are memory-mapped by default
+pub const DEFAULT_MMAP_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+// ScanOptions controls how files are read and which files are scanned
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    // analyze code inside comments too (by default comments are blanked out before parsing)
+    pub include_comments: bool,
+    // only scan files matching one of these patterns (all files when empty)
+    pub include: Vec<Glob>,
+    // skip files and directories matching any of these patterns
+    pub exclude: Vec<Glob>,
+    // only scan files with one of these extensions, lowercase and without the dot (all files when empty)
+    pub extensions: Vec<String>,
+    // how many directory levels below the scanned directory to descend (no limit when None);
+    // 0 scans only the files directly in it
+    pub max_depth: Option<usize>,
+    // follow symbolic links to files and directories (they are skipped by default)
+    pub follow_symlinks: bool,
+    // files of at least this many bytes are memory-mapped instead of read onto the heap
+    // (never when None)
+    pub mmap_threshold: Option<u64>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> ScanOptions {
+        ScanOptions {
+            include_comments: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            extensions: DEFAULT_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+            max_depth: None,
+            follow_symlinks: false,
+            mmap_threshold: Some(DEFAULT_MMAP_THRESHOLD),
+        }
+    }
+}
+
+impl ScanOptions {
+    // selects tests a file path, relative to the scanned directory, against the extensions,
+    // --include and --exclude
+    pub fn selects(&self, relative_path: &str) -> bool {
+        let extension = Path::new(relative_path).extension().map(|e| e.to_string_lossy().to_lowercase());
+        let extension_ok = self.extensions.is_empty() || extension.is_some_and(|e| self.extensions.contains(&e));
+        extension_ok
+            && (self.include.is_empty() || self.include.iter().any(|glob| glob.matches(relative_path)))
+            && !self.exclude.iter().any(|glob| glob.matches(relative_path))
+    }
+}
+
+// ScanResult holds everything produced by scanning a directory: the file metadata, the parse
+// findings and the files that could not be read
+#[derive(Debug, Default)]
+pub struct ScanResult {
+    pub files: Vec<FileInfo>,
+    pub findings: Vec<Finding>,
+    pub errors: Vec<FileError>,
+}
+
+/* -------------------------
+* scan_dir is the programmatic entry point: it walks the directory, runs the default
+* parse functions against every file and returns all of the results in memory.
+* scan_dir_with does the same with a chosen set of parse functions and options.
+* --------------------------- */
+pub fn scan_dir(dir: &str) -> io::Result<ScanResult> {
+    scan_dir_with(dir, &parsers::default_functions(), &ScanOptions::default())
+}
+
+pub fn scan_dir_with(dir: &str, parse_functions: &[Box<dyn Parser>], options: &ScanOptions) -> io::Result<ScanResult> {
+    let mut result = ScanResult::default();
+    if is_remote(dir) {
+        result.files = list_remote(dir, options)?;
+    } else {
+        process_dir(dir, &mut result.files, &ProgressBar::hidden(), options, &mut result.errors)?;
+    }
+
+    for file_info in &result.files {
+        match parse_file(file_info, parse_functions, options) {
+            Ok(findings) => result.findings.extend(findings),
+            Err(e) => result.errors.push(e),
+        }
+    }
+    Ok(result)
+}
+
+// parse_file reads a file once and runs each parse function against its content
+pub fn parse_file(file_info: &FileInfo, parse_functions: &[Box<dyn Parser>], options: &ScanOptions) -> Result<Vec<Finding>, FileError> {
+    let content = load_file(&file_info.path(), options)?;
+    Ok(parse_content(file_info, &content, parse_functions))
+}
+
+// file_infos returns the FileInfo of a single file given by its path, or of each program
+// embedded in it when it is an Enterprise Guide project
+pub fn file_infos(file_path: &str) -> io::Result<Vec<FileInfo>> {
+    match egp::is_project(file_path) {
+        true => egp::programs(file_path, &fs::metadata(file_path)?),
+        false => Ok(vec![FileInfo::from_path(file_path)?]),
+    }
+}
+
+// is_remote tests whether an input or file path is a remote location (s3:// or sftp://)
+pub fn is_remote(path: &str) -> bool {
+    s3::is_url(path) || sftp::is_url(path)
+}
+
+// list_remote returns the FileInfo of every selected file under a remote input location
+pub fn list_remote(url: &str, options: &ScanOptions) -> io::Result<Vec<FileInfo>> {
+    match s3::is_url(url) {
+        true => s3::list(url, options),
+        false => sftp::list(url, options),
+    }
+}
+
+// read_bytes reads a file, a program embedded in an Enterprise Guide project, an S3 object or a
+// file on an SFTP server
+pub fn read_bytes(path: &str) -> io::Result<Vec<u8>> {
+    if s3::is_url(path) {
+        return s3::read(path);
+    }
+    if sftp::is_url(path) {
+        return sftp::read(path);
+    }
+    match egp::member_path(path) {
+        Some((project, member)) => egp::read_member(project, member),
+        None => fs::read(path),
+    }
+}
+
+// is_local tests whether a path is a file on a local disk, which can be memory-mapped
+#[cfg(unix)]
+fn is_local(path: &str) -> bool {
+    !is_remote(path) && egp::member_path(path).is_none()
+}
+
+// BINARY_SAMPLE_BYTES is how much of a file is looked at to decide whether it is binary
+const BINARY_SAMPLE_BYTES: usize = 8192;
+
+// load_file reads a file for parsing. Binary files are not parsed: they are reported as
+// FileError::Binary, as is content that is not valid UTF-8 (FileError::NotUtf8).
+// Files over the mmap threshold are memory-mapped rather than read.
+pub fn load_file(path: &str, options: &ScanOptions) -> Result<FileContent, FileError> {
+    #[cfg(unix)]
+    if let (Some(threshold), true) = (options.mmap_threshold, is_local(path)) {
+        let file = fs::File::open(path).map_err(|e| FileError::from_io(path, e))?;
+        let size = file.metadata().map_err(|e| FileError::from_io(path, e))?.len();
+        if size > 0 && size >= threshold {
+            return load_mapped(path, &file, options);
+        }
+    }
+    let bytes = read_bytes(path).map_err(|e| FileError::from_io(path, e))?;
+    if looks_binary(&bytes[..bytes.len().min(BINARY_SAMPLE_BYTES)]) {
+        return Err(FileError::Binary { path: path.to_string() });
+    }
+    let raw = String::from_utf8(bytes).map_err(|_| FileError::NotUtf8 { path: path.to_string() })?;
+    Ok(FileContent::from_text(raw, options.include_comments))
+}
+
+// load_mapped memory-maps a large file and parses it from the map, without a heap copy of the file
+#[cfg(unix)]
+fn load_mapped(path: &str, file: &fs::File, options: &ScanOptions) -> Result<FileContent, FileError> {
+    let map = Mmap::map(file).map_err(|e| FileError::from_io(path, e))?;
+    if looks_binary(&map[..map.len().min(BINARY_SAMPLE_BYTES)]) {
+        return Err(FileError::Binary { path: path.to_string() });
+    }
+    let raw = Source::mapped(map).map_err(|_| FileError::NotUtf8 { path: path.to_string() })?;
+    Ok(FileContent::from_source(raw, options.include_comments))
+}
+
+// looks_binary is a cheap check of the start of a file: text never holds NUL bytes and has few
+// control characters besides tabs, line ends and form feeds. Bytes above 0x7f are not counted,
+// so Latin-1 and UTF-8 text are not mistaken for binary.
+pub fn looks_binary(sample: &[u8]) -> bool {
+    if sample.contains(&0) {
+        return true;
+    }
+    let control = sample.iter().filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c)).count();
+    control * 10 > sample.len()
+}
+
+// parse_content runs each parse function against content that has already been loaded
+pub fn parse_content(file_info: &FileInfo, content: &FileContent, parse_functions: &[Box<dyn Parser>]) -> Vec<Finding> {
+    let context = FileContext { file_id: &file_info.uuid, content };
+    let mut findings: Vec<Finding> = Vec::new();
+    for parse_function in parse_functions {
+        findings.extend(parse_function.parse(&context));
+    }
+    findings
+}
+
+// CorpusPasses says which corpus-level results (built across all files at the end of a run)
+// need per-file input, besides the findings
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CorpusPasses {
+    pub includes: bool,
+    pub macros: bool,
+    pub lineage: bool,
+    pub duplicates: bool,
+    pub engines: bool,
+    pub migration: bool,
+}
+
+// FileResults is everything a run keeps from parsing one file: the findings, the rows for the
+// structured tables (in StructuredTable order, without the uuid) and the corpus pass inputs.
+// It is what the incremental cache stores for each file. function_times holds how long each parse
+// function took on the file, in parse_functions order (not cached; empty for cached results).
+#[derive(Debug, Clone, Default)]
+pub struct FileResults {
+    pub findings: Vec<Finding>,
+    pub table_rows: Vec<Vec<Vec<String>>>,
+    pub includes: Vec<Include>,
+    pub macro_definitions: Vec<MacroDef>,
+    pub macro_calls: Vec<MacroCall>,
+    pub lineage: Vec<LineageEdge>,
+    pub shingles: Vec<Shingle>,
+    pub engines: Vec<EngineUse>,
+    pub migration: MigrationMetrics,
+    pub function_times: Vec<Duration>,
+}
+
+impl FileResults {
+    pub fn parse(
+        file_info: &FileInfo,
+        content: &FileContent,
+        parse_functions: &[Box<dyn Parser>],
+        tables: &[StructuredTable],
+        passes: CorpusPasses,
+    ) -> FileResults {
+        let context = FileContext { file_id: &file_info.uuid, content };
+        let mut findings: Vec<Finding> = Vec::new();
+        let mut function_times: Vec<Duration> = Vec::new();
+        for parse_function in parse_functions {
+            let start = Instant::now();
+            findings.extend(parse_function.parse(&context));
+            function_times.push(start.elapsed());
+        }
+        let mut results = FileResults {
+            findings,
+            function_times,
+            table_rows: tables.iter().map(|table| table.file_rows(content)).collect(),
+            ..FileResults::default()
+        };
+        if passes.includes {
+            results.includes = includes::find_includes(content);
+        }
+        if passes.macros {
+            results.macro_definitions = macros::find_macros(content);
+            results.macro_calls = macros::find_macro_calls(content);
+        }
+        if passes.lineage {
+            results.lineage = lineage::find_lineage(content);
+        }
+        if passes.duplicates {
+            results.shingles = duplicates::find_shingles(content);
+        }
+        if passes.engines {
+            results.engines = engines::find_engines(content);
+        }
+        if passes.migration {
+            results.migration = MigrationMetrics::count(content);
+        }
+        results
+    }
+}
+
+impl FileResults {
+    // stream is FileResults::parse for --stream runs: the findings are handed to emit as they are
+    // found (by the streaming form of a parse function where there is one) rather than kept, so
+    // the returned results have no findings.
+    pub fn stream(
+        file_info: &FileInfo,
+        content: &FileContent,
+        parse_functions: &[Box<dyn Parser>],
+        tables: &[StructuredTable],
+        passes: CorpusPasses,
+        emit: &mut dyn FnMut(Finding) -> io::Result<()>,
+    ) -> io::Result<FileResults> {
+        let context = FileContext { file_id: &file_info.uuid, content };
+        let mut function_times: Vec<Duration> = Vec::new();
+        for parse_function in parse_functions {
+            let start = Instant::now();
+            match parsers::stream_function(parse_function.name()) {
+                Some(stream_function) => stream_function(&file_info.uuid, content, emit)?,
+                None => {
+                    for finding in parse_function.parse(&context) {
+                        emit(finding)?;
+                    }
+                }
+            }
+            function_times.push(start.elapsed());
+        }
+        Ok(FileResults { function_times, ..FileResults::parse(file_info, content, &[], tables, passes) })
+    }
+}
+
+// StructuredTable collects the rows of one parsers::STRUCTURED_TABLES entry across all scanned files
+pub struct StructuredTable {
+    pub name: &'static str,
+    pub headers: Vec<&'static str>,
+    pub rows: Vec<Vec<String>>,
+    function: TableFunction,
+}
+
+impl StructuredTable {
+    // for_functions returns the structured tables that belong to the selected parse functions
+    pub fn for_functions(function_names: &[String]) -> Vec<StructuredTable> {
+        parsers::STRUCTURED_TABLES
+            .iter()
+            .filter(|(function_name, _, _, _)| function_names.iter().any(|name| name == function_name))
+            .map(|(_, name, columns, function)| {
+                let mut headers = vec!["uuid"];
+                headers.extend(columns.iter());
+                StructuredTable { name, headers, rows: Vec::new(), function: *function }
+            })
+            .collect()
+    }
+
+    pub fn add_file(&mut self, file_info: &FileInfo, content: &FileContent) {
+        let rows = self.file_rows(content);
+        self.add_rows(file_info, rows);
+    }
+
+    // file_rows returns the rows of one file, without the uuid
+    pub fn file_rows(&self, content: &FileContent) -> Vec<Vec<String>> {
+        (self.function)(content)
+    }
+
+    pub fn add_rows(&mut self, file_info: &FileInfo, rows: Vec<Vec<String>>) {
+        for row in rows {
+            let mut record = vec![file_info.uuid.clone()];
+            record.extend(row);
+            self.rows.push(record);
+        }
+    }
+}
+
+pub fn compute_total_files(dir: &str, options: &ScanOptions) -> io::Result<u64> {
+    let mut file_count = 0;
+    // the same errors are met again, and recorded, by process_dir
+    let mut errors: Vec<FileError> = Vec::new();
+    walk_files(dir, options, &mut errors, &mut |_, entry, _| {
+        let path = entry.path().to_string_lossy().to_string();
+        file_count += match egp::is_project(&path) {
+            true => egp::count_programs(&path).unwrap_or(0) as u64,
+            false => 1,
+        };
+        Ok(())
+    })?;
+    Ok(file_count)
+}
+
+/* -------------------------
+* This function processes a single file, it does the following:
+* 1. Get the metadata
+* 2. Create and store a FileInfo structure (one per embedded program for an Enterprise Guide project)
+* 3. Update the progress bar
+* A file or subdirectory that cannot be read is added to errors and skipped.
+* --------------------------- */
+pub fn process_dir(dir: &str, file_data: &mut Vec<FileInfo>, pb: &ProgressBar, options: &ScanOptions, errors: &mut Vec<FileError>) -> io::Result<()> {
+    walk_files(dir, options, errors, &mut |directory, entry, metadata| {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if egp::is_project(&file_name) {
+            let programs = egp::programs(&format!("{}/{}", directory, file_name), metadata)?;
+            pb.inc(programs.len() as u64);
+            file_data.extend(programs);
+            return Ok(());
+        }
+        file_data.push(FileInfo::from_metadata(directory, &file_name, metadata)?);
+        pb.inc(1);
+        Ok(())
+    })
+}
+
+// walk_files calls visit(directory, entry, metadata) for every file under root that the scan
+// options select, recursing into subdirectories up to the maximum depth. Only an unreadable
+// root is an error; entries below it that fail (or whose visit fails) are added to errors.
+fn walk_files(
+    root: &str,
+    options: &ScanOptions,
+    errors: &mut Vec<FileError>,
+    visit: &mut dyn FnMut(&str, &DirEntry, &Metadata) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut visited: HashSet<PathBuf> = HashSet::new();
+    if options.follow_symlinks {
+        visited.insert(fs::canonicalize(root)?);
+    }
+    let mut walker = Walker { root, options, visited, errors, visit };
+    for entry in fs::read_dir(root)? {
+        walker.walk_entry(root, 0, entry);
+    }
+    Ok(())
+}
+
+// Walker holds the state of one walk. Symbolic links are skipped unless follow_symlinks is set;
+// then a linked directory is walked only if its real path has not been walked yet, which stops
+// symlink loops and keeps a directory linked from several places from being scanned twice.
+struct Walker<'a> {
+    // the directory the --include and --exclude patterns are relative to
+    root: &'a str,
+    options: &'a ScanOptions,
+    visited: HashSet<PathBuf>,
+    errors: &'a mut Vec<FileError>,
+    visit: &'a mut dyn FnMut(&str, &DirEntry, &Metadata) -> io::Result<()>,
+}
+
+impl Walker<'_> {
+    // walk_entry handles one entry of dir, which is depth levels below root
+    fn walk_entry(&mut self, dir: &str, depth: usize, entry: io::Result<DirEntry>) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => return self.errors.push(FileError::from_io(dir, e)),
+        };
+        let path = entry.path();
+        let path_str = path.to_string_lossy().to_string();
+        let mut metadata = match fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(e) => return self.errors.push(FileError::from_io(&path_str, e)),
+        };
+        if metadata.file_type().is_symlink() {
+            if !self.options.follow_symlinks {
+                return;
+            }
+            // a broken link has nothing to scan
+            metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(_) => return,
+            };
+        }
+        let relative = relative_path(self.root, &path);
+        if metadata.is_file() {
+            if self.options.selects(&relative) {
+                if let Err(e) = (self.visit)(dir, &entry, &metadata) {
+                    self.errors.push(FileError::from_io(&path_str, e));
+                }
+            }
+        } else if metadata.is_dir() && self.options.max_depth.is_none_or(|max_depth| depth < max_depth) {
+            // an excluded directory is not walked at all
+            let dir_path = format!("{}/", relative);
+            if self.options.exclude.iter().any(|glob| glob.matches(&relative) || glob.matches(&dir_path)) {
+                return;
+            }
+            if self.options.follow_symlinks {
+                match fs::canonicalize(&path) {
+                    Ok(real_path) => {
+                        if !self.visited.insert(real_path) {
+                            return;
+                        }
+                    }
+                    Err(e) => return self.errors.push(FileError::from_io(&path_str, e)),
+                }
+            }
+            match fs::read_dir(&path) {
+                Ok(entries) => {
+                    for child in entries {
+                        self.walk_entry(&path_str, depth + 1, child);
+                    }
+                }
+                Err(e) => self.errors.push(FileError::from_io(&path_str, e)),
+            }
+        }
+    }
+}
+
+// relative_path returns a path relative to the scanned directory, with / separators
+fn relative_path(root: &str, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative.to_string_lossy().replace('\\', "/")
+}
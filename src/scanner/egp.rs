@@ -0,0 +1,140 @@
+use super::FileInfo;
+use crate::zip::ZipArchive;
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::io;
+use std::path::Path;
+
+/* -------------------------
+* Enterprise Guide projects. An .egp file is a zip archive holding the project XML (project.xml)
+* and each program embedded in the project as <task id>/code.sas. Every embedded program is
+* scanned as a file of its own, named project.egp/<task id>/code.sas, so the parse functions run
+* against it as against any other program. The project XML gives each task its label (the program
+* name) and its container (the process flow it is in): those are written to the egp_programs
+* table, which ties each program's uuid to its project, flow and program name.
+* Programs linked from an .egp (stored outside it) are not read: scan their directory instead.
+* --------------------------- */
+
+pub const EGP_HEADERS: [&str; 5] = ["uuid", "project", "flow", "program", "member"];
+
+// is_project tests whether a path is an Enterprise Guide project, by its extension
+pub fn is_project(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|extension| extension.eq_ignore_ascii_case("egp"))
+}
+
+// member_path splits the path of an embedded program into the project path and member name
+pub fn member_path(path: &str) -> Option<(&str, &str)> {
+    let lower = path.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(found) = lower[from..].find(".egp/") {
+        let end = from + found + 4;
+        if Path::new(&path[..end]).is_file() {
+            return Some((&path[..end], &path[end + 1..]));
+        }
+        from = end;
+    }
+    None
+}
+
+// read_member returns the content of an embedded program, without the byte order mark
+// Enterprise Guide may start it with
+pub fn read_member(project: &str, member: &str) -> io::Result<Vec<u8>> {
+    let mut bytes = ZipArchive::open(project)?.read(member)?;
+    if bytes.starts_with(&[0xef, 0xbb, 0xbf]) {
+        bytes.drain(..3);
+    }
+    Ok(bytes)
+}
+
+// programs returns the FileInfo of each program embedded in a project: the project path is its
+// directory and the member its name; the dates are the project's and the size the program's
+pub fn programs(project: &str, metadata: &Metadata) -> io::Result<Vec<FileInfo>> {
+    let archive = ZipArchive::open(project)?;
+    let mut files: Vec<FileInfo> = Vec::new();
+    for entry in archive.entries.iter().filter(|entry| entry.name.to_ascii_lowercase().ends_with(".sas")) {
+        let mut file = FileInfo::from_metadata(project, &entry.name, metadata)?;
+        file.size = entry.size;
+        files.push(file);
+    }
+    Ok(files)
+}
+
+// count_programs returns the number of programs embedded in a project
+pub fn count_programs(project: &str) -> io::Result<usize> {
+    let archive = ZipArchive::open(project)?;
+    Ok(archive.entries.iter().filter(|entry| entry.name.to_ascii_lowercase().ends_with(".sas")).count())
+}
+
+// Task is the label and container of one element of the project XML
+struct Task {
+    label: String,
+    container: String,
+}
+
+// project_tasks reads the label and container of each element of a project, by element id
+fn project_tasks(archive: &ZipArchive) -> HashMap<String, Task> {
+    let mut tasks: HashMap<String, Task> = HashMap::new();
+    let Some(entry) = archive.entries.iter().find(|entry| entry.name.eq_ignore_ascii_case("project.xml")) else {
+        return tasks;
+    };
+    let Ok(bytes) = archive.read(&entry.name) else {
+        return tasks;
+    };
+    let xml = decode_xml(&bytes);
+    for element in xml.split("<Element>").skip(1) {
+        let element = element.split("</Element>").next().unwrap_or(element);
+        if let Some(id) = tag(element, "ID") {
+            let task = Task { label: tag(element, "Label").unwrap_or_default(), container: tag(element, "Container").unwrap_or_default() };
+            tasks.insert(id, task);
+        }
+    }
+    tasks
+}
+
+// decode_xml reads the project XML, which Enterprise Guide writes as UTF-16 with a byte order mark
+fn decode_xml(bytes: &[u8]) -> String {
+    let utf16 = |pairs: &[u8], little_endian: bool| {
+        let units: Vec<u16> = pairs
+            .chunks_exact(2)
+            .map(|pair| if little_endian { u16::from_le_bytes([pair[0], pair[1]]) } else { u16::from_be_bytes([pair[0], pair[1]]) })
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    match bytes {
+        [0xff, 0xfe, rest @ ..] => utf16(rest, true),
+        [0xfe, 0xff, rest @ ..] => utf16(rest, false),
+        [0xef, 0xbb, 0xbf, rest @ ..] => String::from_utf8_lossy(rest).to_string(),
+        _ => String::from_utf8_lossy(bytes).to_string(),
+    }
+}
+
+// tag returns the unescaped text of the first <name>...</name> in an XML fragment
+fn tag(xml: &str, name: &str) -> Option<String> {
+    let open = format!("<{}>", name);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&format!("</{}>", name))?;
+    let text = xml[start..end].trim();
+    Some(text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&"))
+}
+
+// program_rows returns the egp_programs table rows of the embedded programs among the scanned
+// files: uuid, project, flow, program name and member
+pub fn program_rows(files: &[FileInfo]) -> Vec<Vec<String>> {
+    let mut projects: HashMap<String, HashMap<String, Task>> = HashMap::new();
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for file in files {
+        let path = file.path();
+        let Some((project, member)) = member_path(&path) else {
+            continue;
+        };
+        let tasks = projects
+            .entry(project.to_string())
+            .or_insert_with(|| ZipArchive::open(project).map(|archive| project_tasks(&archive)).unwrap_or_default());
+        let task = member.split('/').next().and_then(|id| tasks.get(id));
+        let program = task.map_or(member, |task| task.label.as_str());
+        let flow = task.and_then(|task| tasks.get(&task.container)).map_or("", |flow| flow.label.as_str());
+        let project_name = Path::new(project).file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        rows.push(vec![file.uuid.clone(), project_name, flow.to_string(), program.to_string(), member.to_string()]);
+    }
+    rows
+}
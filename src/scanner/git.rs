@@ -0,0 +1,129 @@
+use super::{FileInfo, ScanOptions};
+use crate::errors::FileError;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use uuid::Uuid;
+
+/* -------------------------
+* Git repository input (--git, or a git URL as the input). Only the files git tracks are scanned,
+* so .git internals, build output and untracked scratch files stay out of the results:
+* - a local repository without a ref is scanned in place, as its working copy has the files
+* - a git URL, or a local repository with --git=REF, is cloned into a temporary directory and REF
+*   (a branch, tag or commit; the default branch when not given) checked out there; the clone is
+*   removed when the run ends
+* With --since-ref REF only the tracked files that changed between REF and the working copy (added,
+* modified or renamed; not deleted) are scanned, for quick pull request scoped runs in CI.
+* The commit that was scanned is recorded in the run manifest. The git command line tool does
+* the work, so it must be on the PATH, and clones use its usual credentials.
+* --------------------------- */
+
+// is_url tests whether an input is a git URL rather than a local path
+pub fn is_url(input: &str) -> bool {
+    input.starts_with("git@")
+        || input.starts_with("git://")
+        || input.starts_with("ssh://")
+        || input.starts_with("file://")
+        || ((input.starts_with("https://") || input.starts_with("http://")) && input.trim_end_matches('/').ends_with(".git"))
+}
+
+// git runs git and returns its output
+fn git(args: &[&str]) -> io::Result<Vec<u8>> {
+    let output = Command::new("git")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| io::Error::new(e.kind(), format!("could not run git (required for git input): {}", e)))?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("git {}: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(output.stdout)
+}
+
+// Checkout is the working copy a run scans: the repository itself or a temporary clone
+pub struct Checkout {
+    pub dir: String,
+    pub commit: String,
+    clone: Option<PathBuf>,
+    // with --since-ref, the paths (relative to dir) of the files that changed
+    changed: Option<HashSet<String>>,
+}
+
+impl Checkout {
+    // open finds or makes the working copy of a repository at a ref (HEAD when None)
+    pub fn open(input: &str, reference: Option<&str>) -> io::Result<Checkout> {
+        if !is_url(input) && reference.is_none() {
+            let dir = input.trim_end_matches('/').to_string();
+            let commit = head(&dir)?;
+            return Ok(Checkout { dir, commit, clone: None, changed: None });
+        }
+        let clone = env::temp_dir().join(format!("sas_parser_git_{}", Uuid::new_v4()));
+        let dir = clone.to_string_lossy().to_string();
+        let mut checkout = Checkout { dir: dir.clone(), commit: String::new(), clone: Some(clone), changed: None };
+        git(&["clone", "--quiet", "--no-checkout", input, &dir])?;
+        match reference {
+            Some(reference) => git(&["-C", &dir, "checkout", "--quiet", "--detach", reference])?,
+            None => git(&["-C", &dir, "checkout", "--quiet"])?,
+        };
+        checkout.commit = head(&dir)?;
+        Ok(checkout)
+    }
+
+    // since limits the files to those that changed between a ref and the working copy
+    pub fn since(&mut self, reference: &str) -> io::Result<()> {
+        let diff = git(&["-C", &self.dir, "diff", "--name-only", "-z", "--relative", "--diff-filter=d", reference])?;
+        let changed = String::from_utf8_lossy(&diff).split('\0').filter(|path| !path.is_empty()).map(str::to_string).collect();
+        self.changed = Some(changed);
+        Ok(())
+    }
+
+    // files returns the FileInfo of every tracked file the scan options select; a tracked file
+    // missing from the working copy is added to errors
+    pub fn files(&self, options: &ScanOptions, errors: &mut Vec<FileError>) -> io::Result<Vec<FileInfo>> {
+        let listing = git(&["-C", &self.dir, "ls-files", "-z"])?;
+        let mut files: Vec<FileInfo> = Vec::new();
+        for relative in String::from_utf8_lossy(&listing).split('\0').filter(|path| !path.is_empty()) {
+            let depth = relative.matches('/').count();
+            if !options.selects(relative)
+                || options.max_depth.is_some_and(|max_depth| depth > max_depth)
+                || self.changed.as_ref().is_some_and(|changed| !changed.contains(relative))
+            {
+                continue;
+            }
+            let path = format!("{}/{}", self.dir, relative);
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) if metadata.is_file() => metadata,
+                Ok(_) => continue,
+                Err(e) => {
+                    errors.push(FileError::from_io(&path, e));
+                    continue;
+                }
+            };
+            let (directory, name) = path.rsplit_once('/').unwrap_or((".", &path));
+            match FileInfo::from_metadata(directory, name, &metadata) {
+                Ok(file_info) => files.push(file_info),
+                Err(e) => errors.push(FileError::from_io(&path, e)),
+            }
+        }
+        Ok(files)
+    }
+}
+
+// head returns the commit checked out in a working copy
+fn head(dir: &str) -> io::Result<String> {
+    if !Path::new(dir).exists() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} does not exist", dir)));
+    }
+    Ok(String::from_utf8_lossy(&git(&["-C", dir, "rev-parse", "HEAD"])?).trim().to_string())
+}
+
+impl Drop for Checkout {
+    fn drop(&mut self) {
+        if let Some(clone) = &self.clone {
+            let _ = fs::remove_dir_all(clone);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use regex::Regex;
+use std::str::FromStr;
+
+/* -------------------------
+* Glob patterns for --include and --exclude. A pattern is matched against the path of a file
+* relative to the scanned directory, always with / separators:
+* - ** matches any number of directories, including none when it is followed by a slash
+* - * matches any characters except /, ? one character except /
+* - [abc], [a-z] and [!abc] match one character from (or not from) a set
+* - {sas,sql} matches any one of the comma separated alternatives
+* A pattern without a / is matched against the file name only, so *.sas matches at any depth.
+* Matching ignores case, since SAS code often lives on Windows shares.
+* --------------------------- */
+
+#[derive(Debug, Clone)]
+pub struct Glob {
+    pub pattern: String,
+    regex: Regex,
+    name_only: bool,
+}
+
+impl Glob {
+    // matches tests a path relative to the scanned directory
+    pub fn matches(&self, relative_path: &str) -> bool {
+        let path = relative_path.replace('\\', "/");
+        if self.name_only {
+            let name = path.rsplit('/').next().unwrap_or(&path);
+            return self.regex.is_match(name);
+        }
+        self.regex.is_match(path.trim_start_matches("./"))
+    }
+}
+
+impl FromStr for Glob {
+    type Err = String;
+
+    fn from_str(pattern: &str) -> Result<Glob, String> {
+        let pattern = pattern.replace('\\', "/");
+        let trimmed = pattern.trim_start_matches("./");
+        let mut regex = String::from("(?i)^");
+        let chars: Vec<char> = trimmed.chars().collect();
+        let mut braces = 0;
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    if chars.get(i + 2) == Some(&'/') {
+                        regex.push_str("(?:.*/)?");
+                        i += 3;
+                    } else {
+                        regex.push_str(".*");
+                        i += 2;
+                    }
+                    continue;
+                }
+                '*' => regex.push_str("[^/]*"),
+                '?' => regex.push_str("[^/]"),
+                '[' => {
+                    let close = chars[i + 1..].iter().position(|c| *c == ']').map(|j| i + 1 + j);
+                    let Some(close) = close else {
+                        return Err(format!("Invalid glob pattern (unclosed [): {}", pattern));
+                    };
+                    let set: String = chars[i + 1..close].iter().collect();
+                    let set = match set.strip_prefix('!') {
+                        Some(rest) => format!("^{}", rest),
+                        None => set,
+                    };
+                    regex.push('[');
+                    regex.push_str(&set.replace('\\', "\\\\").replace('[', "\\["));
+                    regex.push(']');
+                    i = close;
+                }
+                '{' => {
+                    braces += 1;
+                    regex.push_str("(?:");
+                }
+                '}' if braces > 0 => {
+                    braces -= 1;
+                    regex.push(')');
+                }
+                ',' if braces > 0 => regex.push('|'),
+                c => regex.push_str(&regex::escape(&c.to_string())),
+            }
+            i += 1;
+        }
+        if braces > 0 {
+            return Err(format!("Invalid glob pattern (unclosed {{): {}", pattern));
+        }
+        regex.push('$');
+        let regex = Regex::new(&regex).map_err(|e| format!("Invalid glob pattern {}: {}", pattern, e))?;
+        Ok(Glob { name_only: !trimmed.contains('/'), pattern, regex })
+    }
+}
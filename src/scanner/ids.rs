@@ -0,0 +1,66 @@
+use super::FileInfo;
+use crate::sha1::{self, Sha1};
+use std::path::Path;
+use uuid::{Builder, Uuid};
+
+/* -------------------------
+* Stable file ids. By default every file gets a random (v4) uuid, so the same file has a
+* different id in every run. With --stable-ids the uuid is a name-based (v5) uuid of the file's
+* path relative to the scanned directory, so the results of different runs can be joined on it.
+* --stable-ids=content also hashes the file content into the name, so a file whose content
+* changed gets a new id.
+* --------------------------- */
+
+// NAMESPACE is the v5 namespace of sas_parser file ids: uuid5(NAMESPACE_URL, "https://github.com/gcatabr1/sas_parser_rust")
+const NAMESPACE: Uuid = Uuid::from_bytes([
+    0xd7, 0xd8, 0x6f, 0xc6, 0x40, 0xad, 0x5c, 0x54, 0x9f, 0x61, 0x58, 0x9d, 0xc1, 0x5c, 0x4e, 0x9b,
+]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdScheme {
+    // a random v4 uuid per file and run
+    Random,
+    // a v5 uuid of the relative path
+    Path,
+    // a v5 uuid of the relative path and the SHA-1 of the content
+    PathAndContent,
+}
+
+// uuid_v5 returns the name-based (SHA-1) uuid of name in namespace
+pub fn uuid_v5(namespace: &Uuid, name: &[u8]) -> Uuid {
+    let mut hasher = Sha1::new();
+    hasher.update(namespace.as_bytes());
+    hasher.update(name);
+    let digest = hasher.digest();
+    Builder::from_sha1_bytes(digest[..16].try_into().unwrap()).into_uuid()
+}
+
+// assign_ids gives each file its uuid under the scheme; root is the directory the ids are
+// relative to. A file that cannot be read for its content hash keeps a path-only id.
+pub fn assign_ids(files: &mut [FileInfo], root: &str, scheme: IdScheme) {
+    if scheme == IdScheme::Random {
+        return;
+    }
+    for file in files.iter_mut() {
+        let path = file.path();
+        let mut name = stable_path(root, &path);
+        if scheme == IdScheme::PathAndContent {
+            if let Ok(bytes) = super::read_bytes(&path) {
+                name.push('\n');
+                name.push_str(&sha1::hex(&sha1::sha1(&bytes)));
+            }
+        }
+        file.uuid = uuid_v5(&NAMESPACE, name.as_bytes()).to_string();
+    }
+}
+
+// stable_path is a file path relative to root with / separators and no leading ./, so the same
+// file is named the same however the scan was started
+pub fn stable_path(root: &str, path: &str) -> String {
+    let relative = Path::new(path).strip_prefix(root).unwrap_or(Path::new(path));
+    let mut relative = relative.to_string_lossy().replace('\\', "/");
+    while let Some(rest) = relative.strip_prefix("./") {
+        relative = rest.to_string();
+    }
+    relative
+}
@@ -0,0 +1,91 @@
+use super::{FileInfo, ScanOptions};
+use chrono::{DateTime, TimeZone, Utc};
+use std::io;
+use std::process::{Command, Stdio};
+use uuid::Uuid;
+
+/* -------------------------
+* S3 input. With -i s3://bucket/prefix the objects under the prefix are scanned straight from S3:
+* each object is streamed into memory when it is parsed, without a local copy. The listing and the
+* reads go through the AWS command line tool (aws s3api list-objects-v2 and aws s3 cp ... -), so
+* aws must be on the PATH; credentials, profile and region come from its usual configuration
+* (AWS_PROFILE, AWS_REGION, ~/.aws/config, instance roles, ...).
+* Each object is a file named after the last part of its key, in the directory s3://bucket/<rest of
+* the key>; the extensions, --include, --exclude and --max-depth apply to the key relative to the
+* prefix. Objects have no creation date: both dates are the last modified date.
+* --------------------------- */
+
+const SCHEME: &str = "s3://";
+
+// is_url tests whether an input or file path is an S3 location
+pub fn is_url(path: &str) -> bool {
+    path.starts_with(SCHEME)
+}
+
+// split_url splits s3://bucket/key into bucket and key
+fn split_url(url: &str) -> (&str, &str) {
+    let rest = url.strip_prefix(SCHEME).unwrap_or(url);
+    rest.split_once('/').unwrap_or((rest, ""))
+}
+
+// aws runs the AWS command line tool and returns its output
+fn aws(args: &[&str]) -> io::Result<Vec<u8>> {
+    let output = Command::new("aws")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| io::Error::new(e.kind(), format!("could not run aws (required for s3:// input): {}", e)))?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("aws {}: {}", args[..2].join(" "), String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(output.stdout)
+}
+
+// list returns the FileInfo of every object under an s3://bucket/prefix that the scan options select
+pub fn list(url: &str, options: &ScanOptions) -> io::Result<Vec<FileInfo>> {
+    let (bucket, prefix) = split_url(url.trim_end_matches('/'));
+    // the prefix is a directory: s3://bucket/code does not scan s3://bucket/code_old/
+    let prefix = if prefix.is_empty() { String::new() } else { format!("{}/", prefix) };
+    let listing = aws(&[
+        "s3api",
+        "list-objects-v2",
+        "--bucket",
+        bucket,
+        "--prefix",
+        &prefix,
+        "--query",
+        "Contents[].[Key,Size,LastModified]",
+        "--output",
+        "text",
+    ])?;
+    let mut files: Vec<FileInfo> = Vec::new();
+    for line in String::from_utf8_lossy(&listing).lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [key, size, modified] = fields[..] else {
+            continue;
+        };
+        let relative = key.strip_prefix(prefix.as_str()).unwrap_or(key);
+        let depth = relative.matches('/').count();
+        if relative.is_empty() || key.ends_with('/') || !options.selects(relative) || options.max_depth.is_some_and(|max_depth| depth > max_depth) {
+            continue;
+        }
+        let (directory, name) = key.rsplit_once('/').unwrap_or(("", key));
+        let modified = DateTime::parse_from_rfc3339(modified)
+            .map(|date| date.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc.timestamp_opt(0, 0).unwrap());
+        files.push(FileInfo {
+            uuid: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            directory: format!("{}{}/{}", SCHEME, bucket, directory).trim_end_matches('/').to_string(),
+            create_date: modified,
+            modify_date: modified,
+            size: size.parse::<u64>().unwrap_or(0),
+        });
+    }
+    Ok(files)
+}
+
+// read streams one object into memory
+pub fn read(url: &str) -> io::Result<Vec<u8>> {
+    aws(&["s3", "cp", url, "-", "--quiet"])
+}
@@ -0,0 +1,109 @@
+use super::{FileInfo, ScanOptions};
+use chrono::{TimeZone, Utc};
+use std::env;
+use std::io;
+use std::process::{Command, Stdio};
+use uuid::Uuid;
+
+/* -------------------------
+* SFTP/SSH input. With -i sftp://[user@]host[:port]/path the directory tree on a remote SAS server
+* is scanned in place: each file is read into memory over SSH when it is parsed, without mirroring
+* the program libraries locally. The ssh command line tool does the work, so logins use its usual
+* configuration (~/.ssh/config, keys, the agent); it runs in batch mode, so a password prompt fails
+* the run instead of hanging it. One connection is opened and shared by every read.
+* The tree is listed with find -printf, which needs GNU find on the server. The extensions,
+* --include, --exclude and --max-depth apply to the paths relative to the remote directory.
+* Remote files have no creation date: both dates are the last modified date.
+* --------------------------- */
+
+const SCHEME: &str = "sftp://";
+
+// Remote is the host (with its user) and port of an sftp:// location, and its path
+struct Remote<'a> {
+    host: &'a str,
+    port: Option<&'a str>,
+    path: &'a str,
+}
+
+// is_url tests whether an input or file path is an SFTP location
+pub fn is_url(path: &str) -> bool {
+    path.starts_with(SCHEME)
+}
+
+fn parse_url(url: &str) -> Remote<'_> {
+    let rest = url.strip_prefix(SCHEME).unwrap_or(url);
+    let (authority, path) = match rest.find('/') {
+        Some(slash) => (&rest[..slash], &rest[slash..]),
+        None => (rest, "/"),
+    };
+    match authority.rsplit_once(':') {
+        Some((host, port)) => Remote { host, port: Some(port), path },
+        None => Remote { host: authority, port: None, path },
+    }
+}
+
+// quote makes a path a single word for the remote shell
+fn quote(path: &str) -> String {
+    format!("'{}'", path.replace('\'', r"'\''"))
+}
+
+// ssh runs a command on the remote host and returns its output
+fn ssh(remote: &Remote, command: &str) -> io::Result<Vec<u8>> {
+    let control_path = env::temp_dir().join("sas_parser_ssh_%C");
+    let mut ssh = Command::new("ssh");
+    ssh.args(["-o", "BatchMode=yes", "-o", "ControlMaster=auto", "-o", "ControlPersist=60"])
+        .arg("-o")
+        .arg(format!("ControlPath={}", control_path.to_string_lossy()));
+    if let Some(port) = remote.port {
+        ssh.args(["-p", port]);
+    }
+    let output = ssh
+        .arg(remote.host)
+        .arg(command)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| io::Error::new(e.kind(), format!("could not run ssh (required for sftp:// input): {}", e)))?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("ssh {}: {}", remote.host, String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(output.stdout)
+}
+
+// list returns the FileInfo of every file under an sftp:// directory that the scan options select
+pub fn list(url: &str, options: &ScanOptions) -> io::Result<Vec<FileInfo>> {
+    let url = url.trim_end_matches('/');
+    let remote = parse_url(url);
+    let depth = options.max_depth.map_or(String::new(), |max_depth| format!(" -maxdepth {}", max_depth + 1));
+    let listing = ssh(&remote, &format!("find {}{} -type f -printf '%P\\t%s\\t%T@\\n'", quote(remote.path), depth))?;
+    let mut files: Vec<FileInfo> = Vec::new();
+    for line in String::from_utf8_lossy(&listing).lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [relative, size, modified] = fields[..] else {
+            continue;
+        };
+        if !options.selects(relative) {
+            continue;
+        }
+        let (directory, name) = match relative.rsplit_once('/') {
+            Some((directory, name)) => (format!("{}/{}", url, directory), name),
+            None => (url.to_string(), relative),
+        };
+        let seconds = modified.split('.').next().and_then(|seconds| seconds.parse::<i64>().ok()).unwrap_or(0);
+        let modified = Utc.timestamp_opt(seconds, 0).single().unwrap_or_default();
+        files.push(FileInfo {
+            uuid: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            directory,
+            create_date: modified,
+            modify_date: modified,
+            size: size.parse::<u64>().unwrap_or(0),
+        });
+    }
+    Ok(files)
+}
+
+// read reads one remote file into memory
+pub fn read(url: &str) -> io::Result<Vec<u8>> {
+    let remote = parse_url(url);
+    ssh(&remote, &format!("cat -- {}", quote(remote.path)))
+}
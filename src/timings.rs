@@ -0,0 +1,95 @@
+use std::fs::File;
+use std::io;
+use std::time::Duration;
+
+/* -------------------------
+* Run timings: how long each parse function took over the whole run, and optionally on each
+* file, so the analyses that dominate the runtime can be found and tuned. Written to
+* timings_<timestamp>.csv with one row per measurement:
+*   scope     "function" (a parse function over all files), "read" (reading the files),
+*             "file" (one file: a parse function on it, or with no function, reading and parsing it)
+*   file      the file path, for the file scope
+*   function  the parse function name
+*   files     how many files the time covers
+*   elapsed_ms
+* Function rows are sorted slowest first. Files reused from the cache are not timed.
+* --------------------------- */
+
+pub const TIMING_HEADERS: [&str; 5] = ["scope", "file", "function", "files", "elapsed_ms"];
+
+// FileTiming is the time spent on one file: reading it, and each parse function
+struct FileTiming {
+    path: String,
+    read: Duration,
+    functions: Vec<Duration>,
+}
+
+pub struct Timings {
+    function_names: Vec<String>,
+    per_file: bool,
+    totals: Vec<Duration>,
+    read: Duration,
+    timed_files: usize,
+    files: Vec<FileTiming>,
+}
+
+impl Timings {
+    // new starts the timings of a run of these parse functions; per_file also keeps each file's times
+    pub fn new(function_names: &[String], per_file: bool) -> Timings {
+        Timings {
+            function_names: function_names.to_vec(),
+            per_file,
+            totals: vec![Duration::ZERO; function_names.len()],
+            read: Duration::ZERO,
+            timed_files: 0,
+            files: Vec::new(),
+        }
+    }
+
+    // add_file records the read time and the parse function times (in function_names order) of a file
+    pub fn add_file(&mut self, path: &str, read: Duration, function_times: &[Duration]) {
+        for (total, elapsed) in self.totals.iter_mut().zip(function_times) {
+            *total += *elapsed;
+        }
+        self.read += read;
+        self.timed_files += 1;
+        if self.per_file {
+            self.files.push(FileTiming { path: path.to_string(), read, functions: function_times.to_vec() });
+        }
+    }
+
+    pub fn records(&self) -> Vec<Vec<String>> {
+        let files = self.timed_files.to_string();
+        let mut functions: Vec<(&String, &Duration)> = self.function_names.iter().zip(&self.totals).collect();
+        functions.sort_by(|a, b| b.1.cmp(a.1));
+
+        let mut records: Vec<Vec<String>> = Vec::new();
+        for (name, elapsed) in functions {
+            records.push(vec![String::from("function"), String::new(), name.clone(), files.clone(), milliseconds(*elapsed)]);
+        }
+        records.push(vec![String::from("read"), String::new(), String::new(), files, milliseconds(self.read)]);
+        for file in &self.files {
+            let total = file.read + file.functions.iter().sum::<Duration>();
+            records.push(vec![String::from("file"), file.path.clone(), String::new(), String::from("1"), milliseconds(total)]);
+            for (name, elapsed) in self.function_names.iter().zip(&file.functions) {
+                records.push(vec![String::from("file"), file.path.clone(), name.clone(), String::from("1"), milliseconds(*elapsed)]);
+            }
+        }
+        records
+    }
+
+    // write saves the timings as <output_dir>/timings_<timestamp>.csv
+    pub fn write(&self, output_dir: &str, timestamp: &str) -> io::Result<()> {
+        let path = format!("{}/timings_{}.csv", output_dir, timestamp);
+        let mut wtr = csv::Writer::from_writer(File::create(path)?);
+        wtr.write_record(TIMING_HEADERS)?;
+        for record in self.records() {
+            wtr.write_record(record)?;
+        }
+        wtr.flush()
+    }
+}
+
+fn milliseconds(elapsed: Duration) -> String {
+    format!("{:.3}", elapsed.as_secs_f64() * 1000.0)
+}
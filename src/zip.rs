@@ -0,0 +1,275 @@
+use std::fs;
+use std::io;
+
+/* -------------------------
+* Read-only zip archives, used to read the programs embedded in Enterprise Guide projects (.egp).
+* The central directory is read to list the members, and a member is read from its local header
+* and inflated (DEFLATE, RFC 1951) or copied when stored. Zip64, encryption and the other
+* compression methods are not supported; CRCs are not checked.
+* --------------------------- */
+
+const END_OF_DIRECTORY: u32 = 0x06054b50;
+const DIRECTORY_ENTRY: u32 = 0x02014b50;
+const LOCAL_HEADER: u32 = 0x04034b50;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+// ZipEntry is one member of an archive, as listed in its central directory
+#[derive(Debug, Clone)]
+pub struct ZipEntry {
+    pub name: String,
+    pub size: u64,
+    method: u16,
+    compressed_size: u64,
+    offset: u64,
+}
+
+pub struct ZipArchive {
+    bytes: Vec<u8>,
+    pub entries: Vec<ZipEntry>,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::other(format!("invalid zip archive: {}", message))
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> io::Result<u16> {
+    bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]])).ok_or_else(|| invalid("truncated"))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> io::Result<u32> {
+    bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).ok_or_else(|| invalid("truncated"))
+}
+
+impl ZipArchive {
+    pub fn open(path: &str) -> io::Result<ZipArchive> {
+        ZipArchive::from_bytes(fs::read(path)?)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> io::Result<ZipArchive> {
+        // the end of central directory record is the last 22 bytes, before an archive comment
+        let earliest = bytes.len().saturating_sub(22 + 0xffff);
+        let end = (earliest..bytes.len().saturating_sub(21))
+            .rev()
+            .find(|&at| u32_at(&bytes, at).ok() == Some(END_OF_DIRECTORY))
+            .ok_or_else(|| invalid("no end of central directory"))?;
+        let count = u16_at(&bytes, end + 10)? as usize;
+        let mut at = u32_at(&bytes, end + 16)? as usize;
+        let mut entries: Vec<ZipEntry> = Vec::with_capacity(count);
+        for _ in 0..count {
+            if u32_at(&bytes, at)? != DIRECTORY_ENTRY {
+                return Err(invalid("bad central directory entry"));
+            }
+            let name_len = u16_at(&bytes, at + 28)? as usize;
+            let extra_len = u16_at(&bytes, at + 30)? as usize;
+            let comment_len = u16_at(&bytes, at + 32)? as usize;
+            let name = bytes.get(at + 46..at + 46 + name_len).ok_or_else(|| invalid("truncated"))?;
+            entries.push(ZipEntry {
+                name: String::from_utf8_lossy(name).replace('\\', "/"),
+                size: u32_at(&bytes, at + 24)? as u64,
+                method: u16_at(&bytes, at + 10)?,
+                compressed_size: u32_at(&bytes, at + 20)? as u64,
+                offset: u32_at(&bytes, at + 42)? as u64,
+            });
+            at += 46 + name_len + extra_len + comment_len;
+        }
+        Ok(ZipArchive { bytes, entries })
+    }
+
+    // read returns the uncompressed content of the member with this name
+    pub fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} is not in the archive", name)))?;
+        let at = entry.offset as usize;
+        if u32_at(&self.bytes, at)? != LOCAL_HEADER {
+            return Err(invalid("bad local header"));
+        }
+        let start = at + 30 + u16_at(&self.bytes, at + 26)? as usize + u16_at(&self.bytes, at + 28)? as usize;
+        let data = self.bytes.get(start..start + entry.compressed_size as usize).ok_or_else(|| invalid("truncated"))?;
+        match entry.method {
+            METHOD_STORED => Ok(data.to_vec()),
+            METHOD_DEFLATED => inflate(data, entry.size as usize),
+            method => Err(io::Error::other(format!("{}: unsupported zip compression method {}", name, method))),
+        }
+    }
+}
+
+// Bits reads a DEFLATE stream, least significant bit first
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buffer: u64,
+    count: u32,
+}
+
+impl Bits<'_> {
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        while self.count < n {
+            let byte = *self.data.get(self.pos).ok_or_else(|| invalid("deflate stream ends early"))?;
+            self.buffer |= (byte as u64) << self.count;
+            self.pos += 1;
+            self.count += 8;
+        }
+        let value = (self.buffer & ((1u64 << n) - 1)) as u32;
+        self.buffer >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    // align drops the bits left in the current byte, for a stored block
+    fn align(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+}
+
+// Huffman is a canonical Huffman code: the number of codes of each length and the symbols in
+// code order
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..16 {
+            code |= bits.bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("bad Huffman code"))
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+// the order the code length code lengths of a dynamic block are given in
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+// inflate decompresses a raw DEFLATE stream
+pub fn inflate(data: &[u8], size_hint: usize) -> io::Result<Vec<u8>> {
+    let mut bits = Bits { data, pos: 0, buffer: 0, count: 0 };
+    let mut out: Vec<u8> = Vec::with_capacity(size_hint);
+    loop {
+        let last = bits.bits(1)? == 1;
+        match bits.bits(2)? {
+            0 => {
+                bits.align();
+                let len = bits.bits(16)? as usize;
+                bits.bits(16)?;
+                let block = data.get(bits.pos..bits.pos + len).ok_or_else(|| invalid("stored block ends early"))?;
+                out.extend_from_slice(block);
+                bits.pos += len;
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                inflate_block(&mut bits, &mut out, &Huffman::new(&lengths), &Huffman::new(&[5u8; 30]))?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(&mut bits)?;
+                inflate_block(&mut bits, &mut out, &literals, &distances)?;
+            }
+            _ => return Err(invalid("bad deflate block type")),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+// dynamic_codes reads the literal/length and distance codes at the start of a dynamic block
+fn dynamic_codes(bits: &mut Bits) -> io::Result<(Huffman, Huffman)> {
+    let literal_count = bits.bits(5)? as usize + 257;
+    let distance_count = bits.bits(5)? as usize + 1;
+    let code_length_count = bits.bits(4)? as usize + 4;
+    let mut code_lengths = [0u8; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[symbol] = bits.bits(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&code_lengths);
+    let mut lengths: Vec<u8> = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let symbol = code_length_code.decode(bits)?;
+        let (length, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => (*lengths.last().ok_or_else(|| invalid("repeat of no code length"))?, 3 + bits.bits(2)?),
+            17 => (0, 3 + bits.bits(3)?),
+            _ => (0, 11 + bits.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(length, repeat as usize));
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err(invalid("too many code lengths"));
+    }
+    Ok((Huffman::new(&lengths[..literal_count]), Huffman::new(&lengths[literal_count..])))
+}
+
+// inflate_block decodes the literals and back references of a compressed block up to its end code
+fn inflate_block(bits: &mut Bits, out: &mut Vec<u8>, literals: &Huffman, distances: &Huffman) -> io::Result<()> {
+    loop {
+        let symbol = literals.decode(bits)? as usize;
+        if symbol < 256 {
+            out.push(symbol as u8);
+            continue;
+        }
+        if symbol == 256 {
+            return Ok(());
+        }
+        let i = symbol - 257;
+        if i >= LENGTH_BASE.len() {
+            return Err(invalid("bad length code"));
+        }
+        let length = LENGTH_BASE[i] as usize + bits.bits(LENGTH_EXTRA[i] as u32)? as usize;
+        let d = distances.decode(bits)? as usize;
+        if d >= DISTANCE_BASE.len() {
+            return Err(invalid("bad distance code"));
+        }
+        let distance = DISTANCE_BASE[d] as usize + bits.bits(DISTANCE_EXTRA[d] as u32)? as usize;
+        if distance > out.len() {
+            return Err(invalid("distance too far back"));
+        }
+        // the copy may overlap what it writes, so it goes byte by byte
+        let start = out.len() - distance;
+        for k in 0..length {
+            out.push(out[start + k]);
+        }
+    }
+}
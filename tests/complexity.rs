@@ -0,0 +1,19 @@
+use sas_parser::parsers::complexity::count_branches;
+use sas_parser::parsers::FileContent;
+
+// score returns the complexity score of the code
+fn score(code: &str) -> usize {
+    count_branches(&FileContent::from_text(code.to_string(), false)).score()
+}
+
+#[test]
+fn proc_sql_queries_are_not_branches() {
+    let code = "proc sql;\n  create table t as select a, case when b > 1 then 'x' else 'y' end as c from s;\n  select * from t;\nquit;\n";
+    assert_eq!(score(code), 1);
+}
+
+#[test]
+fn data_step_branches_and_macro_ifs_count() {
+    let code = "%macro m;\n%if &x = 1 %then %do;\ndata out;\n  set in;\n  if a then b = 1;\n  else b = 2;\n  do i = 1 to 3;\n  end;\n  select (c);\n    when (1) d = 1;\n    otherwise;\n  end;\nrun;\n%end;\n%mend;\n";
+    assert_eq!(score(code), 7);
+}
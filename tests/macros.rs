@@ -0,0 +1,14 @@
+use sas_parser::parsers::macros::find_macro_calls;
+use sas_parser::parsers::FileContent;
+
+// callees returns the names of the macros the code calls, in order
+fn callees(code: &str) -> Vec<String> {
+    let content = FileContent::from_text(code.to_string(), false);
+    find_macro_calls(&content).into_iter().map(|call| call.callee).collect()
+}
+
+#[test]
+fn macro_calls_skip_single_quoted_strings() {
+    let code = "proc format;\n  picture dt other='%Y-%0m-%0d' (datatype=date);\nrun;\n%put \"%report done\";\n";
+    assert_eq!(callees(code), vec!["report"]);
+}
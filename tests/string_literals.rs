@@ -0,0 +1,76 @@
+use sas_parser::parsers::{export_count, export_other_count, null_count, sql, FileContent, Finding};
+
+// count runs a counting parse function on the code and returns its count
+fn count(function: fn(&str, &FileContent) -> Vec<Finding>, code: &str) -> String {
+    let content = FileContent::from_text(code.to_string(), false);
+    function("file", &content)[0].result.clone()
+}
+
+#[test]
+fn sql_count_skips_proc_sql_in_strings() {
+    let code = "data _null_;\n  put \"PROC SQL is great\";\n  put 'proc sql; quit;';\nrun;\n";
+    assert_eq!(count(sql::sql_count, code), "0");
+}
+
+#[test]
+fn sql_count_counts_proc_sql_in_code() {
+    let code = "proc sql;\n  select * from a where name = 'quit;';\nquit;\nproc sql; drop table b; quit;\n";
+    assert_eq!(count(sql::sql_count, code), "2");
+}
+
+#[test]
+fn get_sql_ignores_blocks_started_in_strings() {
+    let code = "data _null_;\n  put 'PROC SQL';\nrun;\nproc sql;\n  select 1 from c;\nquit;\n";
+    let content = FileContent::from_text(code.to_string(), false);
+    let blocks = sql::find_sql_blocks(&content);
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].start_line, 4);
+}
+
+#[test]
+fn export_count_skips_strings() {
+    let code = "proc export data=a outfile='/data/export.csv' dbms=csv; run;\n%put \"EXPORT done\";\n";
+    assert_eq!(count(export_count, code), "1");
+}
+
+#[test]
+fn null_count_skips_strings() {
+    let code = "data _null_;\n  put '_null_ step';\n  x = \"_NULL_\";\nrun;\n";
+    assert_eq!(count(null_count, code), "1");
+}
+
+#[test]
+fn doubled_quotes_stay_inside_the_string() {
+    let code = "data _null_;\n  put 'it''s _null_ export';\n  put \"say \"\"export\"\" _null_\";\nrun;\n";
+    assert_eq!(count(null_count, code), "1");
+    assert_eq!(count(export_count, code), "0");
+}
+
+#[test]
+fn strings_spanning_lines_are_skipped() {
+    let code = "data _null_;\n  put 'first line\n  export _null_';\nrun;\n";
+    assert_eq!(count(null_count, code), "1");
+    assert_eq!(count(export_count, code), "0");
+}
+
+#[test]
+fn literal_suffixes_end_the_string() {
+    let code = "data x;\n  where dt > '01jan2020'd and 'my _null_ var'n = 1;\n  call export;\nrun;\n";
+    assert_eq!(count(null_count, code), "0");
+    assert_eq!(count(export_count, code), "0");
+    assert_eq!(count(export_other_count, code), "1");
+}
+
+#[test]
+fn quotes_in_comments_do_not_open_strings() {
+    let code = "/* don't count this */\ndata _null_;\nrun;\n* it's a comment;\nproc export data=a; run;\n";
+    assert_eq!(count(null_count, code), "1");
+    assert_eq!(count(export_count, code), "1");
+}
+
+#[test]
+fn export_counts_whole_words_by_kind() {
+    let code = "%macro export_data;\nproc export data=a outfile=\"/out/export.csv\" dbms=csv replace; run;\ndata _null_;\n  call export;\nrun;\n";
+    assert_eq!(count(export_count, code), "1");
+    assert_eq!(count(export_other_count, code), "1");
+}